@@ -0,0 +1,176 @@
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, parse_quote, Data, DeriveInput, Expr, Fields, Ident, Path, Type};
+
+struct LayerAttributes {
+    krate: Path,
+    puzzle: Expr,
+    mod_hash: Expr,
+    args: Path,
+    solution: Type,
+}
+
+fn parse_attributes(input: &DeriveInput) -> syn::Result<LayerAttributes> {
+    let mut krate = None;
+    let mut puzzle = None;
+    let mut mod_hash = None;
+    let mut args = None;
+    let mut solution = None;
+
+    for attr in input
+        .attrs
+        .iter()
+        .filter(|attr| attr.path().is_ident("layer"))
+    {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("crate") {
+                krate = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("puzzle") {
+                puzzle = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("mod_hash") {
+                mod_hash = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("args") {
+                args = Some(meta.value()?.parse()?);
+            } else if meta.path.is_ident("solution") {
+                solution = Some(meta.value()?.parse()?);
+            } else {
+                return Err(
+                    meta.error("expected `crate`, `puzzle`, `mod_hash`, `args` or `solution`")
+                );
+            }
+            Ok(())
+        })?;
+    }
+
+    let missing = |name: &str| {
+        syn::Error::new_spanned(
+            &input.ident,
+            format!("missing `{name}` in `#[layer(...)]` attribute"),
+        )
+    };
+
+    let krate: Path = krate.unwrap_or_else(|| parse_quote!(::chia_sdk_driver));
+
+    Ok(LayerAttributes {
+        puzzle: puzzle.ok_or_else(|| missing("puzzle"))?,
+        mod_hash: mod_hash.ok_or_else(|| missing("mod_hash"))?,
+        args: args.ok_or_else(|| missing("args"))?,
+        solution: solution.unwrap_or_else(|| parse_quote!(#krate::__derive::clvmr::NodePtr)),
+        krate,
+    })
+}
+
+fn parse_fields(input: &DeriveInput) -> syn::Result<Vec<Ident>> {
+    let Data::Struct(data) = &input.data else {
+        return Err(syn::Error::new_spanned(
+            &input.ident,
+            "`Layer` can only be derived for structs",
+        ));
+    };
+
+    match &data.fields {
+        Fields::Named(fields) => Ok(fields
+            .named
+            .iter()
+            .filter_map(|field| field.ident.clone())
+            .collect()),
+        Fields::Unit => Ok(Vec::new()),
+        Fields::Unnamed(..) => Err(syn::Error::new_spanned(
+            &input.ident,
+            "`Layer` can only be derived for structs with named fields",
+        )),
+    }
+}
+
+pub(crate) fn impl_layer(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+
+    let (attributes, fields) = match (parse_attributes(&input), parse_fields(&input)) {
+        (Ok(attributes), Ok(fields)) => (attributes, fields),
+        (Err(error), _) | (_, Err(error)) => return error.to_compile_error().into(),
+    };
+
+    let LayerAttributes {
+        krate,
+        puzzle,
+        mod_hash,
+        args,
+        solution,
+    } = attributes;
+
+    let derive = quote!(#krate::__derive);
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+
+    quote! {
+        impl #impl_generics #derive::Layer for #name #ty_generics #where_clause {
+            type Solution = #solution;
+
+            fn parse_puzzle(
+                allocator: &#derive::clvmr::Allocator,
+                puzzle: #derive::Puzzle,
+            ) -> ::std::result::Result<::std::option::Option<Self>, #derive::DriverError> {
+                let ::std::option::Option::Some(puzzle) = puzzle.as_curried() else {
+                    return ::std::result::Result::Ok(::std::option::Option::None);
+                };
+
+                if puzzle.mod_hash != #mod_hash {
+                    return ::std::result::Result::Ok(::std::option::Option::None);
+                }
+
+                let args = <#args as #derive::clvm_traits::FromClvm<#derive::clvmr::Allocator>>::from_clvm(
+                    allocator,
+                    puzzle.args,
+                )?;
+
+                ::std::result::Result::Ok(::std::option::Option::Some(Self {
+                    #( #fields: args.#fields, )*
+                }))
+            }
+
+            fn parse_solution(
+                allocator: &#derive::clvmr::Allocator,
+                solution: #derive::clvmr::NodePtr,
+            ) -> ::std::result::Result<Self::Solution, #derive::DriverError> {
+                ::std::result::Result::Ok(
+                    <#solution as #derive::clvm_traits::FromClvm<#derive::clvmr::Allocator>>::from_clvm(
+                        allocator, solution,
+                    )?,
+                )
+            }
+
+            fn construct_puzzle(
+                &self,
+                ctx: &mut #derive::SpendContext,
+            ) -> ::std::result::Result<#derive::clvmr::NodePtr, #derive::DriverError> {
+                let program = ctx.puzzle(#mod_hash, &#puzzle)?;
+                ctx.alloc(&#derive::clvm_utils::CurriedProgram {
+                    program,
+                    args: #args {
+                        #( #fields: ::std::clone::Clone::clone(&self.#fields), )*
+                    },
+                })
+            }
+
+            fn construct_solution(
+                &self,
+                ctx: &mut #derive::SpendContext,
+                solution: Self::Solution,
+            ) -> ::std::result::Result<#derive::clvmr::NodePtr, #derive::DriverError> {
+                ctx.alloc(&solution)
+            }
+        }
+
+        impl #impl_generics #derive::clvm_utils::ToTreeHash for #name #ty_generics #where_clause {
+            fn tree_hash(&self) -> #derive::clvm_utils::TreeHash {
+                #derive::clvm_utils::ToTreeHash::tree_hash(&#derive::clvm_utils::CurriedProgram {
+                    program: #mod_hash,
+                    args: #args {
+                        #( #fields: ::std::clone::Clone::clone(&self.#fields), )*
+                    },
+                })
+            }
+        }
+    }
+    .into()
+}
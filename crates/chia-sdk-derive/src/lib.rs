@@ -1,10 +1,42 @@
 use proc_macro::TokenStream;
 
 mod impl_conditions;
+mod impl_layer;
 
 use impl_conditions::impl_conditions;
+use impl_layer::impl_layer;
 
 #[proc_macro]
 pub fn conditions(input: TokenStream) -> TokenStream {
     impl_conditions(input)
 }
+
+/// Implements `Layer` and `ToTreeHash` for a layer whose fields are the curried arguments of a puzzle.
+///
+/// ```ignore
+/// #[derive(Debug, Clone, Copy, PartialEq, Eq, Layer)]
+/// #[layer(
+///     puzzle = P2_ONE_OF_MANY_PUZZLE,
+///     mod_hash = P2_ONE_OF_MANY_PUZZLE_HASH,
+///     args = P2OneOfManyArgs,
+///     solution = P2OneOfManySolution<NodePtr, NodePtr>,
+/// )]
+/// pub struct P2OneOfMany {
+///     pub merkle_root: Bytes32,
+/// }
+/// ```
+///
+/// The args struct must have the same field names as the layer. The puzzle is cached in the
+/// `SpendContext` by its mod hash, and the solution defaults to `NodePtr` if it's omitted.
+///
+/// Only leaf layers are supported, since every field is curried in as it is. A layer that wraps an
+/// inner puzzle, such as one with an `inner_puzzle` field that is itself a `Layer`, must implement
+/// `Layer` by hand so that it can construct and parse the inner puzzle.
+///
+/// The generated code refers to `chia_sdk_driver` and its dependencies through `::chia_sdk_driver`,
+/// so the other crates don't need to be dependencies. If it's used under another name, such as through
+/// `chia-wallet-sdk`, set the path with `crate = chia_wallet_sdk`.
+#[proc_macro_derive(Layer, attributes(layer))]
+pub fn layer(input: TokenStream) -> TokenStream {
+    impl_layer(input)
+}
@@ -0,0 +1,77 @@
+use chia_protocol::Bytes32;
+use chia_sdk_driver::{
+    Layer, P2OneOfMany, P2OneOfManyArgs, P2OneOfManySolution, Puzzle, SpendContext,
+    P2_ONE_OF_MANY_PUZZLE, P2_ONE_OF_MANY_PUZZLE_HASH,
+};
+use clvm_utils::ToTreeHash;
+use clvmr::NodePtr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Layer)]
+#[layer(
+    puzzle = P2_ONE_OF_MANY_PUZZLE,
+    mod_hash = P2_ONE_OF_MANY_PUZZLE_HASH,
+    args = P2OneOfManyArgs,
+    solution = P2OneOfManySolution<NodePtr, NodePtr>,
+)]
+struct DefaultPathLayer {
+    merkle_root: Bytes32,
+}
+
+mod renamed {
+    use chia_sdk_driver as sdk;
+
+    use super::{Bytes32, P2OneOfManyArgs, P2_ONE_OF_MANY_PUZZLE, P2_ONE_OF_MANY_PUZZLE_HASH};
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, sdk::Layer)]
+    #[layer(
+        crate = sdk,
+        puzzle = P2_ONE_OF_MANY_PUZZLE,
+        mod_hash = P2_ONE_OF_MANY_PUZZLE_HASH,
+        args = P2OneOfManyArgs,
+    )]
+    pub(super) struct RenamedPathLayer {
+        pub(super) merkle_root: Bytes32,
+    }
+}
+
+#[test]
+fn test_default_path() -> anyhow::Result<()> {
+    let ctx = &mut SpendContext::new();
+
+    let merkle_root = Bytes32::new([42; 32]);
+    let expected = P2OneOfMany { merkle_root }.tree_hash();
+
+    let layer = DefaultPathLayer { merkle_root };
+    let ptr = layer.construct_puzzle(ctx)?;
+    assert_eq!(ctx.tree_hash(ptr), expected);
+    assert_eq!(layer.tree_hash(), expected);
+
+    let puzzle = Puzzle::parse(&ctx.allocator, ptr);
+    assert_eq!(
+        DefaultPathLayer::parse_puzzle(&ctx.allocator, puzzle)?,
+        Some(layer)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_renamed_path() -> anyhow::Result<()> {
+    let ctx = &mut SpendContext::new();
+
+    let merkle_root = Bytes32::new([42; 32]);
+    let expected = P2OneOfMany { merkle_root }.tree_hash();
+
+    let layer = renamed::RenamedPathLayer { merkle_root };
+    let ptr = layer.construct_puzzle(ctx)?;
+    assert_eq!(ctx.tree_hash(ptr), expected);
+    assert_eq!(layer.tree_hash(), expected);
+
+    let puzzle = Puzzle::parse(&ctx.allocator, ptr);
+    assert_eq!(
+        renamed::RenamedPathLayer::parse_puzzle(&ctx.allocator, puzzle)?,
+        Some(layer)
+    );
+
+    Ok(())
+}
@@ -0,0 +1,754 @@
+use std::{any::Any, collections::HashMap, fmt, sync::Arc};
+
+use chia_protocol::{Coin, CoinSpend};
+use chia_puzzles::{
+    cat::{CAT_PUZZLE_HASH, CAT_PUZZLE_HASH_V1},
+    did::DID_INNER_PUZZLE_HASH,
+    nft::{NFT_OWNERSHIP_LAYER_PUZZLE_HASH, NFT_STATE_LAYER_PUZZLE_HASH},
+    offer::{SETTLEMENT_PAYMENTS_PUZZLE_HASH, SETTLEMENT_PAYMENTS_PUZZLE_HASH_V1},
+    singleton::{SingletonSolution, SINGLETON_TOP_LAYER_PUZZLE_HASH},
+    standard::STANDARD_PUZZLE_HASH,
+};
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::TreeHash;
+use clvmr::{Allocator, NodePtr};
+
+use crate::{
+    Cat, Cat1, Cat1Layer, CatLayer, Did, DidInfo, DriverError, HashedPtr, Layer, Nft, NftInfo,
+    NftStateLayer, Puzzle, SingletonLayer,
+};
+
+#[cfg(feature = "chip-0035")]
+use crate::{DataStore, DataStoreInfo, StandardDataStoreLayers, DELEGATION_LAYER_PUZZLE_HASH};
+
+/// The kind of asset a puzzle represents, as identified by an [`AssetRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum AssetKind {
+    /// A coin which isn't wrapped in any asset layer, such as the standard puzzle.
+    Xch,
+    /// A CAT2 coin.
+    Cat,
+    /// A legacy CAT1 coin.
+    Cat1,
+    /// A singleton with the NFT state and ownership layers.
+    Nft,
+    /// A singleton with the DID inner puzzle.
+    Did,
+    /// A singleton with the NFT state layer, but without the ownership layer.
+    #[cfg(feature = "chip-0035")]
+    DataStore,
+    /// A puzzle which has been registered by the user with [`AssetRegistry::register_custom`].
+    Custom(&'static str),
+    /// A puzzle which isn't registered.
+    Unknown,
+}
+
+/// An asset that has been decoded by an [`AssetRegistry`].
+#[derive(Debug, Clone)]
+pub enum DecodedAsset {
+    Xch(Coin),
+    Cat(Cat),
+    Cat1(Cat1),
+    Nft(Nft<HashedPtr>),
+    Did(Did<HashedPtr>),
+    #[cfg(feature = "chip-0035")]
+    DataStore(DataStore),
+    /// A coin wrapped in a user registered puzzle, along with the value returned by its parser.
+    /// When decoding a child, the puzzle is parsed from the parent, since the child's isn't revealed yet.
+    Custom {
+        name: &'static str,
+        coin: Coin,
+        puzzle: Puzzle,
+        value: Arc<dyn Any + Send + Sync>,
+    },
+    /// A coin whose puzzle couldn't be recognized.
+    /// When decoding a child, this is the puzzle of the parent, since the child's isn't revealed yet.
+    Unknown {
+        coin: Coin,
+        puzzle: Puzzle,
+    },
+}
+
+impl DecodedAsset {
+    pub fn coin(&self) -> Coin {
+        match self {
+            Self::Xch(coin) | Self::Custom { coin, .. } | Self::Unknown { coin, .. } => *coin,
+            Self::Cat(cat) => cat.coin,
+            Self::Cat1(cat) => cat.coin,
+            Self::Nft(nft) => nft.coin,
+            Self::Did(did) => did.coin,
+            #[cfg(feature = "chip-0035")]
+            Self::DataStore(datastore) => datastore.coin,
+        }
+    }
+
+    /// The value returned by the parser of a custom asset, if it's of the given type.
+    pub fn custom<T>(&self) -> Option<&T>
+    where
+        T: Any,
+    {
+        match self {
+            Self::Custom { value, .. } => value.downcast_ref(),
+            _ => None,
+        }
+    }
+
+    pub fn kind(&self) -> AssetKind {
+        match self {
+            Self::Xch(..) => AssetKind::Xch,
+            Self::Cat(..) => AssetKind::Cat,
+            Self::Cat1(..) => AssetKind::Cat1,
+            Self::Nft(..) => AssetKind::Nft,
+            Self::Did(..) => AssetKind::Did,
+            #[cfg(feature = "chip-0035")]
+            Self::DataStore(..) => AssetKind::DataStore,
+            Self::Custom { name, .. } => AssetKind::Custom(name),
+            Self::Unknown { .. } => AssetKind::Unknown,
+        }
+    }
+}
+
+/// Identifies and decodes coins by the mod hash of their outer puzzle, so that wallets don't have
+/// to try every primitive's parser in turn. Singletons are identified by the mod hash of the layer
+/// directly inside of the singleton top layer.
+///
+/// The default registry knows about every asset supported by this crate. Additional puzzles can
+/// be registered with [`AssetRegistry::register`], [`AssetRegistry::register_singleton`] and
+/// [`AssetRegistry::register_custom`].
+#[derive(Debug, Clone)]
+pub struct AssetRegistry {
+    outer_puzzles: HashMap<TreeHash, AssetKind>,
+    singleton_puzzles: HashMap<TreeHash, AssetKind>,
+    custom_parsers: HashMap<&'static str, CustomParser>,
+}
+
+type ParseCustom = dyn Fn(&Allocator, Puzzle) -> Result<Option<Arc<dyn Any + Send + Sync>>, DriverError>
+    + Send
+    + Sync;
+
+#[derive(Clone)]
+struct CustomParser(Arc<ParseCustom>);
+
+impl fmt::Debug for CustomParser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CustomParser").finish_non_exhaustive()
+    }
+}
+
+impl Default for AssetRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+
+        registry.register(STANDARD_PUZZLE_HASH, AssetKind::Xch);
+        registry.register(SETTLEMENT_PAYMENTS_PUZZLE_HASH, AssetKind::Xch);
+        registry.register(SETTLEMENT_PAYMENTS_PUZZLE_HASH_V1, AssetKind::Xch);
+        registry.register(CAT_PUZZLE_HASH, AssetKind::Cat);
+        registry.register(CAT_PUZZLE_HASH_V1, AssetKind::Cat1);
+        registry.register_singleton(NFT_STATE_LAYER_PUZZLE_HASH, AssetKind::Nft);
+        registry.register_singleton(DID_INNER_PUZZLE_HASH, AssetKind::Did);
+
+        registry
+    }
+}
+
+impl AssetRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a registry which doesn't recognize any puzzles.
+    pub fn empty() -> Self {
+        Self {
+            outer_puzzles: HashMap::new(),
+            singleton_puzzles: HashMap::new(),
+            custom_parsers: HashMap::new(),
+        }
+    }
+
+    /// Registers the mod hash of an outer puzzle. For puzzles that aren't curried, this is the puzzle hash.
+    /// Registering a custom p2 puzzle as [`AssetKind::Xch`] will decode coins locked by it as XCH.
+    pub fn register(&mut self, mod_hash: TreeHash, kind: AssetKind) {
+        self.outer_puzzles.insert(mod_hash, kind);
+    }
+
+    /// Registers the mod hash of a puzzle directly inside of the singleton top layer.
+    pub fn register_singleton(&mut self, inner_mod_hash: TreeHash, kind: AssetKind) {
+        self.singleton_puzzles.insert(inner_mod_hash, kind);
+    }
+
+    /// Registers a custom asset with the mod hash of its outer puzzle, and the parser used to decode it.
+    ///
+    /// The parser is given the full puzzle of the coin, or of its parent when decoding a child, and
+    /// returns [`None`] if it doesn't match. The [`Layer::parse_puzzle`] method of a layer can be used
+    /// directly as the parser, and the result can be retrieved with [`DecodedAsset::custom`].
+    pub fn register_custom<T, F>(&mut self, mod_hash: TreeHash, name: &'static str, parser: F)
+    where
+        T: Any + Send + Sync,
+        F: Fn(&Allocator, Puzzle) -> Result<Option<T>, DriverError> + Send + Sync + 'static,
+    {
+        self.register(mod_hash, AssetKind::Custom(name));
+        self.custom_parsers.insert(
+            name,
+            CustomParser(Arc::new(move |allocator, puzzle| {
+                let Some(value) = parser(allocator, puzzle)? else {
+                    return Ok(None);
+                };
+                let value: Arc<dyn Any + Send + Sync> = Arc::new(value);
+                Ok(Some(value))
+            })),
+        );
+    }
+
+    fn parse_custom(
+        &self,
+        allocator: &Allocator,
+        name: &'static str,
+        puzzle: Puzzle,
+    ) -> Result<Option<Arc<dyn Any + Send + Sync>>, DriverError> {
+        match self.custom_parsers.get(name) {
+            Some(parser) => (parser.0)(allocator, puzzle),
+            None => Ok(None),
+        }
+    }
+
+    /// Identifies the kind of asset a puzzle represents, without fully parsing it.
+    pub fn identify(
+        &self,
+        allocator: &Allocator,
+        puzzle: Puzzle,
+    ) -> Result<AssetKind, DriverError> {
+        if puzzle.mod_hash() != SINGLETON_TOP_LAYER_PUZZLE_HASH {
+            return Ok(self
+                .outer_puzzles
+                .get(&puzzle.mod_hash())
+                .copied()
+                .unwrap_or(AssetKind::Unknown));
+        }
+
+        let Some(singleton_layer) = SingletonLayer::<Puzzle>::parse_puzzle(allocator, puzzle)?
+        else {
+            return Ok(AssetKind::Unknown);
+        };
+
+        let inner_puzzle = singleton_layer.inner_puzzle;
+
+        let Some(kind) = self
+            .singleton_puzzles
+            .get(&inner_puzzle.mod_hash())
+            .copied()
+        else {
+            return Ok(AssetKind::Unknown);
+        };
+
+        if kind != AssetKind::Nft {
+            return Ok(kind);
+        }
+
+        // The NFT state layer is shared by NFTs and data stores, which are told apart by the ownership layer.
+        let Some(state_layer) =
+            NftStateLayer::<HashedPtr, Puzzle>::parse_puzzle(allocator, inner_puzzle)?
+        else {
+            return Ok(AssetKind::Unknown);
+        };
+
+        if state_layer.inner_puzzle.mod_hash() == NFT_OWNERSHIP_LAYER_PUZZLE_HASH {
+            return Ok(AssetKind::Nft);
+        }
+
+        #[cfg(feature = "chip-0035")]
+        return Ok(AssetKind::DataStore);
+
+        #[cfg(not(feature = "chip-0035"))]
+        Ok(AssetKind::Unknown)
+    }
+
+    /// Decodes the coin being spent from its own puzzle and solution.
+    ///
+    /// Data stores with a delegation layer are decoded as [`DecodedAsset::Unknown`], since their
+    /// delegated puzzles are only revealed in the memos of the parent spend. Use [`AssetRegistry::decode_child`] instead.
+    pub fn decode_spend(
+        &self,
+        allocator: &mut Allocator,
+        coin_spend: &CoinSpend,
+    ) -> Result<DecodedAsset, DriverError> {
+        let coin = coin_spend.coin;
+        let puzzle_ptr = coin_spend.puzzle_reveal.to_clvm(allocator)?;
+        let puzzle = Puzzle::parse(allocator, puzzle_ptr);
+        let solution = coin_spend.solution.to_clvm(allocator)?;
+
+        let decoded = match self.identify(allocator, puzzle)? {
+            AssetKind::Xch => Some(DecodedAsset::Xch(coin)),
+            AssetKind::Cat => CatLayer::<Puzzle>::parse_puzzle(allocator, puzzle)?
+                .map(|layer| {
+                    CatLayer::<Puzzle>::parse_solution(allocator, solution).map(|solution| {
+                        DecodedAsset::Cat(Cat::new(
+                            coin,
+                            solution.lineage_proof,
+                            layer.asset_id,
+                            layer.inner_puzzle.curried_puzzle_hash().into(),
+                        ))
+                    })
+                })
+                .transpose()?,
+            AssetKind::Cat1 => Cat1Layer::<Puzzle>::parse_puzzle(allocator, puzzle)?
+                .map(|layer| {
+                    Cat1Layer::<Puzzle>::parse_solution(allocator, solution).map(|solution| {
+                        DecodedAsset::Cat1(Cat1::new(
+                            coin,
+                            solution.lineage_proof,
+                            layer.asset_id,
+                            layer.inner_puzzle.curried_puzzle_hash().into(),
+                        ))
+                    })
+                })
+                .transpose()?,
+            AssetKind::Nft => match NftInfo::<HashedPtr>::parse(allocator, puzzle)? {
+                Some((info, _p2_puzzle)) => {
+                    let solution = SingletonSolution::<NodePtr>::from_clvm(allocator, solution)?;
+                    Some(DecodedAsset::Nft(Nft::new(
+                        coin,
+                        solution.lineage_proof,
+                        info,
+                    )))
+                }
+                None => None,
+            },
+            AssetKind::Did => match DidInfo::<HashedPtr>::parse(allocator, puzzle)? {
+                Some((info, _p2_puzzle)) => {
+                    let solution = SingletonSolution::<NodePtr>::from_clvm(allocator, solution)?;
+                    Some(DecodedAsset::Did(Did::new(
+                        coin,
+                        solution.lineage_proof,
+                        info,
+                    )))
+                }
+                None => None,
+            },
+            #[cfg(feature = "chip-0035")]
+            AssetKind::DataStore => {
+                match StandardDataStoreLayers::<_, Puzzle>::parse_puzzle(allocator, puzzle)? {
+                    Some(layers)
+                        if layers.inner_puzzle.inner_puzzle.mod_hash()
+                            != DELEGATION_LAYER_PUZZLE_HASH =>
+                    {
+                        let solution =
+                            SingletonSolution::<NodePtr>::from_clvm(allocator, solution)?;
+                        Some(DecodedAsset::DataStore(DataStore::new(
+                            coin,
+                            solution.lineage_proof,
+                            DataStoreInfo::from_layers_without_delegation_layer(layers),
+                        )))
+                    }
+                    _ => None,
+                }
+            }
+            AssetKind::Custom(name) => {
+                self.parse_custom(allocator, name, puzzle)?
+                    .map(|value| DecodedAsset::Custom {
+                        name,
+                        coin,
+                        puzzle,
+                        value,
+                    })
+            }
+            AssetKind::Unknown => None,
+        };
+
+        Ok(decoded.unwrap_or(DecodedAsset::Unknown { coin, puzzle }))
+    }
+
+    /// Decodes a coin created by the given parent spend.
+    ///
+    /// Children of coins that aren't wrapped in an asset layer (and the even outputs of singletons)
+    /// are decoded as XCH, since their own puzzle isn't revealed until they are spent.
+    /// Data stores are parsed without the delegated puzzles of the parent, so if the spend
+    /// doesn't restate them they will be left empty. Use [`DataStore::from_spend`] directly
+    /// if you keep track of them.
+    pub fn decode_child(
+        &self,
+        allocator: &mut Allocator,
+        parent_spend: &CoinSpend,
+        coin: Coin,
+    ) -> Result<DecodedAsset, DriverError> {
+        let mut decoded = self.decode_children(allocator, parent_spend, &[coin])?;
+        Ok(decoded.remove(0))
+    }
+
+    /// Decodes several coins created by the same parent spend, in the given order.
+    ///
+    /// This is the same as calling [`AssetRegistry::decode_child`] for each coin,
+    /// except that the parent spend is only parsed once.
+    pub fn decode_children(
+        &self,
+        allocator: &mut Allocator,
+        parent_spend: &CoinSpend,
+        coins: &[Coin],
+    ) -> Result<Vec<DecodedAsset>, DriverError> {
+        let parent_coin = parent_spend.coin;
+        let parent_coin_id = parent_coin.coin_id();
+
+        if coins
+            .iter()
+            .any(|coin| coin.parent_coin_info != parent_coin_id)
+        {
+            return Err(DriverError::MissingChild);
+        }
+
+        let parent_puzzle_ptr = parent_spend.puzzle_reveal.to_clvm(allocator)?;
+        let parent_puzzle = Puzzle::parse(allocator, parent_puzzle_ptr);
+        let parent_solution = parent_spend.solution.to_clvm(allocator)?;
+
+        match self.identify(allocator, parent_puzzle)? {
+            AssetKind::Xch => Ok(coins.iter().copied().map(DecodedAsset::Xch).collect()),
+            AssetKind::Cat => {
+                let children =
+                    Cat::parse_children(allocator, parent_coin, parent_puzzle, parent_solution)?
+                        .unwrap_or_default();
+
+                coins
+                    .iter()
+                    .map(|coin| {
+                        children
+                            .iter()
+                            .find(|cat| cat.coin == *coin)
+                            .map(|cat| DecodedAsset::Cat(*cat))
+                            .ok_or(DriverError::MissingChild)
+                    })
+                    .collect()
+            }
+            AssetKind::Cat1 => {
+                let children =
+                    Cat1::parse_children(allocator, parent_coin, parent_puzzle, parent_solution)?
+                        .unwrap_or_default();
+
+                coins
+                    .iter()
+                    .map(|coin| {
+                        children
+                            .iter()
+                            .find(|cat| cat.coin == *coin)
+                            .map(|cat| DecodedAsset::Cat1(*cat))
+                            .ok_or(DriverError::MissingChild)
+                    })
+                    .collect()
+            }
+            AssetKind::Nft => {
+                let child = Nft::<HashedPtr>::parse_child(
+                    allocator,
+                    parent_coin,
+                    parent_puzzle,
+                    parent_solution,
+                )?;
+
+                Ok(coins
+                    .iter()
+                    .map(|coin| match &child {
+                        Some(nft) if nft.coin == *coin => DecodedAsset::Nft(*nft),
+                        _ => DecodedAsset::Xch(*coin),
+                    })
+                    .collect())
+            }
+            AssetKind::Did => coins
+                .iter()
+                .map(|&coin| {
+                    // Only the odd output of a singleton is wrapped, the rest are regular coins.
+                    if coin.amount % 2 == 0 {
+                        return Ok(DecodedAsset::Xch(coin));
+                    }
+
+                    Ok(Did::<HashedPtr>::parse_child(
+                        allocator,
+                        parent_coin,
+                        parent_puzzle,
+                        parent_solution,
+                        coin,
+                    )?
+                    .map_or(DecodedAsset::Xch(coin), DecodedAsset::Did))
+                })
+                .collect(),
+            #[cfg(feature = "chip-0035")]
+            AssetKind::DataStore => {
+                let child = DataStore::from_spend(allocator, parent_spend, &[])?;
+
+                Ok(coins
+                    .iter()
+                    .map(|coin| match &child {
+                        Some(datastore) if datastore.coin == *coin => {
+                            DecodedAsset::DataStore(datastore.clone())
+                        }
+                        _ => DecodedAsset::Xch(*coin),
+                    })
+                    .collect())
+            }
+            AssetKind::Custom(name) => {
+                let value = self.parse_custom(allocator, name, parent_puzzle)?;
+
+                Ok(coins
+                    .iter()
+                    .map(|&coin| match &value {
+                        Some(value) => DecodedAsset::Custom {
+                            name,
+                            coin,
+                            puzzle: parent_puzzle,
+                            value: value.clone(),
+                        },
+                        None => DecodedAsset::Unknown {
+                            coin,
+                            puzzle: parent_puzzle,
+                        },
+                    })
+                    .collect())
+            }
+            AssetKind::Unknown => Ok(coins
+                .iter()
+                .map(|&coin| DecodedAsset::Unknown {
+                    coin,
+                    puzzle: parent_puzzle,
+                })
+                .collect()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_protocol::Bytes32;
+    use chia_puzzles::nft::NftMetadata;
+    use chia_sdk_test::Simulator;
+    use chia_sdk_types::Conditions;
+    use clvm_traits::{clvm_list, clvm_quote};
+    use clvm_utils::ToTreeHash;
+
+    use crate::{
+        DidOwner, IntermediateLauncher, Launcher, MerkleTree, NftMint, P2OneOfMany, SpendContext,
+        StandardLayer, P2_ONE_OF_MANY_PUZZLE_HASH,
+    };
+
+    use super::*;
+
+    fn find_spend(coin_spends: &[CoinSpend], coin: Coin) -> CoinSpend {
+        coin_spends
+            .iter()
+            .find(|coin_spend| coin_spend.coin == coin)
+            .expect("missing coin spend")
+            .clone()
+    }
+
+    #[test]
+    fn test_decode_cat() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+        let registry = AssetRegistry::new();
+
+        let (issue_cat, eve) = Cat::single_issuance_eve(
+            ctx,
+            coin.coin_id(),
+            1,
+            Conditions::new().create_coin(puzzle_hash, 1, vec![puzzle_hash.into()]),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+
+        let coin_spends = ctx.take();
+        sim.spend_coins(coin_spends.clone(), &[sk.clone()])?;
+
+        let parent_spend = find_spend(&coin_spends, coin);
+        let decoded = registry.decode_spend(&mut ctx.allocator, &parent_spend)?;
+        assert_eq!(decoded.kind(), AssetKind::Xch);
+
+        let decoded = registry.decode_child(&mut ctx.allocator, &parent_spend, eve.coin)?;
+        assert_eq!(decoded.kind(), AssetKind::Xch);
+
+        let eve_spend = find_spend(&coin_spends, eve.coin);
+        let DecodedAsset::Cat(decoded) = registry.decode_spend(&mut ctx.allocator, &eve_spend)?
+        else {
+            panic!("expected a CAT");
+        };
+        assert_eq!(decoded, eve);
+
+        let cat = eve.wrapped_child(puzzle_hash, 1);
+        let DecodedAsset::Cat(decoded) =
+            registry.decode_child(&mut ctx.allocator, &eve_spend, cat.coin)?
+        else {
+            panic!("expected a CAT");
+        };
+        assert_eq!(decoded, cat);
+
+        // Coins which weren't created by the spend can't be decoded.
+        let other = Coin::new(eve.coin.coin_id(), Bytes32::default(), 1);
+        assert!(matches!(
+            registry.decode_child(&mut ctx.allocator, &eve_spend, other),
+            Err(DriverError::MissingChild)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_nft_and_did() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(2)?;
+        let p2 = StandardLayer::new(pk);
+        let registry = AssetRegistry::new();
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+
+        let mint = NftMint::new(
+            NftMetadata::default(),
+            puzzle_hash,
+            300,
+            Some(DidOwner::from_did_info(&did.info)),
+        );
+
+        let (mint_nft, nft) = IntermediateLauncher::new(did.coin.coin_id(), 0, 1)
+            .create(ctx)?
+            .mint_nft(ctx, mint)?;
+        let new_did = did.update(ctx, &p2, mint_nft)?;
+        let new_nft = nft
+            .clone()
+            .transfer(ctx, &p2, puzzle_hash, Conditions::new())?;
+
+        let coin_spends = ctx.take();
+        sim.spend_coins(coin_spends.clone(), &[sk])?;
+
+        let did_spend = find_spend(&coin_spends, did.coin);
+        let DecodedAsset::Did(decoded) = registry.decode_spend(&mut ctx.allocator, &did_spend)?
+        else {
+            panic!("expected a DID");
+        };
+        assert_eq!(decoded.coin, did.coin);
+        assert_eq!(decoded.proof, did.proof);
+        assert_eq!(decoded.info.launcher_id, did.info.launcher_id);
+
+        let DecodedAsset::Did(decoded) =
+            registry.decode_child(&mut ctx.allocator, &did_spend, new_did.coin)?
+        else {
+            panic!("expected a DID");
+        };
+        assert_eq!(decoded.coin, new_did.coin);
+        assert_eq!(decoded.info.p2_puzzle_hash, puzzle_hash);
+
+        // The intermediate launcher is an even output of the DID, so it isn't wrapped.
+        let intermediate_coin = coin_spends
+            .iter()
+            .find(|coin_spend| coin_spend.coin.parent_coin_info == did.coin.coin_id())
+            .expect("missing intermediate launcher")
+            .coin;
+        let decoded = registry.decode_child(&mut ctx.allocator, &did_spend, intermediate_coin)?;
+        assert_eq!(decoded.kind(), AssetKind::Xch);
+
+        let nft_spend = find_spend(&coin_spends, nft.coin);
+        let DecodedAsset::Nft(decoded) = registry.decode_spend(&mut ctx.allocator, &nft_spend)?
+        else {
+            panic!("expected an NFT");
+        };
+        assert_eq!(decoded.coin, nft.coin);
+        assert_eq!(decoded.info.launcher_id, nft.info.launcher_id);
+
+        let DecodedAsset::Nft(decoded) =
+            registry.decode_child(&mut ctx.allocator, &nft_spend, new_nft.coin)?
+        else {
+            panic!("expected an NFT");
+        };
+        assert_eq!(decoded.coin, new_nft.coin);
+        assert_eq!(decoded.info.p2_puzzle_hash, puzzle_hash);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_custom_puzzle() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let puzzle = ctx.alloc(&1)?;
+        let puzzle_hash = ctx.tree_hash(puzzle).into();
+        let coin = sim.new_coin(puzzle_hash, 1);
+
+        let solution = ctx.alloc(&Conditions::new().create_coin(puzzle_hash, 1, vec![]))?;
+        ctx.spend(coin, crate::Spend::new(puzzle, solution))?;
+
+        let coin_spends = ctx.take();
+        sim.spend_coins(coin_spends.clone(), &[])?;
+
+        let coin_spend = coin_spends[0].clone();
+
+        let mut registry = AssetRegistry::new();
+        let decoded = registry.decode_spend(&mut ctx.allocator, &coin_spend)?;
+        assert!(
+            matches!(decoded, DecodedAsset::Unknown { coin: unknown, puzzle } if unknown == coin && puzzle.curried_puzzle_hash() == puzzle_hash.into())
+        );
+
+        registry.register(puzzle_hash.into(), AssetKind::Xch);
+        let decoded = registry.decode_spend(&mut ctx.allocator, &coin_spend)?;
+        assert_eq!(decoded.kind(), AssetKind::Xch);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_decode_custom_layer() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let delegated_puzzle = ctx.alloc(&clvm_quote!(Conditions::new().create_coin(
+            Bytes32::default(),
+            1,
+            Vec::new()
+        )))?;
+        let delegated_puzzle_hash: Bytes32 = ctx.tree_hash(delegated_puzzle).into();
+        let merkle_tree = MerkleTree::new(&[delegated_puzzle_hash]);
+        let (path, proof) = merkle_tree
+            .get_proof(delegated_puzzle_hash)
+            .expect("missing proof");
+
+        let layer = P2OneOfMany {
+            merkle_root: merkle_tree.root(),
+        };
+        let puzzle_hash = layer.tree_hash().into();
+        let coin = sim.new_coin(puzzle_hash, 1);
+
+        let puzzle = layer.construct_puzzle(ctx)?;
+        let solution = ctx.alloc(&clvm_list!((path, proof), delegated_puzzle, ()))?;
+        ctx.spend(coin, crate::Spend::new(puzzle, solution))?;
+
+        let coin_spends = ctx.take();
+        sim.spend_coins(coin_spends.clone(), &[])?;
+
+        let mut registry = AssetRegistry::new();
+        registry.register_custom(
+            P2_ONE_OF_MANY_PUZZLE_HASH,
+            "p2 one of many",
+            P2OneOfMany::parse_puzzle,
+        );
+
+        let decoded = registry.decode_spend(&mut ctx.allocator, &coin_spends[0])?;
+        assert_eq!(decoded.kind(), AssetKind::Custom("p2 one of many"));
+        assert_eq!(decoded.coin(), coin);
+        assert_eq!(decoded.custom::<P2OneOfMany>(), Some(&layer));
+        assert_eq!(decoded.custom::<Bytes32>(), None);
+
+        // Children are decoded with the value parsed from the parent puzzle.
+        let child = Coin::new(coin.coin_id(), Bytes32::default(), 1);
+        let decoded = registry.decode_child(&mut ctx.allocator, &coin_spends[0], child)?;
+        assert_eq!(decoded.kind(), AssetKind::Custom("p2 one of many"));
+        assert_eq!(decoded.coin(), child);
+        assert_eq!(decoded.custom::<P2OneOfMany>(), Some(&layer));
+
+        // A puzzle with the same mod hash which can't be parsed isn't decoded as the custom asset.
+        let mut registry = AssetRegistry::new();
+        registry.register_custom(
+            P2_ONE_OF_MANY_PUZZLE_HASH,
+            "p2 one of many",
+            |_allocator, _puzzle| Ok(None::<P2OneOfMany>),
+        );
+
+        let decoded = registry.decode_spend(&mut ctx.allocator, &coin_spends[0])?;
+        assert_eq!(decoded.kind(), AssetKind::Unknown);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,766 @@
+use chia_bls::PublicKey;
+use chia_protocol::{Bytes32, Coin, CoinSpend};
+use chia_puzzles::standard::StandardArgs;
+use chia_sdk_types::Conditions;
+use chia_sdk_utils::CoinSelectionError;
+
+use crate::{Cat, CatSpend, DriverError, SpendContext, SpendWithConditions, StandardLayer};
+
+/// The estimated cost of each part of a spend bundle, which is used to decide how many coins
+/// can be spent or created in a single spend bundle.
+///
+/// The defaults are slightly higher than the actual cost of a standard spend and a CAT spend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SpendCosts {
+    /// The cost of spending an XCH coin with the standard puzzle.
+    pub xch_spend: u64,
+    /// The cost of spending a CAT coin with the standard puzzle as its inner puzzle.
+    pub cat_spend: u64,
+    /// The cost of each created coin, including its hint.
+    pub create_coin: u64,
+}
+
+impl Default for SpendCosts {
+    fn default() -> Self {
+        Self {
+            xch_spend: 6_000_000,
+            cat_spend: 32_000_000,
+            create_coin: 3_000_000,
+        }
+    }
+}
+
+/// The distribution of coins that a [`CoinPlanner`] should end up with.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CoinTarget {
+    /// Combines all of the coins into a single coin.
+    Consolidate,
+    /// Creates a coin for each puzzle hash and amount, with anything left over sent back as change.
+    ///
+    /// Coins with the same puzzle hash and amount are created in separate spend bundles,
+    /// since a coin can't create two identical children.
+    Distribute(Vec<(Bytes32, u64)>),
+}
+
+impl CoinTarget {
+    /// Creates a coin with the same amount for each puzzle hash.
+    pub fn split(puzzle_hashes: impl IntoIterator<Item = Bytes32>, amount: u64) -> Self {
+        Self::Distribute(
+            puzzle_hashes
+                .into_iter()
+                .map(|puzzle_hash| (puzzle_hash, amount))
+                .collect(),
+        )
+    }
+}
+
+/// A spend bundle in a [`CoinPlan`], which still needs to be signed.
+#[derive(Debug, Clone)]
+pub struct PlannedBundle {
+    pub coin_spends: Vec<CoinSpend>,
+    pub fee: u64,
+}
+
+/// The result of a [`CoinPlanner`].
+///
+/// The spend bundles must be submitted in order, since later spend bundles can spend coins created by earlier ones.
+#[derive(Debug, Clone)]
+pub struct CoinPlan<T> {
+    pub bundles: Vec<PlannedBundle>,
+    /// The coins created for [`CoinTarget::Distribute`], in the same order as the target.
+    pub outputs: Vec<T>,
+    /// The coin that everything left over was sent to. This is the consolidated coin for [`CoinTarget::Consolidate`].
+    pub change: Option<T>,
+    /// The XCH change from paying the fee for CAT spend bundles.
+    pub fee_change: Option<Coin>,
+}
+
+impl<T> CoinPlan<T> {
+    pub fn total_fee(&self) -> u64 {
+        self.bundles.iter().map(|bundle| bundle.fee).sum()
+    }
+}
+
+/// Plans a sequence of spend bundles that combine or split coins into a [`CoinTarget`],
+/// without exceeding the cost limit of each spend bundle.
+///
+/// Every coin must be owned by the synthetic key, and the change is sent back to its standard puzzle hash.
+/// Each spend bundle pays the fee, which is taken out of the XCH coins themselves, or a separate fee coin for CATs.
+///
+/// Each spend bundle takes every coin spend in the [`SpendContext`], so it should be empty beforehand.
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub struct CoinPlanner {
+    synthetic_key: PublicKey,
+    max_cost: u64,
+    fee: u64,
+    costs: SpendCosts,
+}
+
+impl CoinPlanner {
+    /// Creates a planner with a cost limit of half of the maximum block cost, which is the limit of the mempool.
+    pub fn new(synthetic_key: PublicKey) -> Self {
+        Self {
+            synthetic_key,
+            max_cost: 5_500_000_000,
+            fee: 0,
+            costs: SpendCosts::default(),
+        }
+    }
+
+    pub fn max_cost(mut self, max_cost: u64) -> Self {
+        self.max_cost = max_cost;
+        self
+    }
+
+    /// Sets the fee that is paid by each spend bundle.
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn costs(mut self, costs: SpendCosts) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Plans the spend bundles for XCH coins. All of the coins are spent.
+    pub fn plan_xch(
+        &self,
+        ctx: &mut SpendContext,
+        coins: &[Coin],
+        target: &CoinTarget,
+    ) -> Result<CoinPlan<Coin>, DriverError> {
+        let puzzle_hash = self.puzzle_hash();
+
+        if coins.iter().any(|coin| coin.puzzle_hash != puzzle_hash) {
+            return Err(DriverError::MissingKey);
+        }
+
+        self.plan(ctx, coins.to_vec(), None, target)
+    }
+
+    /// Plans the spend bundles for CAT coins of a single asset id. All of the coins are spent.
+    ///
+    /// If there is a fee, it's paid with the fee coin, which is then passed along as change from one spend bundle to the next.
+    pub fn plan_cat(
+        &self,
+        ctx: &mut SpendContext,
+        cats: &[Cat],
+        fee_coin: Option<Coin>,
+        target: &CoinTarget,
+    ) -> Result<CoinPlan<Cat>, DriverError> {
+        let puzzle_hash = self.puzzle_hash();
+
+        if cats.iter().any(|cat| cat.p2_puzzle_hash != puzzle_hash)
+            || fee_coin.is_some_and(|coin| coin.puzzle_hash != puzzle_hash)
+        {
+            return Err(DriverError::MissingKey);
+        }
+
+        if cats.iter().any(|cat| cat.asset_id != cats[0].asset_id) {
+            return Err(DriverError::MixedAssetIds);
+        }
+
+        if self.fee > 0 && fee_coin.is_none() {
+            return Err(DriverError::MissingCoin);
+        }
+
+        self.plan(ctx, cats.to_vec(), fee_coin, target)
+    }
+
+    fn puzzle_hash(&self) -> Bytes32 {
+        StandardArgs::curry_tree_hash(self.synthetic_key).into()
+    }
+
+    /// The cost of the fee coin spend in each spend bundle, if it's paid separately.
+    fn overhead<T: PlanCoin>(&self) -> u64 {
+        if T::PAYS_FEE || self.fee == 0 {
+            0
+        } else {
+            self.costs.xch_spend + self.costs.create_coin
+        }
+    }
+
+    /// The number of coins that can be created in a spend bundle that spends the given number of coins.
+    fn output_capacity<T: PlanCoin>(&self, inputs: usize) -> usize {
+        let spend_cost = (inputs as u64).saturating_mul(T::spend_cost(&self.costs));
+        let remaining = self
+            .max_cost
+            .saturating_sub(self.overhead::<T>())
+            .saturating_sub(spend_cost);
+        usize::try_from(remaining / self.costs.create_coin.max(1)).unwrap_or(usize::MAX)
+    }
+
+    /// The number of coins that can be combined into one coin in a single spend bundle.
+    fn input_capacity<T: PlanCoin>(&self) -> usize {
+        let remaining = self
+            .max_cost
+            .saturating_sub(self.overhead::<T>())
+            .saturating_sub(self.costs.create_coin);
+        usize::try_from(remaining / T::spend_cost(&self.costs).max(1)).unwrap_or(usize::MAX)
+    }
+
+    fn plan<T: PlanCoin>(
+        &self,
+        ctx: &mut SpendContext,
+        mut coins: Vec<T>,
+        mut fee_coin: Option<Coin>,
+        target: &CoinTarget,
+    ) -> Result<CoinPlan<T>, DriverError> {
+        if coins.is_empty() {
+            return Err(DriverError::MissingCoin);
+        }
+
+        let mut plan = CoinPlan {
+            bundles: Vec::new(),
+            outputs: Vec::new(),
+            change: None,
+            fee_change: None,
+        };
+
+        let input_capacity = self.input_capacity::<T>();
+
+        // Check this up front, since otherwise the coins would be consolidated forever without being split.
+        if matches!(target, CoinTarget::Distribute(..)) && self.output_capacity::<T>(1) < 2 {
+            return Err(DriverError::CostLimitTooLow);
+        }
+
+        // A split needs room for at least one output and the change.
+        let needs_consolidation = |coins: &[T]| match target {
+            CoinTarget::Consolidate => coins.len() > 1,
+            CoinTarget::Distribute(..) => self.output_capacity::<T>(coins.len()) < 2,
+        };
+
+        while needs_consolidation(&coins) {
+            if input_capacity < 2 {
+                return Err(DriverError::CostLimitTooLow);
+            }
+
+            let mut combined = Vec::new();
+
+            for chunk in coins.chunks(input_capacity) {
+                if chunk.len() == 1 {
+                    combined.push(chunk[0]);
+                    continue;
+                }
+
+                let amount = self.remainder(chunk, &[])?;
+
+                if amount == 0 {
+                    return Err(insufficient_balance(chunk));
+                }
+
+                let children = self.spend_bundle(
+                    ctx,
+                    chunk,
+                    &[(self.puzzle_hash(), amount)],
+                    &mut fee_coin,
+                    &mut plan,
+                )?;
+                combined.extend(children);
+            }
+
+            coins = combined;
+        }
+
+        let CoinTarget::Distribute(targets) = target else {
+            plan.change = coins.first().copied();
+            plan.fee_change = fee_coin;
+            return Ok(plan);
+        };
+
+        let puzzle_hash = self.puzzle_hash();
+        let mut pending: Vec<(usize, (Bytes32, u64))> =
+            targets.iter().copied().enumerate().collect();
+        let mut outputs = vec![None; targets.len()];
+
+        loop {
+            // Reserve a slot for the change, and skip outputs that would be identical.
+            let capacity = self.output_capacity::<T>(coins.len()) - 1;
+            let mut chosen = Vec::new();
+            let mut deferred = Vec::new();
+
+            for (index, output) in pending {
+                if chosen.len() < capacity
+                    && !chosen
+                        .iter()
+                        .any(|(_, chosen_output)| *chosen_output == output)
+                {
+                    chosen.push((index, output));
+                } else {
+                    deferred.push((index, output));
+                }
+            }
+
+            let amounts: Vec<(Bytes32, u64)> = chosen.iter().map(|(_, output)| *output).collect();
+            let mut change = self.remainder(&coins, &amounts)?;
+
+            // The change can't be identical to one of the outputs, so defer that output to the next spend bundle.
+            if let Some(position) = chosen
+                .iter()
+                .position(|(_, output)| change > 0 && *output == (puzzle_hash, change))
+            {
+                if deferred.is_empty() {
+                    return Err(DriverError::DuplicateOutput);
+                }
+
+                let output = chosen.remove(position);
+                change += output.1 .1;
+                deferred.insert(0, output);
+            }
+
+            if !deferred.is_empty() && change == 0 {
+                return Err(insufficient_balance(&coins));
+            }
+
+            let mut amounts: Vec<(Bytes32, u64)> =
+                chosen.iter().map(|(_, output)| *output).collect();
+
+            if change > 0 {
+                amounts.push((puzzle_hash, change));
+            }
+
+            let mut children =
+                self.spend_bundle(ctx, &coins, &amounts, &mut fee_coin, &mut plan)?;
+
+            let change_coin = if change > 0 { children.pop() } else { None };
+
+            for ((index, _), child) in chosen.into_iter().zip(children) {
+                outputs[index] = Some(child);
+            }
+
+            pending = deferred;
+
+            if pending.is_empty() {
+                plan.change = change_coin;
+                break;
+            }
+
+            coins = change_coin.into_iter().collect();
+        }
+
+        plan.outputs = outputs.into_iter().flatten().collect();
+        plan.fee_change = fee_coin;
+
+        Ok(plan)
+    }
+
+    /// The amount left over after spending the coins to create the outputs, and paying the fee if needed.
+    fn remainder<T: PlanCoin>(
+        &self,
+        coins: &[T],
+        outputs: &[(Bytes32, u64)],
+    ) -> Result<u64, DriverError> {
+        let total: u128 = coins
+            .iter()
+            .map(|coin| u128::from(coin.coin().amount))
+            .sum();
+        let mut spent: u128 = outputs.iter().map(|(_, amount)| u128::from(*amount)).sum();
+
+        if T::PAYS_FEE {
+            spent += u128::from(self.fee);
+        }
+
+        let remainder = total
+            .checked_sub(spent)
+            .ok_or_else(|| insufficient_balance(coins))?;
+
+        Ok(remainder.try_into()?)
+    }
+
+    /// Spends the coins in a single spend bundle, with the first coin creating all of the outputs.
+    fn spend_bundle<T: PlanCoin>(
+        &self,
+        ctx: &mut SpendContext,
+        coins: &[T],
+        outputs: &[(Bytes32, u64)],
+        fee_coin: &mut Option<Coin>,
+        plan: &mut CoinPlan<T>,
+    ) -> Result<Vec<T>, DriverError> {
+        let p2 = StandardLayer::new(self.synthetic_key);
+        let puzzle_hash = self.puzzle_hash();
+        let primary = coins[0];
+        let primary_id = primary.coin().coin_id();
+
+        let mut primary_conditions = Conditions::new();
+
+        for &(puzzle_hash, amount) in outputs {
+            primary_conditions =
+                primary_conditions.create_coin(puzzle_hash, amount, vec![puzzle_hash.into()]);
+        }
+
+        if T::PAYS_FEE && self.fee > 0 {
+            primary_conditions = primary_conditions.reserve_fee(self.fee);
+        }
+
+        let mut spends = vec![(primary, primary_conditions)];
+
+        for coin in &coins[1..] {
+            spends.push((*coin, Conditions::new().assert_concurrent_spend(primary_id)));
+        }
+
+        T::spend_all(ctx, &p2, spends)?;
+
+        if !T::PAYS_FEE && self.fee > 0 {
+            let coin = fee_coin.take().ok_or(DriverError::MissingCoin)?;
+            let change = coin
+                .amount
+                .checked_sub(self.fee)
+                .ok_or_else(|| insufficient_balance(&[coin]))?;
+
+            let mut conditions = Conditions::new()
+                .assert_concurrent_spend(primary_id)
+                .reserve_fee(self.fee);
+
+            if change > 0 {
+                conditions = conditions.create_coin(puzzle_hash, change, vec![puzzle_hash.into()]);
+                *fee_coin = Some(Coin::new(coin.coin_id(), puzzle_hash, change));
+            }
+
+            p2.spend(ctx, coin, conditions)?;
+        }
+
+        plan.bundles.push(PlannedBundle {
+            coin_spends: ctx.take(),
+            fee: self.fee,
+        });
+
+        Ok(outputs
+            .iter()
+            .map(|&(puzzle_hash, amount)| primary.child(puzzle_hash, amount))
+            .collect())
+    }
+}
+
+fn insufficient_balance<T: PlanCoin>(coins: &[T]) -> DriverError {
+    let total = coins
+        .iter()
+        .map(|coin| u128::from(coin.coin().amount))
+        .sum();
+    CoinSelectionError::InsufficientBalance(total).into()
+}
+
+/// A coin that can be combined or split by the [`CoinPlanner`].
+trait PlanCoin: Copy {
+    /// Whether the fee is paid out of the coins themselves, rather than a separate XCH coin.
+    const PAYS_FEE: bool;
+
+    fn coin(&self) -> Coin;
+
+    fn spend_cost(costs: &SpendCosts) -> u64;
+
+    fn child(&self, p2_puzzle_hash: Bytes32, amount: u64) -> Self;
+
+    fn spend_all(
+        ctx: &mut SpendContext,
+        p2: &StandardLayer,
+        spends: Vec<(Self, Conditions)>,
+    ) -> Result<(), DriverError>;
+}
+
+impl PlanCoin for Coin {
+    const PAYS_FEE: bool = true;
+
+    fn coin(&self) -> Coin {
+        *self
+    }
+
+    fn spend_cost(costs: &SpendCosts) -> u64 {
+        costs.xch_spend
+    }
+
+    fn child(&self, p2_puzzle_hash: Bytes32, amount: u64) -> Self {
+        Coin::new(self.coin_id(), p2_puzzle_hash, amount)
+    }
+
+    fn spend_all(
+        ctx: &mut SpendContext,
+        p2: &StandardLayer,
+        spends: Vec<(Self, Conditions)>,
+    ) -> Result<(), DriverError> {
+        for (coin, conditions) in spends {
+            p2.spend(ctx, coin, conditions)?;
+        }
+        Ok(())
+    }
+}
+
+impl PlanCoin for Cat {
+    const PAYS_FEE: bool = false;
+
+    fn coin(&self) -> Coin {
+        self.coin
+    }
+
+    fn spend_cost(costs: &SpendCosts) -> u64 {
+        costs.cat_spend
+    }
+
+    fn child(&self, p2_puzzle_hash: Bytes32, amount: u64) -> Self {
+        self.wrapped_child(p2_puzzle_hash, amount)
+    }
+
+    fn spend_all(
+        ctx: &mut SpendContext,
+        p2: &StandardLayer,
+        spends: Vec<(Self, Conditions)>,
+    ) -> Result<(), DriverError> {
+        let mut cat_spends = Vec::new();
+
+        for (cat, conditions) in spends {
+            let inner_spend = p2.spend_with_conditions(ctx, conditions)?;
+            cat_spends.push(CatSpend::new(cat, inner_spend));
+        }
+
+        Cat::spend_all(ctx, &cat_spends)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_bls::Signature;
+    use chia_consensus::spendbundle_conditions::get_conditions_from_spendbundle;
+    use chia_protocol::SpendBundle;
+    use chia_sdk_test::Simulator;
+    use chia_sdk_types::TESTNET11_CONSTANTS;
+
+    use super::*;
+
+    fn measure_cost(ctx: &mut SpendContext) -> anyhow::Result<u64> {
+        let spend_bundle = SpendBundle::new(ctx.take(), Signature::default());
+
+        let conds = get_conditions_from_spendbundle(
+            &mut ctx.allocator,
+            &spend_bundle,
+            u64::MAX,
+            100_000_000,
+            &TESTNET11_CONSTANTS,
+        )?;
+
+        Ok(conds.cost)
+    }
+
+    fn costs_for(inputs: u64, outputs: u64) -> u64 {
+        let costs = SpendCosts::default();
+        inputs * costs.xch_spend + outputs * costs.create_coin
+    }
+
+    #[test]
+    fn test_default_costs() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (_sk, pk, puzzle_hash, coin) = sim.new_p2(3)?;
+        let p2 = StandardLayer::new(pk);
+        let defaults = SpendCosts::default();
+
+        let create_coin = |conditions: Conditions, amount: u64| {
+            conditions.create_coin(puzzle_hash, amount, vec![puzzle_hash.into()])
+        };
+
+        p2.spend(ctx, coin, create_coin(Conditions::new(), 3))?;
+        let one_output = measure_cost(ctx)?;
+
+        p2.spend(ctx, coin, create_coin(create_coin(Conditions::new(), 2), 1))?;
+        let two_outputs = measure_cost(ctx)?;
+
+        let create_coin_cost = two_outputs - one_output;
+        assert!(create_coin_cost <= defaults.create_coin);
+        assert!(one_output - create_coin_cost <= defaults.xch_spend);
+
+        let (_issue_cat, eve) =
+            Cat::single_issuance_eve(ctx, coin.coin_id(), 3, create_coin(Conditions::new(), 3))?;
+        ctx.take();
+
+        let cat = eve.wrapped_child(puzzle_hash, 3);
+        let inner_spend = p2.spend_with_conditions(ctx, create_coin(Conditions::new(), 3))?;
+        Cat::spend_all(ctx, &[CatSpend::new(cat, inner_spend)])?;
+        let cat_output = measure_cost(ctx)?;
+
+        assert!(cat_output - create_coin_cost <= defaults.cat_spend);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_consolidate_xch() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(100)?;
+
+        let mut coins = vec![coin];
+        for amount in 101..110 {
+            coins.push(sim.new_coin(puzzle_hash, amount));
+        }
+
+        // Only three coins can be combined at a time, so it takes multiple rounds.
+        let plan = CoinPlanner::new(pk)
+            .max_cost(costs_for(3, 1))
+            .fee(1)
+            .plan_xch(ctx, &coins, &CoinTarget::Consolidate)?;
+
+        assert_eq!(plan.bundles.len(), 5);
+        assert_eq!(plan.total_fee(), 5);
+        assert!(plan.outputs.is_empty());
+
+        for bundle in plan.bundles {
+            sim.spend_coins(bundle.coin_spends, &[sk.clone()])?;
+        }
+
+        let change = plan.change.expect("missing consolidated coin");
+        assert_eq!(change.amount, (100..110).sum::<u64>() - 5);
+        assert!(sim
+            .coin_state(change.coin_id())
+            .is_some_and(|cs| cs.spent_height.is_none()));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_split_xch() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let other = sim.new_coin(puzzle_hash, 50);
+
+        // The last two outputs are identical, so they must be created by different spend bundles.
+        let mut targets: Vec<(Bytes32, u64)> =
+            (0..5).map(|i| (Bytes32::new([i; 32]), 100)).collect();
+        targets.push((puzzle_hash, 100));
+        targets.push((puzzle_hash, 100));
+
+        // Each spend bundle can create two outputs and the change.
+        let plan = CoinPlanner::new(pk)
+            .max_cost(costs_for(1, 3))
+            .fee(1)
+            .plan_xch(
+                ctx,
+                &[coin, other],
+                &CoinTarget::Distribute(targets.clone()),
+            )?;
+
+        assert_eq!(plan.bundles.len(), 5);
+
+        for bundle in plan.bundles {
+            sim.spend_coins(bundle.coin_spends, &[sk.clone()])?;
+        }
+
+        assert_eq!(plan.outputs.len(), targets.len());
+
+        for (output, (puzzle_hash, amount)) in plan.outputs.iter().zip(targets) {
+            assert_eq!(output.puzzle_hash, puzzle_hash);
+            assert_eq!(output.amount, amount);
+            assert!(sim.coin_state(output.coin_id()).is_some());
+        }
+
+        let change = plan.change.expect("missing change");
+        assert_eq!(change.amount, 1050 - 700 - 5);
+        assert!(sim.coin_state(change.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_consolidate_and_split_cat() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(15)?;
+        let p2 = StandardLayer::new(pk);
+        let fee_coin = sim.new_coin(puzzle_hash, 10);
+
+        let mut conditions = Conditions::new();
+        for amount in 1..=5 {
+            conditions = conditions.create_coin(puzzle_hash, amount, vec![puzzle_hash.into()]);
+        }
+
+        let (issue_cat, eve) = Cat::single_issuance_eve(ctx, coin.coin_id(), 15, conditions)?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let cats: Vec<Cat> = (1..=5)
+            .map(|amount| eve.wrapped_child(puzzle_hash, amount))
+            .collect();
+
+        let planner = CoinPlanner::new(pk).fee(2);
+        let plan = planner.plan_cat(ctx, &cats, Some(fee_coin), &CoinTarget::Consolidate)?;
+
+        assert_eq!(plan.bundles.len(), 1);
+
+        for bundle in plan.bundles {
+            sim.spend_coins(bundle.coin_spends, &[sk.clone()])?;
+        }
+
+        let cat = plan.change.expect("missing consolidated CAT");
+        assert_eq!(cat.coin.amount, 15);
+
+        let plan = planner.plan_cat(
+            ctx,
+            &[cat],
+            plan.fee_change,
+            &CoinTarget::split([Bytes32::new([1; 32]), Bytes32::new([2; 32])], 7),
+        )?;
+
+        for bundle in plan.bundles {
+            sim.spend_coins(bundle.coin_spends, &[sk.clone()])?;
+        }
+
+        for cat in &plan.outputs {
+            assert_eq!(cat.coin.amount, 7);
+            assert!(sim.coin_state(cat.coin.coin_id()).is_some());
+        }
+
+        assert_eq!(plan.change.map(|cat| cat.coin.amount), Some(1));
+        assert_eq!(plan.fee_change.map(|coin| coin.amount), Some(6));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cost_limit_too_low_to_split() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (_sk, pk, puzzle_hash, coin) = sim.new_p2(100)?;
+        let other = sim.new_coin(puzzle_hash, 100);
+
+        // Coins can be combined, but even a single coin doesn't have room for an output and the change.
+        let result = CoinPlanner::new(pk)
+            .max_cost(15)
+            .costs(SpendCosts {
+                xch_spend: 1,
+                cat_spend: 1,
+                create_coin: 10,
+            })
+            .plan_xch(
+                ctx,
+                &[coin, other],
+                &CoinTarget::split([Bytes32::default()], 50),
+            );
+
+        assert!(matches!(result, Err(DriverError::CostLimitTooLow)));
+        assert_eq!(ctx.iter().count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_insufficient_balance() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (_sk, pk, _puzzle_hash, coin) = sim.new_p2(100)?;
+
+        let result = CoinPlanner::new(pk).fee(1).plan_xch(
+            ctx,
+            &[coin],
+            &CoinTarget::split([Bytes32::default()], 100),
+        );
+
+        assert!(matches!(
+            result,
+            Err(DriverError::CoinSelection(
+                CoinSelectionError::InsufficientBalance(100)
+            ))
+        ));
+
+        Ok(())
+    }
+}
@@ -1,5 +1,8 @@
 use std::num::TryFromIntError;
 
+use chia_protocol::{Bytes, Bytes32};
+use chia_sdk_signer::SignerError;
+use chia_sdk_utils::CoinSelectionError;
 use clvm_traits::{FromClvmError, ToClvmError};
 use clvmr::reduction::EvalErr;
 use thiserror::Error;
@@ -45,6 +48,89 @@ pub enum DriverError {
     #[error("expected even oracle fee, but it was odd")]
     OddOracleFee,
 
+    #[error("signer error: {0}")]
+    Signer(#[from] SignerError),
+
+    #[error("coin selection error: {0}")]
+    CoinSelection(#[from] CoinSelectionError),
+
+    #[error("missing spendable coin")]
+    MissingCoin,
+
+    #[error("missing public key for p2 puzzle hash")]
+    MissingKey,
+
+    #[error("each issued cat needs a different xch coin as its parent")]
+    MissingIssuanceParent,
+
+    #[error("nft {0} can't be both transferred and locked in the same transaction")]
+    ConflictingNftActions(Bytes32),
+
+    #[error("invalid nft royalty")]
+    InvalidRoyalty,
+
+    #[error("the nft's transfer program doesn't allow it to be traded")]
+    UntradableNft,
+
+    #[error("the metadata updater puzzle hash doesn't match the nft")]
+    MetadataUpdaterMismatch,
+
+    #[error("the recovery request doesn't match the did")]
+    InvalidRecoveryRequest,
+
+    #[error("only {0} of the {1} required recovery attestations were provided")]
+    InsufficientAttestations(usize, u64),
+
+    #[error("missing recovery attestation from did {0}")]
+    MissingAttestation(Bytes32),
+
+    #[error("the key already exists in the datalayer tree")]
+    DuplicateDataLayerKey(Bytes),
+
+    #[error("the key doesn't exist in the datalayer tree")]
+    MissingDataLayerKey(Bytes),
+
+    #[error("the outputs exceed the inputs by {0} mojos")]
+    OutputsExceedInputs(u128),
+
+    #[error("unsupported asset")]
+    UnsupportedAsset,
+
+    #[error("requested payments to {0} can't be fulfilled")]
+    UnfulfilledPayment(Bytes32),
+
+    #[error("all of the cats must have the same asset id")]
+    MixedAssetIds,
+
+    #[error("the cost limit is too low to fit the spends in a spend bundle")]
+    CostLimitTooLow,
+
+    #[error("the change would be identical to one of the outputs")]
+    DuplicateOutput,
+
+    #[error("the mint numbers end at {0}, which exceeds the total of {1}")]
+    MintNumberExceedsTotal(usize, usize),
+
+    #[cfg(feature = "peer")]
+    #[error("client error: {0}")]
+    Client(#[from] chia_sdk_client::ClientError),
+
+    #[cfg(feature = "peer")]
+    #[error("coin state request was rejected: {0:?}")]
+    RejectCoinState(chia_protocol::RejectStateReason),
+
+    #[cfg(feature = "peer")]
+    #[error("puzzle state request was rejected: {0:?}")]
+    RejectPuzzleState(chia_protocol::RejectStateReason),
+
+    #[cfg(feature = "peer")]
+    #[error("puzzle and solution request was rejected for coin {0}")]
+    RejectPuzzleSolution(Bytes32),
+
+    #[cfg(feature = "peer")]
+    #[error("the spend of singleton {0} couldn't be parsed as the expected primitive")]
+    UnexpectedSingleton(Bytes32),
+
     #[error("custom driver error: {0}")]
     Custom(String),
 }
@@ -4,6 +4,8 @@ use clvmr::{Allocator, NodePtr};
 
 use crate::{DriverError, Puzzle, Spend, SpendContext};
 
+pub use chia_sdk_derive::Layer;
+
 /// An individual layer in a puzzle's hierarchy.
 pub trait Layer {
     /// Most of the time, this is an actual CLVM type representing the solution.
@@ -1,27 +1,37 @@
+mod cat1_layer;
 mod cat_layer;
+mod did_allowlist_transfer_layer;
 mod did_layer;
 mod nft_ownership_layer;
 mod nft_state_layer;
+mod nft_transfer_layer;
 mod p2_delegated_conditions_layer;
 mod p2_delegated_singleton_layer;
 mod p2_one_of_many;
 mod p2_singleton;
+mod royalty_split_transfer_layer;
 mod royalty_transfer_layer;
 mod settlement_layer;
 mod singleton_layer;
+mod soulbound_transfer_layer;
 mod standard_layer;
 
+pub use cat1_layer::*;
 pub use cat_layer::*;
+pub use did_allowlist_transfer_layer::*;
 pub use did_layer::*;
 pub use nft_ownership_layer::*;
 pub use nft_state_layer::*;
+pub use nft_transfer_layer::*;
 pub use p2_delegated_conditions_layer::*;
 pub use p2_delegated_singleton_layer::*;
 pub use p2_one_of_many::*;
 pub use p2_singleton::*;
+pub use royalty_split_transfer_layer::*;
 pub use royalty_transfer_layer::*;
 pub use settlement_layer::*;
 pub use singleton_layer::*;
+pub use soulbound_transfer_layer::*;
 pub use standard_layer::*;
 
 #[cfg(feature = "chip-0035")]
@@ -0,0 +1,199 @@
+use std::convert::Infallible;
+
+use chia_protocol::Bytes32;
+use chia_puzzles::singleton::{
+    SingletonStruct, SINGLETON_LAUNCHER_PUZZLE_HASH, SINGLETON_TOP_LAYER_PUZZLE_HASH,
+};
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::{CurriedProgram, ToTreeHash, TreeHash};
+use clvmr::{Allocator, NodePtr};
+use hex_literal::hex;
+
+use crate::{DriverError, Layer, Puzzle, SpendContext};
+
+/// The DID allowlist transfer [`Layer`] only allows NFTs to be assigned to a DID that is on the allowlist.
+/// The NFT can still be removed from its DID, and it can be traded without paying royalties.
+///
+/// The allowlist is generic, so that it can be kept in an allocator as a [`HashedPtr`](crate::HashedPtr).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DidAllowlistTransferLayer<A = Vec<Bytes32>> {
+    /// The launcher id of the NFT this transfer program belongs to.
+    pub launcher_id: Bytes32,
+    /// The launcher ids of the DIDs that the NFT can be assigned to.
+    pub allowed_dids: A,
+}
+
+impl<A> DidAllowlistTransferLayer<A> {
+    pub fn new(launcher_id: Bytes32, allowed_dids: A) -> Self {
+        Self {
+            launcher_id,
+            allowed_dids,
+        }
+    }
+}
+
+impl<A> Layer for DidAllowlistTransferLayer<A>
+where
+    A: ToClvm<Allocator> + FromClvm<Allocator> + Clone,
+{
+    type Solution = Infallible;
+
+    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        let curried = CurriedProgram {
+            program: ctx.did_allowlist_transfer_puzzle()?,
+            args: DidAllowlistTransferArgs::new(self.launcher_id, self.allowed_dids.clone()),
+        };
+        ctx.alloc(&curried)
+    }
+
+    fn parse_puzzle(allocator: &Allocator, puzzle: Puzzle) -> Result<Option<Self>, DriverError> {
+        let Some(puzzle) = puzzle.as_curried() else {
+            return Ok(None);
+        };
+
+        if puzzle.mod_hash != DID_ALLOWLIST_TRANSFER_PUZZLE_HASH {
+            return Ok(None);
+        }
+
+        let args = DidAllowlistTransferArgs::<A>::from_clvm(allocator, puzzle.args)?;
+
+        if args.singleton_struct.mod_hash != SINGLETON_TOP_LAYER_PUZZLE_HASH.into()
+            || args.singleton_struct.launcher_puzzle_hash != SINGLETON_LAUNCHER_PUZZLE_HASH.into()
+        {
+            return Err(DriverError::InvalidSingletonStruct);
+        }
+
+        Ok(Some(Self {
+            launcher_id: args.singleton_struct.launcher_id,
+            allowed_dids: args.allowed_dids,
+        }))
+    }
+
+    fn construct_solution(
+        &self,
+        _ctx: &mut SpendContext,
+        _solution: Self::Solution,
+    ) -> Result<NodePtr, DriverError> {
+        panic!("DidAllowlistTransferLayer does not have a solution");
+    }
+
+    fn parse_solution(
+        _allocator: &Allocator,
+        _solution: NodePtr,
+    ) -> Result<Self::Solution, DriverError> {
+        panic!("DidAllowlistTransferLayer does not have a solution");
+    }
+}
+
+impl<A> ToTreeHash for DidAllowlistTransferLayer<A>
+where
+    A: ToTreeHash,
+{
+    fn tree_hash(&self) -> TreeHash {
+        DidAllowlistTransferArgs::curry_tree_hash(self.launcher_id, self.allowed_dids.tree_hash())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(curry)]
+pub struct DidAllowlistTransferArgs<A = Vec<Bytes32>> {
+    pub singleton_struct: SingletonStruct,
+    pub allowed_dids: A,
+}
+
+impl<A> DidAllowlistTransferArgs<A> {
+    pub fn new(launcher_id: Bytes32, allowed_dids: A) -> Self {
+        Self {
+            singleton_struct: SingletonStruct::new(launcher_id),
+            allowed_dids,
+        }
+    }
+}
+
+impl DidAllowlistTransferArgs<TreeHash> {
+    pub fn curry_tree_hash(launcher_id: Bytes32, allowed_dids: TreeHash) -> TreeHash {
+        CurriedProgram {
+            program: DID_ALLOWLIST_TRANSFER_PUZZLE_HASH,
+            args: Self::new(launcher_id, allowed_dids),
+        }
+        .tree_hash()
+    }
+}
+
+/// ```text
+/// (mod (SINGLETON_STRUCT ALLOWED_DIDS Current_Owner conditions solution)
+///   (include condition_codes.clib)
+///   (include curry-and-treehash.clib)
+///
+///   (defun-inline calculate_full_puzzle_hash (SINGLETON_STRUCT inner_puzzle_hash)
+///     (puzzle-hash-of-curried-function (f SINGLETON_STRUCT) inner_puzzle_hash (sha256tree SINGLETON_STRUCT))
+///   )
+///
+///   (defun is_allowed (allowed_dids did)
+///     (if allowed_dids
+///       (if (= (f allowed_dids) did)
+///         1
+///         (is_allowed (r allowed_dids) did)
+///       )
+///       (x)
+///     )
+///   )
+///
+///   (if solution
+///     (list (f solution) 0
+///       (if (all (f solution) (not (= (f solution) Current_Owner)) (if (f solution) (is_allowed ALLOWED_DIDS (f solution)) ()))
+///         (list
+///           (list
+///             ASSERT_PUZZLE_ANNOUNCEMENT
+///             (sha256
+///               (calculate_full_puzzle_hash (c (f SINGLETON_STRUCT) (c (f solution) (r (r SINGLETON_STRUCT)))) (f (r (r solution))))
+///               (f (r SINGLETON_STRUCT))
+///             )
+///           )
+///         )
+///         ()
+///       )
+///     )
+///     (list Current_Owner () ())
+///   )
+/// )
+/// ```
+pub const DID_ALLOWLIST_TRANSFER_PUZZLE: [u8; 567] = hex!(
+    "
+    ff02ffff01ff02ffff03ff5fffff01ff04ff819fffff04ff80ffff04ffff02ff
+    ff03ffff22ff819fffff20ffff09ff819fff178080ffff02ffff03ff819fffff
+    01ff02ff16ffff04ff02ffff04ff0bffff04ff819fff8080808080ff8080ff01
+    8080ffff01ff04ffff04ff10ffff04ffff0bffff02ff2effff04ff02ffff04ff
+    09ffff04ff8202dfffff04ffff02ff3effff04ff02ffff04ffff04ff09ffff04
+    ff819fff1d8080ff80808080ff808080808080ff1580ff808080ff8080ff8080
+    ff0180ff80808080ffff01ff04ff17ffff01ff80ff80808080ff0180ffff04ff
+    ff01ffffff3f02ff04ff0101ffff02ff02ffff03ff05ffff01ff02ff1affff04
+    ff02ffff04ff0dffff04ffff0bff12ffff0bff2cff1480ffff0bff12ffff0bff
+    12ffff0bff2cff3c80ff0980ffff0bff12ff0bffff0bff2cff8080808080ff80
+    80808080ffff010b80ff0180ffff02ffff03ff05ffff01ff02ffff03ffff09ff
+    09ff0b80ffff01ff0101ffff01ff02ff16ffff04ff02ffff04ff0dffff04ff0b
+    ff808080808080ff0180ffff01ff088080ff0180ffff0bff12ffff0bff2cff18
+    80ffff0bff12ffff0bff12ffff0bff2cff3c80ff0580ffff0bff12ffff02ff1a
+    ffff04ff02ffff04ff07ffff04ffff0bff2cff2c80ff8080808080ffff0bff2c
+    ff8080808080ff02ffff03ffff07ff0580ffff01ff0bffff0102ffff02ff3eff
+    ff04ff02ffff04ff09ff80808080ffff02ff3effff04ff02ffff04ff0dff8080
+    808080ffff01ff0bffff0101ff058080ff0180ff018080
+    "
+);
+
+pub const DID_ALLOWLIST_TRANSFER_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "36c508156039439e28d9770baf03400114ec4e533e6cc6a4d4758751f2bd3e3f"
+));
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_puzzle_hash;
+
+    use super::*;
+
+    #[test]
+    fn test_puzzle_hash() -> anyhow::Result<()> {
+        assert_puzzle_hash!(DID_ALLOWLIST_TRANSFER_PUZZLE => DID_ALLOWLIST_TRANSFER_PUZZLE_HASH);
+        Ok(())
+    }
+}
@@ -1,6 +1,6 @@
 use chia_protocol::Bytes32;
 use chia_puzzles::nft::{NftStateLayerArgs, NftStateLayerSolution, NFT_STATE_LAYER_PUZZLE_HASH};
-use chia_sdk_types::{run_puzzle, NewMetadataOutput, UpdateNftMetadata};
+use chia_sdk_types::{run_puzzle, NewMetadataInfo, NewMetadataOutput, UpdateNftMetadata};
 use clvm_traits::{FromClvm, ToClvm};
 use clvm_utils::{CurriedProgram, ToTreeHash, TreeHash};
 use clvmr::{Allocator, NodePtr};
@@ -130,12 +130,15 @@ where
 }
 
 impl<M, I> NftStateLayer<M, I> {
+    /// Runs the metadata updater puzzle in the condition, to get the new metadata and metadata updater puzzle hash.
+    ///
+    /// This works with any metadata updater, not just the default one.
     pub fn get_next_metadata(
         allocator: &mut Allocator,
         current_metadata: &M,
         curent_metadata_updater_puzzle_hash: Bytes32,
         condition: UpdateNftMetadata<NodePtr, NodePtr>,
-    ) -> Result<M, DriverError>
+    ) -> Result<NewMetadataInfo<M>, DriverError>
     where
         M: ToClvm<Allocator> + FromClvm<Allocator>,
     {
@@ -154,6 +157,6 @@ impl<M, I> NftStateLayer<M, I> {
 
         let parsed = NewMetadataOutput::<M, NodePtr>::from_clvm(allocator, output)?;
 
-        Ok(parsed.metadata_info.new_metadata)
+        Ok(parsed.metadata_info)
     }
 }
@@ -0,0 +1,84 @@
+use std::convert::Infallible;
+
+use chia_protocol::Bytes32;
+use clvm_traits::FromClvm;
+use clvm_utils::{ToTreeHash, TreeHash};
+use clvmr::{Allocator, NodePtr};
+
+use crate::{
+    DidAllowlistTransferLayer, DriverError, HashedPtr, Layer, Puzzle, RoyaltySplit,
+    RoyaltySplitTransferLayer, RoyaltyTransferLayer, SoulboundTransferLayer, SpendContext,
+};
+
+/// Any of the transfer program [`Layer`]s that can be used in the [`NftOwnershipLayer`](crate::NftOwnershipLayer).
+///
+/// The lists of royalties and allowed DIDs are kept in the allocator, so that the layer is [`Copy`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NftTransferLayer {
+    Royalty(RoyaltyTransferLayer),
+    RoyaltySplit(RoyaltySplitTransferLayer<HashedPtr>),
+    DidAllowlist(DidAllowlistTransferLayer<HashedPtr>),
+    Soulbound(SoulboundTransferLayer),
+}
+
+impl Layer for NftTransferLayer {
+    type Solution = Infallible;
+
+    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        match self {
+            Self::Royalty(layer) => layer.construct_puzzle(ctx),
+            Self::RoyaltySplit(layer) => layer.construct_puzzle(ctx),
+            Self::DidAllowlist(layer) => layer.construct_puzzle(ctx),
+            Self::Soulbound(layer) => layer.construct_puzzle(ctx),
+        }
+    }
+
+    fn parse_puzzle(allocator: &Allocator, puzzle: Puzzle) -> Result<Option<Self>, DriverError> {
+        if let Some(layer) = RoyaltyTransferLayer::parse_puzzle(allocator, puzzle)? {
+            return Ok(Some(Self::Royalty(layer)));
+        }
+
+        // The lists are parsed once, so that malformed programs are rejected up front.
+        if let Some(layer) =
+            RoyaltySplitTransferLayer::<HashedPtr>::parse_puzzle(allocator, puzzle)?
+        {
+            Vec::<RoyaltySplit>::from_clvm(allocator, layer.royalties.ptr())?;
+            return Ok(Some(Self::RoyaltySplit(layer)));
+        }
+
+        if let Some(layer) =
+            DidAllowlistTransferLayer::<HashedPtr>::parse_puzzle(allocator, puzzle)?
+        {
+            Vec::<Bytes32>::from_clvm(allocator, layer.allowed_dids.ptr())?;
+            return Ok(Some(Self::DidAllowlist(layer)));
+        }
+
+        Ok(SoulboundTransferLayer::parse_puzzle(allocator, puzzle)?.map(Self::Soulbound))
+    }
+
+    fn construct_solution(
+        &self,
+        _ctx: &mut SpendContext,
+        _solution: Self::Solution,
+    ) -> Result<NodePtr, DriverError> {
+        panic!("NftTransferLayer does not have a solution");
+    }
+
+    fn parse_solution(
+        _allocator: &Allocator,
+        _solution: NodePtr,
+    ) -> Result<Self::Solution, DriverError> {
+        panic!("NftTransferLayer does not have a solution");
+    }
+}
+
+impl ToTreeHash for NftTransferLayer {
+    fn tree_hash(&self) -> TreeHash {
+        match self {
+            Self::Royalty(layer) => layer.tree_hash(),
+            Self::RoyaltySplit(layer) => layer.tree_hash(),
+            Self::DidAllowlist(layer) => layer.tree_hash(),
+            Self::Soulbound(layer) => layer.tree_hash(),
+        }
+    }
+}
@@ -1,66 +1,28 @@
 use chia_bls::PublicKey;
 use chia_sdk_types::Condition;
 use clvm_traits::{FromClvm, ToClvm};
-use clvm_utils::{CurriedProgram, TreeHash};
-use clvmr::{Allocator, NodePtr};
+use clvm_utils::TreeHash;
+use clvmr::NodePtr;
 use hex_literal::hex;
 
-use crate::{DriverError, Layer, Puzzle, SpendContext};
+use crate::Layer;
 
 /// The p2 delegated conditions [`Layer`] allows a certain key to spend the coin.
 /// To do so, a list of additional conditions is signed and passed in the solution.
 /// Typically, the [`StandardLayer`](crate::StandardLayer) is used instead, since it adds more flexibility.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Layer)]
+#[layer(
+    crate = crate,
+    puzzle = P2_DELEGATED_CONDITIONS_PUZZLE,
+    mod_hash = P2_DELEGATED_CONDITIONS_PUZZLE_HASH,
+    args = P2DelegatedConditionsArgs,
+    solution = P2DelegatedConditionsSolution,
+)]
 pub struct P2DelegatedConditionsLayer {
     /// The public key that has the ability to spend the coin.
     pub public_key: PublicKey,
 }
 
-impl Layer for P2DelegatedConditionsLayer {
-    type Solution = P2DelegatedConditionsSolution;
-
-    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
-        let curried = CurriedProgram {
-            program: ctx.p2_delegated_conditions_puzzle()?,
-            args: P2DelegatedConditionsArgs::new(self.public_key),
-        };
-        ctx.alloc(&curried)
-    }
-
-    fn construct_solution(
-        &self,
-        ctx: &mut SpendContext,
-        solution: Self::Solution,
-    ) -> Result<NodePtr, DriverError> {
-        ctx.alloc(&solution)
-    }
-
-    fn parse_puzzle(allocator: &Allocator, puzzle: Puzzle) -> Result<Option<Self>, DriverError> {
-        let Some(puzzle) = puzzle.as_curried() else {
-            return Ok(None);
-        };
-
-        if puzzle.mod_hash != P2_DELEGATED_CONDITIONS_PUZZLE_HASH {
-            return Ok(None);
-        }
-
-        let args = P2DelegatedConditionsArgs::from_clvm(allocator, puzzle.args)?;
-
-        Ok(Some(Self {
-            public_key: args.public_key,
-        }))
-    }
-
-    fn parse_solution(
-        allocator: &Allocator,
-        solution: NodePtr,
-    ) -> Result<Self::Solution, DriverError> {
-        Ok(P2DelegatedConditionsSolution::from_clvm(
-            allocator, solution,
-        )?)
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
 #[clvm(curry)]
 pub struct P2DelegatedConditionsArgs {
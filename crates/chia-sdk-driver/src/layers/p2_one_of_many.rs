@@ -1,65 +1,25 @@
 use chia_protocol::Bytes32;
 use clvm_traits::{FromClvm, ToClvm};
-use clvm_utils::{CurriedProgram, TreeHash};
-use clvmr::{Allocator, NodePtr};
+use clvm_utils::TreeHash;
+use clvmr::NodePtr;
 use hex_literal::hex;
 
-use crate::{DriverError, Layer, Puzzle, SpendContext};
+use crate::Layer;
 
 /// The p2 1 of n [`Layer`] allows for picking from several delegated puzzles at runtime without revealing up front.
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Layer)]
+#[layer(
+    crate = crate,
+    puzzle = P2_ONE_OF_MANY_PUZZLE,
+    mod_hash = P2_ONE_OF_MANY_PUZZLE_HASH,
+    args = P2OneOfManyArgs,
+    solution = P2OneOfManySolution<NodePtr, NodePtr>,
+)]
 pub struct P2OneOfMany {
     /// The merkle root used to lookup the delegated puzzle as part of the solution.
     pub merkle_root: Bytes32,
 }
 
-impl Layer for P2OneOfMany {
-    type Solution = P2OneOfManySolution<NodePtr, NodePtr>;
-
-    fn parse_puzzle(allocator: &Allocator, puzzle: Puzzle) -> Result<Option<Self>, DriverError> {
-        let Some(puzzle) = puzzle.as_curried() else {
-            return Ok(None);
-        };
-
-        if puzzle.mod_hash != P2_ONE_OF_MANY_PUZZLE_HASH {
-            return Ok(None);
-        }
-
-        let args = P2OneOfManyArgs::from_clvm(allocator, puzzle.args)?;
-
-        Ok(Some(Self {
-            merkle_root: args.merkle_root,
-        }))
-    }
-
-    fn parse_solution(
-        allocator: &Allocator,
-        solution: NodePtr,
-    ) -> Result<Self::Solution, DriverError> {
-        Ok(P2OneOfManySolution::<NodePtr, NodePtr>::from_clvm(
-            allocator, solution,
-        )?)
-    }
-
-    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
-        let curried = CurriedProgram {
-            program: ctx.p2_one_of_many_puzzle()?,
-            args: P2OneOfManyArgs {
-                merkle_root: self.merkle_root,
-            },
-        };
-        ctx.alloc(&curried)
-    }
-
-    fn construct_solution(
-        &self,
-        ctx: &mut SpendContext,
-        solution: Self::Solution,
-    ) -> Result<NodePtr, DriverError> {
-        ctx.alloc(&solution)
-    }
-}
-
 #[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
 #[clvm(curry)]
 pub struct P2OneOfManyArgs {
@@ -94,13 +54,40 @@ pub const P2_ONE_OF_MANY_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
 
 #[cfg(test)]
 mod tests {
+    use clvm_utils::ToTreeHash;
+
     use super::*;
 
-    use crate::assert_puzzle_hash;
+    use crate::{assert_puzzle_hash, Layer, Puzzle, SpendContext};
 
     #[test]
     fn test_puzzle_hash() -> anyhow::Result<()> {
         assert_puzzle_hash!(P2_ONE_OF_MANY_PUZZLE => P2_ONE_OF_MANY_PUZZLE_HASH);
         Ok(())
     }
+
+    #[test]
+    fn test_derived_layer() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        let layer = P2OneOfMany {
+            merkle_root: Bytes32::new([42; 32]),
+        };
+        let puzzle = layer.construct_puzzle(ctx)?;
+
+        assert_eq!(ctx.tree_hash(puzzle), layer.tree_hash());
+
+        let puzzle = Puzzle::parse(&ctx.allocator, puzzle);
+        assert_eq!(
+            P2OneOfMany::parse_puzzle(&ctx.allocator, puzzle)?,
+            Some(layer)
+        );
+
+        // Other puzzles aren't parsed as this layer.
+        let puzzle = ctx.p2_delegated_conditions_puzzle()?;
+        let puzzle = Puzzle::parse(&ctx.allocator, puzzle);
+        assert_eq!(P2OneOfMany::parse_puzzle(&ctx.allocator, puzzle)?, None);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,245 @@
+use std::convert::Infallible;
+
+use chia_protocol::Bytes32;
+use chia_puzzles::singleton::{
+    SingletonStruct, SINGLETON_LAUNCHER_PUZZLE_HASH, SINGLETON_TOP_LAYER_PUZZLE_HASH,
+};
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::{CurriedProgram, ToTreeHash, TreeHash};
+use clvmr::{Allocator, NodePtr};
+use hex_literal::hex;
+
+use crate::{DriverError, Layer, Puzzle, SpendContext};
+
+/// The royalty split transfer [`Layer`] is used to transfer NFTs with royalties that are split between multiple recipients.
+/// When an NFT is traded, each recipient is paid their own percentage of the trade price.
+///
+/// The list of royalties is generic, so that it can be kept in an allocator as a [`HashedPtr`](crate::HashedPtr).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RoyaltySplitTransferLayer<R = Vec<RoyaltySplit>> {
+    /// The launcher id of the NFT this transfer program belongs to.
+    pub launcher_id: Bytes32,
+    /// The recipients of the royalties, and the percentage that each of them is paid.
+    pub royalties: R,
+}
+
+impl<R> RoyaltySplitTransferLayer<R> {
+    pub fn new(launcher_id: Bytes32, royalties: R) -> Self {
+        Self {
+            launcher_id,
+            royalties,
+        }
+    }
+}
+
+impl<R> Layer for RoyaltySplitTransferLayer<R>
+where
+    R: ToClvm<Allocator> + FromClvm<Allocator> + Clone,
+{
+    type Solution = Infallible;
+
+    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        let curried = CurriedProgram {
+            program: ctx.royalty_split_transfer_puzzle()?,
+            args: RoyaltySplitTransferArgs::new(self.launcher_id, self.royalties.clone()),
+        };
+        ctx.alloc(&curried)
+    }
+
+    fn parse_puzzle(allocator: &Allocator, puzzle: Puzzle) -> Result<Option<Self>, DriverError> {
+        let Some(puzzle) = puzzle.as_curried() else {
+            return Ok(None);
+        };
+
+        if puzzle.mod_hash != ROYALTY_SPLIT_TRANSFER_PUZZLE_HASH {
+            return Ok(None);
+        }
+
+        let args = RoyaltySplitTransferArgs::<R>::from_clvm(allocator, puzzle.args)?;
+
+        if args.singleton_struct.mod_hash != SINGLETON_TOP_LAYER_PUZZLE_HASH.into()
+            || args.singleton_struct.launcher_puzzle_hash != SINGLETON_LAUNCHER_PUZZLE_HASH.into()
+        {
+            return Err(DriverError::InvalidSingletonStruct);
+        }
+
+        Ok(Some(Self {
+            launcher_id: args.singleton_struct.launcher_id,
+            royalties: args.royalties,
+        }))
+    }
+
+    fn construct_solution(
+        &self,
+        _ctx: &mut SpendContext,
+        _solution: Self::Solution,
+    ) -> Result<NodePtr, DriverError> {
+        panic!("RoyaltySplitTransferLayer does not have a solution");
+    }
+
+    fn parse_solution(
+        _allocator: &Allocator,
+        _solution: NodePtr,
+    ) -> Result<Self::Solution, DriverError> {
+        panic!("RoyaltySplitTransferLayer does not have a solution");
+    }
+}
+
+impl<R> ToTreeHash for RoyaltySplitTransferLayer<R>
+where
+    R: ToTreeHash,
+{
+    fn tree_hash(&self) -> TreeHash {
+        RoyaltySplitTransferArgs::curry_tree_hash(self.launcher_id, self.royalties.tree_hash())
+    }
+}
+
+/// A recipient of the royalties paid when an NFT with a [`RoyaltySplitTransferLayer`] is traded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub struct RoyaltySplit {
+    pub puzzle_hash: Bytes32,
+    /// The percentage of the trade price, represented in ten thousandths, so a value of 300 means 3%.
+    pub ten_thousandths: u16,
+}
+
+impl RoyaltySplit {
+    pub fn new(puzzle_hash: Bytes32, ten_thousandths: u16) -> Self {
+        Self {
+            puzzle_hash,
+            ten_thousandths,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(curry)]
+pub struct RoyaltySplitTransferArgs<R = Vec<RoyaltySplit>> {
+    pub singleton_struct: SingletonStruct,
+    pub royalties: R,
+}
+
+impl<R> RoyaltySplitTransferArgs<R> {
+    pub fn new(launcher_id: Bytes32, royalties: R) -> Self {
+        Self {
+            singleton_struct: SingletonStruct::new(launcher_id),
+            royalties,
+        }
+    }
+}
+
+impl RoyaltySplitTransferArgs<TreeHash> {
+    pub fn curry_tree_hash(launcher_id: Bytes32, royalties: TreeHash) -> TreeHash {
+        CurriedProgram {
+            program: ROYALTY_SPLIT_TRANSFER_PUZZLE_HASH,
+            args: Self::new(launcher_id, royalties),
+        }
+        .tree_hash()
+    }
+}
+
+/// ```text
+/// (mod (SINGLETON_STRUCT ROYALTIES Current_Owner conditions solution)
+///   (include condition_codes.clib)
+///   (include curry-and-treehash.clib)
+///
+///   (defconstant TEN_THOUSAND 10000)
+///
+///   (defun-inline calculate_full_puzzle_hash (SINGLETON_STRUCT inner_puzzle_hash)
+///     (puzzle-hash-of-curried-function (f SINGLETON_STRUCT) inner_puzzle_hash (sha256tree SINGLETON_STRUCT))
+///   )
+///
+///   (defun-inline calculate_percentage (amount percentage)
+///     (f (divmod (* amount percentage) TEN_THOUSAND))
+///   )
+///
+///   (defun royalty_payments (royalties trade_price)
+///     (if royalties
+///       (c
+///         (list (f (f royalties)) (calculate_percentage trade_price (f (r (f royalties)))) (list (f (f royalties))))
+///         (royalty_payments (r royalties) trade_price)
+///       )
+///       ()
+///     )
+///   )
+///
+///   (defun parse_trade_prices_list (ROYALTIES trade_prices_list my_nft_id)
+///     (if trade_prices_list
+///       (c
+///         (list
+///           ASSERT_PUZZLE_ANNOUNCEMENT
+///           (sha256
+///             (f (r (f trade_prices_list)))
+///             (sha256tree (c my_nft_id (royalty_payments ROYALTIES (f (f trade_prices_list)))))
+///           )
+///         )
+///         (parse_trade_prices_list ROYALTIES (r trade_prices_list) my_nft_id)
+///       )
+///       ()
+///     )
+///   )
+///
+///   (if solution
+///     (list (f solution) 0
+///       (if (all (f solution) (not (= (f solution) Current_Owner)))
+///         (c
+///           (list
+///             ASSERT_PUZZLE_ANNOUNCEMENT
+///             (sha256
+///               (calculate_full_puzzle_hash (c (f SINGLETON_STRUCT) (c (f solution) (r (r SINGLETON_STRUCT)))) (f (r (r solution))))
+///               (f (r SINGLETON_STRUCT))
+///             )
+///           )
+///           (parse_trade_prices_list ROYALTIES (f (r solution)) (f (r SINGLETON_STRUCT)))
+///         )
+///         (parse_trade_prices_list ROYALTIES (f (r solution)) (f (r SINGLETON_STRUCT)))
+///       )
+///     )
+///     (list Current_Owner () ())
+///   )
+/// )
+/// ```
+pub const ROYALTY_SPLIT_TRANSFER_PUZZLE: [u8; 729] = hex!(
+    "
+    ff02ffff01ff02ffff03ff5fffff01ff04ff819fffff04ff80ffff04ffff02ff
+    ff03ffff22ff819fffff20ffff09ff819fff17808080ffff01ff04ffff04ff10
+    ffff04ffff0bffff02ff16ffff04ff02ffff04ff09ffff04ff8202dfffff04ff
+    ff02ff3effff04ff02ffff04ffff04ff09ffff04ff819fff1d8080ff80808080
+    ff808080808080ff1580ff808080ffff02ff3affff04ff02ffff04ff0bffff04
+    ff82015fffff04ff15ff80808080808080ffff01ff02ff3affff04ff02ffff04
+    ff0bffff04ff82015fffff04ff15ff80808080808080ff0180ff80808080ffff
+    01ff04ff17ffff01ff80ff80808080ff0180ffff04ffff01ffffff3fff0204ff
+    01ff01822710ffff02ffff02ffff03ff05ffff01ff02ff2affff04ff02ffff04
+    ff0dffff04ffff0bff12ffff0bff14ff3880ffff0bff12ffff0bff12ffff0bff
+    14ff2c80ff0980ffff0bff12ff0bffff0bff14ff8080808080ff8080808080ff
+    ff010b80ff0180ff02ffff03ff0bffff01ff04ffff04ff10ffff04ffff0bff53
+    ffff02ff3effff04ff02ffff04ffff04ff17ffff02ff2effff04ff02ffff04ff
+    05ffff04ff23ff808080808080ff8080808080ff808080ffff02ff3affff04ff
+    02ffff04ff05ffff04ff1bffff04ff17ff80808080808080ff8080ff0180ffff
+    0bff12ffff0bff14ff2880ffff0bff12ffff0bff12ffff0bff14ff2c80ff0580
+    ffff0bff12ffff02ff2affff04ff02ffff04ff07ffff04ffff0bff14ff1480ff
+    8080808080ffff0bff14ff8080808080ffff02ffff03ff05ffff01ff04ffff04
+    ff11ffff04ffff05ffff14ffff12ff0bff2980ff3c8080ffff04ffff04ff11ff
+    8080ff80808080ffff02ff2effff04ff02ffff04ff0dffff04ff0bff80808080
+    8080ff8080ff0180ff02ffff03ffff07ff0580ffff01ff0bffff0102ffff02ff
+    3effff04ff02ffff04ff09ff80808080ffff02ff3effff04ff02ffff04ff0dff
+    8080808080ffff01ff0bffff0101ff058080ff0180ff018080
+    "
+);
+
+pub const ROYALTY_SPLIT_TRANSFER_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "33965e63753253d4ecd0cd4d76cf911f8f8ff88c61c947e7e7c2e82a89406475"
+));
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_puzzle_hash;
+
+    use super::*;
+
+    #[test]
+    fn test_puzzle_hash() -> anyhow::Result<()> {
+        assert_puzzle_hash!(ROYALTY_SPLIT_TRANSFER_PUZZLE => ROYALTY_SPLIT_TRANSFER_PUZZLE_HASH);
+        Ok(())
+    }
+}
@@ -1,4 +1,6 @@
-use chia_puzzles::offer::{SettlementPaymentsSolution, SETTLEMENT_PAYMENTS_PUZZLE_HASH};
+use chia_puzzles::offer::{
+    SettlementPaymentsSolution, SETTLEMENT_PAYMENTS_PUZZLE_HASH, SETTLEMENT_PAYMENTS_PUZZLE_HASH_V1,
+};
 use clvm_traits::FromClvm;
 use clvmr::{Allocator, NodePtr};
 
@@ -37,3 +39,38 @@ impl Layer for SettlementLayer {
         Ok(FromClvm::from_clvm(allocator, solution)?)
     }
 }
+
+/// The legacy settlement [`Layer`] was used by offers before CAT2 was released.
+/// It has the same solution as the [`SettlementLayer`], but a different puzzle hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SettlementV1Layer;
+
+impl Layer for SettlementV1Layer {
+    type Solution = SettlementPaymentsSolution;
+
+    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        ctx.settlement_payments_puzzle_v1()
+    }
+
+    fn construct_solution(
+        &self,
+        ctx: &mut SpendContext,
+        solution: Self::Solution,
+    ) -> Result<NodePtr, DriverError> {
+        ctx.alloc(&solution)
+    }
+
+    fn parse_puzzle(_allocator: &Allocator, puzzle: Puzzle) -> Result<Option<Self>, DriverError> {
+        if puzzle.curried_puzzle_hash() != SETTLEMENT_PAYMENTS_PUZZLE_HASH_V1 {
+            return Ok(None);
+        }
+        Ok(Some(Self))
+    }
+
+    fn parse_solution(
+        allocator: &Allocator,
+        solution: NodePtr,
+    ) -> Result<Self::Solution, DriverError> {
+        Ok(FromClvm::from_clvm(allocator, solution)?)
+    }
+}
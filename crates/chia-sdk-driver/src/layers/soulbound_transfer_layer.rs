@@ -0,0 +1,201 @@
+use std::convert::Infallible;
+
+use chia_protocol::Bytes32;
+use chia_puzzles::singleton::{
+    SingletonStruct, SINGLETON_LAUNCHER_PUZZLE_HASH, SINGLETON_TOP_LAYER_PUZZLE_HASH,
+};
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::{CurriedProgram, ToTreeHash, TreeHash};
+use clvmr::{Allocator, NodePtr};
+use hex_literal::hex;
+
+use crate::{DriverError, Layer, Puzzle, SpendContext};
+
+/// The soulbound transfer [`Layer`] makes NFTs non-transferable, by only allowing them to be sent to the same p2 puzzle hash.
+/// The NFT can still be assigned to a DID, but it can't be traded.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SoulboundTransferLayer {
+    /// The launcher id of the NFT this transfer program belongs to.
+    pub launcher_id: Bytes32,
+    /// The p2 puzzle hash that the NFT is bound to.
+    pub p2_puzzle_hash: Bytes32,
+}
+
+impl SoulboundTransferLayer {
+    pub fn new(launcher_id: Bytes32, p2_puzzle_hash: Bytes32) -> Self {
+        Self {
+            launcher_id,
+            p2_puzzle_hash,
+        }
+    }
+}
+
+impl Layer for SoulboundTransferLayer {
+    type Solution = Infallible;
+
+    fn construct_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        let curried = CurriedProgram {
+            program: ctx.soulbound_transfer_puzzle()?,
+            args: SoulboundTransferArgs::new(self.launcher_id, self.p2_puzzle_hash),
+        };
+        ctx.alloc(&curried)
+    }
+
+    fn parse_puzzle(allocator: &Allocator, puzzle: Puzzle) -> Result<Option<Self>, DriverError> {
+        let Some(puzzle) = puzzle.as_curried() else {
+            return Ok(None);
+        };
+
+        if puzzle.mod_hash != SOULBOUND_TRANSFER_PUZZLE_HASH {
+            return Ok(None);
+        }
+
+        let args = SoulboundTransferArgs::from_clvm(allocator, puzzle.args)?;
+
+        if args.singleton_struct.mod_hash != SINGLETON_TOP_LAYER_PUZZLE_HASH.into()
+            || args.singleton_struct.launcher_puzzle_hash != SINGLETON_LAUNCHER_PUZZLE_HASH.into()
+        {
+            return Err(DriverError::InvalidSingletonStruct);
+        }
+
+        Ok(Some(Self {
+            launcher_id: args.singleton_struct.launcher_id,
+            p2_puzzle_hash: args.p2_puzzle_hash,
+        }))
+    }
+
+    fn construct_solution(
+        &self,
+        _ctx: &mut SpendContext,
+        _solution: Self::Solution,
+    ) -> Result<NodePtr, DriverError> {
+        panic!("SoulboundTransferLayer does not have a solution");
+    }
+
+    fn parse_solution(
+        _allocator: &Allocator,
+        _solution: NodePtr,
+    ) -> Result<Self::Solution, DriverError> {
+        panic!("SoulboundTransferLayer does not have a solution");
+    }
+}
+
+impl ToTreeHash for SoulboundTransferLayer {
+    fn tree_hash(&self) -> TreeHash {
+        SoulboundTransferArgs::curry_tree_hash(self.launcher_id, self.p2_puzzle_hash)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(curry)]
+pub struct SoulboundTransferArgs {
+    pub singleton_struct: SingletonStruct,
+    pub p2_puzzle_hash: Bytes32,
+}
+
+impl SoulboundTransferArgs {
+    pub fn new(launcher_id: Bytes32, p2_puzzle_hash: Bytes32) -> Self {
+        Self {
+            singleton_struct: SingletonStruct::new(launcher_id),
+            p2_puzzle_hash,
+        }
+    }
+
+    pub fn curry_tree_hash(launcher_id: Bytes32, p2_puzzle_hash: Bytes32) -> TreeHash {
+        CurriedProgram {
+            program: SOULBOUND_TRANSFER_PUZZLE_HASH,
+            args: Self::new(launcher_id, p2_puzzle_hash),
+        }
+        .tree_hash()
+    }
+}
+
+/// ```text
+/// (mod (SINGLETON_STRUCT P2_PUZZLE_HASH Current_Owner conditions solution)
+///   (include condition_codes.clib)
+///   (include curry-and-treehash.clib)
+///
+///   (defun-inline calculate_full_puzzle_hash (SINGLETON_STRUCT inner_puzzle_hash)
+///     (puzzle-hash-of-curried-function (f SINGLETON_STRUCT) inner_puzzle_hash (sha256tree SINGLETON_STRUCT))
+///   )
+///
+///   (defun assert_bound (P2_PUZZLE_HASH conditions)
+///     (if conditions
+///       (if (all (= (f (f conditions)) CREATE_COIN) (logand (f (r (r (f conditions)))) 1))
+///         (if (= (f (r (f conditions))) P2_PUZZLE_HASH)
+///           (assert_bound P2_PUZZLE_HASH (r conditions))
+///           (x)
+///         )
+///         (assert_bound P2_PUZZLE_HASH (r conditions))
+///       )
+///       1
+///     )
+///   )
+///
+///   (if (assert_bound P2_PUZZLE_HASH conditions)
+///     (if solution
+///       (if (f (r solution))
+///         (x)
+///         (list (f solution) 0
+///           (if (all (f solution) (not (= (f solution) Current_Owner)))
+///             (list
+///               (list
+///                 ASSERT_PUZZLE_ANNOUNCEMENT
+///                 (sha256
+///                   (calculate_full_puzzle_hash (c (f SINGLETON_STRUCT) (c (f solution) (r (r SINGLETON_STRUCT)))) (f (r (r solution))))
+///                   (f (r SINGLETON_STRUCT))
+///                 )
+///               )
+///             )
+///             ()
+///           )
+///         )
+///       )
+///       (list Current_Owner () ())
+///     )
+///     (x)
+///   )
+/// )
+/// ```
+pub const SOULBOUND_TRANSFER_PUZZLE: [u8; 654] = hex!(
+    "
+    ff02ffff01ff02ffff03ffff02ff3affff04ff02ffff04ff0bffff04ff2fff80
+    80808080ffff01ff02ffff03ff5fffff01ff02ffff03ff82015fffff01ff0880
+    ffff01ff04ff819fffff04ff80ffff04ffff02ffff03ffff22ff819fffff20ff
+    ff09ff819fff17808080ffff01ff04ffff04ff10ffff04ffff0bffff02ff2eff
+    ff04ff02ffff04ff09ffff04ff8202dfffff04ffff02ff3effff04ff02ffff04
+    ffff04ff09ffff04ff819fff1d8080ff80808080ff808080808080ff1580ff80
+    8080ff8080ff8080ff0180ff8080808080ff0180ffff01ff04ff17ffff01ff80
+    ff80808080ff0180ffff01ff088080ff0180ffff04ffff01ffffff3f02ff33ff
+    0401ffff01ff02ff02ffff03ff0bffff01ff02ffff03ffff22ffff09ff23ff14
+    80ffff18ff81b3ffff01018080ffff01ff02ffff03ffff09ff53ff0580ffff01
+    ff02ff3affff04ff02ffff04ff05ffff04ff1bff8080808080ffff01ff088080
+    ff0180ffff01ff02ff3affff04ff02ffff04ff05ffff04ff1bff808080808080
+    ff0180ffff01ff010180ff0180ffff02ffff03ff05ffff01ff02ff16ffff04ff
+    02ffff04ff0dffff04ffff0bff2affff0bff3cff2c80ffff0bff2affff0bff2a
+    ffff0bff3cff1280ff0980ffff0bff2aff0bffff0bff3cff8080808080ff8080
+    808080ffff010b80ff0180ffff0bff2affff0bff3cff1880ffff0bff2affff0b
+    ff2affff0bff3cff1280ff0580ffff0bff2affff02ff16ffff04ff02ffff04ff
+    07ffff04ffff0bff3cff3c80ff8080808080ffff0bff3cff8080808080ff02ff
+    ff03ffff07ff0580ffff01ff0bffff0102ffff02ff3effff04ff02ffff04ff09
+    ff80808080ffff02ff3effff04ff02ffff04ff0dff8080808080ffff01ff0bff
+    ff0101ff058080ff0180ff018080
+    "
+);
+
+pub const SOULBOUND_TRANSFER_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "ab8f1352f85fc808a73901af46c8d8c27656bc1d5a2bde02e76efe44ecb73344"
+));
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_puzzle_hash;
+
+    use super::*;
+
+    #[test]
+    fn test_puzzle_hash() -> anyhow::Result<()> {
+        assert_puzzle_hash!(SOULBOUND_TRANSFER_PUZZLE => SOULBOUND_TRANSFER_PUZZLE_HASH);
+        Ok(())
+    }
+}
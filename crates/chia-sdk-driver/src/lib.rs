@@ -1,5 +1,7 @@
 #![doc = include_str!("../docs.md")]
 
+mod asset_registry;
+mod coin_planner;
 mod driver_error;
 mod hashed_ptr;
 mod layer;
@@ -9,8 +11,12 @@ mod primitives;
 mod puzzle;
 mod spend;
 mod spend_context;
+mod spend_summary;
 mod spend_with_conditions;
+mod transaction_builder;
 
+pub use asset_registry::*;
+pub use coin_planner::*;
 pub use driver_error::*;
 pub use hashed_ptr::*;
 pub use layer::*;
@@ -20,10 +26,28 @@ pub use primitives::*;
 pub use puzzle::*;
 pub use spend::*;
 pub use spend_context::*;
+pub use spend_summary::*;
 pub use spend_with_conditions::*;
+pub use transaction_builder::*;
+
+/// The paths used by the code generated by `#[derive(Layer)]`.
+#[doc(hidden)]
+pub mod __derive {
+    pub use clvm_traits;
+    pub use clvm_utils;
+    pub use clvmr;
+
+    pub use crate::{DriverError, Layer, Puzzle, SpendContext};
+}
 
 #[cfg(feature = "offers")]
 mod offers;
 
 #[cfg(feature = "offers")]
 pub use offers::*;
+
+#[cfg(feature = "peer")]
+mod singleton_tracer;
+
+#[cfg(feature = "peer")]
+pub use singleton_tracer::*;
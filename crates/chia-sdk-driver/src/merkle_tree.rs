@@ -1,4 +1,4 @@
-use std::collections::HashMap;
+use std::{collections::HashMap, sync::OnceLock};
 
 use chia_protocol::Bytes32;
 use clvmr::sha2::Sha256;
@@ -6,36 +6,148 @@ use clvmr::sha2::Sha256;
 const HASH_TREE_PREFIX: &[u8] = &[2];
 const HASH_LEAF_PREFIX: &[u8] = &[1];
 
+/// A merkle tree with the same layout as `merkle_utils.clib`, which is used by puzzles such as
+/// [`P2OneOfMany`](crate::P2OneOfMany) and the delegation layer.
+///
+/// Changes are hashed lazily, the next time the root or a proof is needed, so building a tree one leaf
+/// at a time is linear. When the tree is rehashed, subtrees whose leaves haven't changed are reused.
+/// The tree is split in half at every level though, so a change near the start shifts most subtrees.
 #[derive(Debug, Clone)]
 pub struct MerkleTree {
-    pub root: Bytes32,
-    pub proofs: HashMap<Bytes32, (u32, Vec<Bytes32>)>,
-}
-
-use std::fmt::Debug;
-
-#[derive(Debug, Clone)]
-pub enum BinaryTree<T> {
-    Leaf(T),
-    Node(Box<BinaryTree<T>>, Box<BinaryTree<T>>),
+    leaves: Vec<Bytes32>,
+    leaf_hashes: Vec<Bytes32>,
+    indices: HashMap<Bytes32, usize>,
+    /// The hash of every node in pre-order, where a subtree with `n` leaves takes up `2n - 1` nodes.
+    /// This is empty until it's needed after a change.
+    nodes: OnceLock<Vec<Bytes32>>,
+    /// The nodes and number of leaves the last time the tree was hashed.
+    previous: Option<(Vec<Bytes32>, usize)>,
+    /// The index of the first leaf that has changed since the previous nodes were hashed.
+    first_changed: usize,
 }
 
 impl MerkleTree {
     pub fn new(leaves: &[Bytes32]) -> Self {
-        if leaves.is_empty() {
-            return Self {
-                root: Bytes32::default(),
-                proofs: HashMap::new(),
-            };
+        let mut tree = Self {
+            leaves: leaves.to_vec(),
+            leaf_hashes: leaves.iter().map(|leaf| Self::leaf_hash(*leaf)).collect(),
+            indices: HashMap::new(),
+            nodes: OnceLock::new(),
+            previous: None,
+            first_changed: 0,
+        };
+        tree.reindex(0);
+        tree
+    }
+
+    pub fn root(&self) -> Bytes32 {
+        self.nodes().first().copied().unwrap_or_default()
+    }
+
+    pub fn leaves(&self) -> &[Bytes32] {
+        &self.leaves
+    }
+
+    /// Appends a leaf to the end of the tree.
+    pub fn insert(&mut self, leaf: Bytes32) {
+        self.extend([leaf]);
+    }
+
+    /// Appends multiple leaves to the end of the tree.
+    pub fn extend(&mut self, leaves: impl IntoIterator<Item = Bytes32>) {
+        let start = self.leaves.len();
+        self.invalidate(start);
+
+        for leaf in leaves {
+            self.leaves.push(leaf);
+            self.leaf_hashes.push(Self::leaf_hash(leaf));
+        }
+
+        self.reindex(start);
+    }
+
+    /// Removes a leaf from the tree, keeping the order of the remaining leaves.
+    ///
+    /// Returns `false` if the leaf isn't in the tree.
+    pub fn remove(&mut self, leaf: Bytes32) -> bool {
+        let Some(index) = self.indices.remove(&leaf) else {
+            return false;
+        };
+
+        self.invalidate(index);
+        self.leaves.remove(index);
+        self.leaf_hashes.remove(index);
+        self.reindex(index);
+
+        // An earlier duplicate of the leaf may still be in the tree.
+        if let Some(duplicate) = self.leaves[..index].iter().rposition(|item| *item == leaf) {
+            self.indices.insert(leaf, duplicate);
+        }
+
+        true
+    }
+
+    /// Returns the path and proof of a leaf, which can be checked with [`MerkleTree::verify_proof`].
+    pub fn get_proof(&self, leaf: Bytes32) -> Option<(u32, Vec<Bytes32>)> {
+        let mut index = *self.indices.get(&leaf)?;
+
+        let nodes = self.nodes();
+        let mut offset = 0;
+        let mut size = self.leaves.len();
+        let mut sides = Vec::new();
+        let mut proof = Vec::new();
+
+        while size > 1 {
+            let left_size = (size + 1) >> 1;
+            let right_offset = offset + 2 * left_size;
+
+            if index < left_size {
+                sides.push(false);
+                proof.push(nodes[right_offset]);
+                offset += 1;
+                size = left_size;
+            } else {
+                sides.push(true);
+                proof.push(nodes[offset + 1]);
+                offset = right_offset;
+                index -= left_size;
+                size -= left_size;
+            }
         }
 
-        let (root, proofs) = MerkleTree::build_merkle_tree(leaves);
-        Self { root, proofs }
+        proof.reverse();
+
+        let path = sides
+            .iter()
+            .rev()
+            .enumerate()
+            .fold(
+                0,
+                |path, (depth, is_right)| {
+                    if *is_right {
+                        path | (1 << depth)
+                    } else {
+                        path
+                    }
+                },
+            );
+
+        Some((path, proof))
     }
 
-    fn build_merkle_tree(leaves: &[Bytes32]) -> (Bytes32, HashMap<Bytes32, (u32, Vec<Bytes32>)>) {
-        let binary_tree = MerkleTree::list_to_binary_tree(leaves);
-        MerkleTree::build_merkle_tree_from_binary_tree(&binary_tree)
+    /// Checks that the leaf is in a tree with the given root, the same way that `merkle_utils.clib` does on-chain.
+    pub fn verify_proof(root: Bytes32, leaf: Bytes32, path: u32, proof: &[Bytes32]) -> bool {
+        let mut hash = Self::leaf_hash(leaf);
+
+        for (depth, sibling) in proof.iter().enumerate() {
+            hash = if depth < 32 && path & (1 << depth) != 0 {
+                Self::sha256(&[HASH_TREE_PREFIX, sibling, &hash])
+            } else {
+                Self::sha256(&[HASH_TREE_PREFIX, &hash, sibling])
+            };
+        }
+
+        hash == root
     }
 
     fn sha256(args: &[&[u8]]) -> Bytes32 {
@@ -45,67 +157,134 @@ impl MerkleTree {
         Bytes32::from(hasher.finalize())
     }
 
-    fn list_to_binary_tree<T: Clone + Debug + Default>(objects: &[T]) -> BinaryTree<T> {
-        let size = objects.len();
-        if size == 0 {
-            return BinaryTree::Leaf(T::default());
+    fn leaf_hash(leaf: Bytes32) -> Bytes32 {
+        Self::sha256(&[HASH_LEAF_PREFIX, &leaf])
+    }
+
+    /// Updates the index of every leaf from `start` onwards. If a leaf is duplicated, the last one is used.
+    fn reindex(&mut self, start: usize) {
+        for (index, leaf) in self.leaves.iter().enumerate().skip(start) {
+            self.indices.insert(*leaf, index);
         }
-        if size == 1 {
-            return BinaryTree::Leaf(objects[0].clone());
+    }
+
+    /// Marks the leaves from `index` onwards as changed. This must be called before the leaves are changed.
+    fn invalidate(&mut self, index: usize) {
+        if let Some(nodes) = self.nodes.take() {
+            self.previous = Some((nodes, self.leaves.len()));
+            self.first_changed = index;
+        } else {
+            self.first_changed = self.first_changed.min(index);
         }
-        let midpoint = (size + 1) >> 1;
-        let first_half = &objects[..midpoint];
-        let last_half = &objects[midpoint..];
-        let left_tree = MerkleTree::list_to_binary_tree(first_half);
-        let right_tree = MerkleTree::list_to_binary_tree(last_half);
-        BinaryTree::Node(Box::new(left_tree), Box::new(right_tree))
     }
 
-    fn build_merkle_tree_from_binary_tree(
-        tuples: &BinaryTree<Bytes32>,
-    ) -> (Bytes32, HashMap<Bytes32, (u32, Vec<Bytes32>)>) {
-        match tuples {
-            BinaryTree::Leaf(t) => {
-                let hash = MerkleTree::sha256(&[HASH_LEAF_PREFIX, t]);
-                let mut proof = HashMap::new();
-                proof.insert(*t, (0, vec![]));
-                (hash, proof)
-            }
-            BinaryTree::Node(left, right) => {
-                let (left_root, left_proofs) = MerkleTree::build_merkle_tree_from_binary_tree(left);
-                let (right_root, right_proofs) =
-                    MerkleTree::build_merkle_tree_from_binary_tree(right);
+    fn nodes(&self) -> &[Bytes32] {
+        self.nodes.get_or_init(|| {
+            let mut nodes = vec![Bytes32::default(); (2 * self.leaves.len()).saturating_sub(1)];
 
-                let new_root = MerkleTree::sha256(&[HASH_TREE_PREFIX, &left_root, &right_root]);
-                let mut new_proofs = HashMap::new();
+            if !nodes.is_empty() {
+                let previous =
+                    self.previous
+                        .as_ref()
+                        .filter(|(_, len)| *len > 0)
+                        .map(|(nodes, len)| Subtree {
+                            nodes,
+                            start: 0,
+                            len: *len,
+                        });
 
-                for (name, (path, mut proof)) in left_proofs {
-                    proof.push(right_root);
-                    new_proofs.insert(name, (path, proof));
-                }
+                self.hash_subtree(0, &mut nodes, previous);
+            }
 
-                for (name, (path, mut proof)) in right_proofs {
-                    let path = path | (1 << proof.len());
-                    proof.push(left_root);
-                    new_proofs.insert(name, (path, proof));
-                }
+            nodes
+        })
+    }
+
+    fn hash_subtree(
+        &self,
+        start: usize,
+        nodes: &mut [Bytes32],
+        previous: Option<Subtree<'_>>,
+    ) -> Bytes32 {
+        let len = (nodes.len() + 1) >> 1;
 
-                (new_root, new_proofs)
+        // If the leaves of the subtree haven't changed, neither has its hash.
+        if start + len <= self.first_changed {
+            if let Some(subtree) = previous.and_then(|previous| previous.find(start, len)) {
+                nodes.copy_from_slice(subtree.nodes);
+                return nodes[0];
             }
         }
+
+        let hash = if len == 1 {
+            self.leaf_hashes[start]
+        } else {
+            let midpoint = (len + 1) >> 1;
+            let (left_nodes, right_nodes) = nodes[1..].split_at_mut(2 * midpoint - 1);
+            let left = self.hash_subtree(start, left_nodes, previous);
+            let right = self.hash_subtree(start + midpoint, right_nodes, previous);
+            Self::sha256(&[HASH_TREE_PREFIX, &left, &right])
+        };
+
+        nodes[0] = hash;
+        hash
     }
+}
 
-    pub fn get_proof(&self, leaf: Bytes32) -> Option<(u32, Vec<Bytes32>)> {
-        self.proofs.get(&leaf).cloned()
+/// A subtree of previously hashed nodes, covering `len` leaves from `start`.
+#[derive(Clone, Copy)]
+struct Subtree<'a> {
+    nodes: &'a [Bytes32],
+    start: usize,
+    len: usize,
+}
+
+impl<'a> Subtree<'a> {
+    /// Finds the subtree that covers `len` leaves from `start`, if there is one.
+    fn find(self, start: usize, len: usize) -> Option<Subtree<'a>> {
+        let mut subtree = self;
+
+        if start < subtree.start || start + len > subtree.start + subtree.len {
+            return None;
+        }
+
+        while subtree.start != start || subtree.len != len {
+            if subtree.len <= len {
+                return None;
+            }
+
+            let midpoint = (subtree.len + 1) >> 1;
+
+            subtree = if start < subtree.start + midpoint {
+                Subtree {
+                    nodes: &subtree.nodes[1..2 * midpoint],
+                    start: subtree.start,
+                    len: midpoint,
+                }
+            } else {
+                Subtree {
+                    nodes: &subtree.nodes[2 * midpoint..],
+                    start: subtree.start + midpoint,
+                    len: subtree.len - midpoint,
+                }
+            };
+        }
+
+        Some(subtree)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use chia_sdk_types::{run_puzzle, Conditions};
+    use clvm_traits::{clvm_list, clvm_quote};
+    use clvmr::NodePtr;
     use hex_literal::hex;
     use rstest::rstest;
 
+    use crate::{Layer, P2OneOfMany, SpendContext};
+
     #[rstest]
     #[case::no_leaves(&[],
            Bytes32::default(),
@@ -162,10 +341,165 @@ mod tests {
     ) {
         let merkle_tree = MerkleTree::new(leaves);
 
-        assert_eq!(merkle_tree.root, expected_root);
+        assert_eq!(merkle_tree.root(), expected_root);
 
         for (leaf, path, proof) in expected_proofs {
             assert_eq!(merkle_tree.get_proof(leaf), Some((path, proof)));
         }
     }
+
+    #[test]
+    fn test_verify_proof() {
+        let leaves: Vec<Bytes32> = (0..100).map(|i| Bytes32::new([i; 32])).collect();
+        let merkle_tree = MerkleTree::new(&leaves);
+
+        for leaf in &leaves {
+            let (path, proof) = merkle_tree.get_proof(*leaf).expect("missing proof");
+            assert!(MerkleTree::verify_proof(
+                merkle_tree.root(),
+                *leaf,
+                path,
+                &proof
+            ));
+
+            // The proof is only valid for the leaf it was generated for.
+            assert!(!MerkleTree::verify_proof(
+                merkle_tree.root(),
+                Bytes32::new([200; 32]),
+                path,
+                &proof
+            ));
+            assert!(
+                proof.is_empty()
+                    || !MerkleTree::verify_proof(merkle_tree.root(), *leaf, !path, &proof)
+            );
+        }
+    }
+
+    #[test]
+    fn test_incremental_updates() {
+        let leaves: Vec<Bytes32> = (0..50).map(|i| Bytes32::new([i; 32])).collect();
+
+        let mut merkle_tree = MerkleTree::new(&[]);
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            merkle_tree.insert(*leaf);
+
+            let expected = MerkleTree::new(&leaves[..=i]);
+            assert_eq!(merkle_tree.root(), expected.root());
+        }
+
+        let mut remaining = leaves.clone();
+
+        for leaf in leaves.iter().step_by(3) {
+            assert!(merkle_tree.remove(*leaf));
+            remaining.retain(|item| item != leaf);
+
+            let expected = MerkleTree::new(&remaining);
+            assert_eq!(merkle_tree.root(), expected.root());
+
+            for leaf in &remaining {
+                assert_eq!(merkle_tree.get_proof(*leaf), expected.get_proof(*leaf));
+            }
+        }
+
+        assert!(!merkle_tree.remove(leaves[0]));
+        assert_eq!(merkle_tree.get_proof(leaves[0]), None);
+
+        merkle_tree.extend(leaves.iter().step_by(3).copied());
+        remaining.extend(leaves.iter().step_by(3));
+        assert_eq!(merkle_tree.leaves(), remaining.as_slice());
+        assert_eq!(merkle_tree.root(), MerkleTree::new(&remaining).root());
+    }
+
+    #[test]
+    fn test_large_tree_updates() {
+        let leaves: Vec<Bytes32> = (0..12_000u32)
+            .map(|i| MerkleTree::sha256(&[&i.to_be_bytes()]))
+            .collect();
+
+        let mut merkle_tree = MerkleTree::new(&[]);
+
+        for (i, leaf) in leaves.iter().enumerate() {
+            merkle_tree.insert(*leaf);
+
+            if (i + 1) % 3000 == 0 {
+                assert_eq!(merkle_tree.root(), MerkleTree::new(&leaves[..=i]).root());
+            }
+        }
+
+        let mut remaining = leaves.clone();
+
+        // Changes near the end of the tree reuse most of the subtrees before them.
+        for leaf in leaves.iter().rev().step_by(97).take(20) {
+            assert!(merkle_tree.remove(*leaf));
+            remaining.retain(|item| item != leaf);
+        }
+
+        merkle_tree.extend(leaves[..10].iter().copied());
+        remaining.extend(&leaves[..10]);
+
+        let expected = MerkleTree::new(&remaining);
+        assert_eq!(merkle_tree.root(), expected.root());
+
+        // And changes near the start shift almost every subtree.
+        assert!(merkle_tree.remove(leaves[5_000]));
+        assert!(merkle_tree.remove(leaves[10]));
+        remaining.retain(|item| *item != leaves[5_000] && *item != leaves[10]);
+
+        let expected = MerkleTree::new(&remaining);
+        assert_eq!(merkle_tree.root(), expected.root());
+
+        for leaf in remaining.iter().step_by(101) {
+            assert_eq!(merkle_tree.get_proof(*leaf), expected.get_proof(*leaf));
+        }
+
+        // The first ten leaves are duplicated at the end, so their proofs are for the last copy.
+        let (path, proof) = merkle_tree.get_proof(leaves[0]).expect("missing proof");
+        assert_eq!(
+            path,
+            expected.get_proof(leaves[0]).expect("missing proof").0
+        );
+        assert!(MerkleTree::verify_proof(
+            merkle_tree.root(),
+            leaves[0],
+            path,
+            &proof
+        ));
+    }
+
+    #[test]
+    fn test_p2_one_of_many_proof() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        let puzzles: Vec<NodePtr> = (0..5)
+            .map(|i| ctx.alloc(&clvm_quote!(Conditions::new().reserve_fee(i))))
+            .collect::<Result<_, _>>()?;
+        let leaves: Vec<Bytes32> = puzzles
+            .iter()
+            .map(|puzzle| ctx.tree_hash(*puzzle).into())
+            .collect();
+        let merkle_tree = MerkleTree::new(&leaves);
+
+        let p2_one_of_many = P2OneOfMany {
+            merkle_root: merkle_tree.root(),
+        };
+        let puzzle = p2_one_of_many.construct_puzzle(ctx)?;
+
+        for (inner_puzzle, leaf) in puzzles.into_iter().zip(leaves) {
+            let proof = merkle_tree.get_proof(leaf).expect("missing proof");
+            assert!(MerkleTree::verify_proof(
+                merkle_tree.root(),
+                leaf,
+                proof.0,
+                &proof.1
+            ));
+
+            // The on-chain puzzle accepts the same proof.
+            let solution = ctx.alloc(&clvm_list!(proof, inner_puzzle, ()))?;
+            run_puzzle(&mut ctx.allocator, puzzle, solution)?;
+        }
+
+        Ok(())
+    }
 }
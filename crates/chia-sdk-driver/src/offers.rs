@@ -1,13 +1,24 @@
+mod asset_offer;
 mod compress;
 mod encode;
 mod error;
 mod offer;
+mod offer_aggregation;
 mod offer_builder;
+mod offer_summary;
+mod offer_validity;
 mod parsed_offer;
 
+#[cfg(test)]
+mod test_utils;
+
+pub use asset_offer::*;
 pub use compress::*;
 pub use encode::*;
 pub use error::*;
 pub use offer::*;
+pub use offer_aggregation::*;
 pub use offer_builder::*;
+pub use offer_summary::*;
+pub use offer_validity::*;
 pub use parsed_offer::*;
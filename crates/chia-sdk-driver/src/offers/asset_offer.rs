@@ -0,0 +1,945 @@
+use chia_protocol::{Bytes32, Coin, SpendBundle};
+use chia_puzzles::{
+    cat::CatArgs,
+    offer::{
+        NotarizedPayment, Payment, SettlementPaymentsSolution, SETTLEMENT_PAYMENTS_PUZZLE_HASH,
+    },
+};
+use chia_sdk_signer::AggSigConstants;
+use chia_sdk_types::{run_puzzle, Condition, Conditions, TradePrice};
+use clvm_traits::{FromClvm, ToClvm};
+use clvmr::Allocator;
+use indexmap::IndexMap;
+
+use crate::{
+    calculate_nft_trace_price, payment_assertion, Action, AssetRegistry, Cat, CatLayer, CatSpend,
+    DecodedAsset, DriverError, HashedPtr, Layer, Nft, NftInfo, Offer, ParsedOffer, Puzzle,
+    SettlementLayer, SpendContext, SpendableCoins, TransactionBuilder, UnsignedTransaction,
+};
+
+/// An asset that the maker of an offer locks into the settlement payments puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OfferedAsset {
+    Xch(u64),
+    Cat {
+        asset_id: Bytes32,
+        amount: u64,
+    },
+    /// An NFT owned by the maker, identified by its launcher id.
+    Nft(Bytes32),
+}
+
+/// An asset that the maker of an offer requests in return.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RequestedAsset {
+    Xch(u64),
+    Cat {
+        asset_id: Bytes32,
+        amount: u64,
+    },
+    /// The info is needed to construct the NFT's puzzle and calculate its royalty.
+    Nft(Box<NftInfo<HashedPtr>>),
+}
+
+impl TransactionBuilder {
+    /// Creates an offer, which pays the requested assets to the change puzzle hash.
+    ///
+    /// The royalties of the requested NFTs are paid on top of the offered fungible assets,
+    /// and the royalties of the offered NFTs are requested on top of the requested fungible assets.
+    /// Once signed, the spend bundle can be converted into an [`Offer`].
+    pub fn make_offer(
+        self,
+        ctx: &mut SpendContext,
+        coins: &SpendableCoins,
+        offered: &[OfferedAsset],
+        requested: &[RequestedAsset],
+        constants: &AggSigConstants,
+    ) -> Result<UnsignedTransaction, DriverError> {
+        let puzzle_hash = self.change_puzzle_hash();
+
+        let offered_nfts = offered
+            .iter()
+            .filter_map(|asset| match asset {
+                OfferedAsset::Nft(launcher_id) => Some(launcher_id),
+                _ => None,
+            })
+            .map(|launcher_id| {
+                coins
+                    .nfts
+                    .get(launcher_id)
+                    .map(|nft| nft.info)
+                    .ok_or(DriverError::MissingCoin)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let requested_nfts: Vec<NftInfo<HashedPtr>> = requested
+            .iter()
+            .filter_map(|asset| match asset {
+                RequestedAsset::Nft(info) => Some(**info),
+                _ => None,
+            })
+            .collect();
+
+        if offered_nfts
+            .iter()
+            .chain(&requested_nfts)
+            .any(|info| !info.transfer_program.is_tradable())
+        {
+            return Err(DriverError::UntradableNft);
+        }
+
+        let requested_fungibles: Vec<(Option<Bytes32>, u64)> = requested
+            .iter()
+            .filter_map(|asset| match asset {
+                RequestedAsset::Xch(amount) => Some((None, *amount)),
+                RequestedAsset::Cat { asset_id, amount } => Some((Some(*asset_id), *amount)),
+                RequestedAsset::Nft(..) => None,
+            })
+            .collect();
+
+        let mut builder = self;
+
+        for asset in offered {
+            builder = match asset {
+                OfferedAsset::Xch(amount) => {
+                    let royalty =
+                        royalty_total(&royalties(&ctx.allocator, *amount, &requested_nfts)?);
+
+                    builder.action(Action::SendXch {
+                        puzzle_hash: SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(),
+                        amount: amount + royalty,
+                        memos: Vec::new(),
+                    })
+                }
+                OfferedAsset::Cat { asset_id, amount } => {
+                    let royalty =
+                        royalty_total(&royalties(&ctx.allocator, *amount, &requested_nfts)?);
+
+                    builder.action(Action::SendCat {
+                        asset_id: *asset_id,
+                        puzzle_hash: SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(),
+                        amount: amount + royalty,
+                        memos: Vec::new(),
+                    })
+                }
+                OfferedAsset::Nft(launcher_id) => {
+                    let trade_prices = requested_fungibles
+                        .iter()
+                        .map(|(asset_id, amount)| {
+                            Ok(TradePrice {
+                                amount: trade_price(*amount, offered_nfts.len())?,
+                                puzzle_hash: settlement_puzzle_hash(*asset_id),
+                            })
+                        })
+                        .collect::<Result<Vec<_>, DriverError>>()?;
+
+                    builder.lock_nft(*launcher_id, trade_prices)
+                }
+            };
+        }
+
+        let selected = builder.select(coins)?;
+        let settlement = ctx.settlement_payments_puzzle()?;
+        let mut offer = Offer::build(selected.coin_ids());
+
+        for asset in requested {
+            let (puzzle, amount) = match asset {
+                RequestedAsset::Xch(amount) => (settlement, *amount),
+                RequestedAsset::Cat { asset_id, amount } => (
+                    CatLayer::new(*asset_id, SettlementLayer).construct_puzzle(ctx)?,
+                    *amount,
+                ),
+                RequestedAsset::Nft(info) => {
+                    (info.into_layers(settlement).construct_puzzle(ctx)?, 1)
+                }
+            };
+
+            offer = offer.request(ctx, &puzzle, vec![hinted_payment(puzzle_hash, amount)])?;
+
+            if matches!(asset, RequestedAsset::Nft(..)) {
+                continue;
+            }
+
+            // The royalties of the offered NFTs are paid by the taker, out of the same asset.
+            for (info, payments) in
+                offered_nfts
+                    .iter()
+                    .zip(royalties(&ctx.allocator, amount, &offered_nfts)?)
+            {
+                if payments.is_empty() {
+                    continue;
+                }
+
+                offer = offer.request_with_nonce(ctx, &puzzle, info.launcher_id, payments)?;
+            }
+        }
+
+        let (assertions, partial) = offer.finish();
+
+        let mut tx = builder
+            .conditions(Conditions::new().extend(assertions))
+            .build_selected(ctx, &selected, constants)?;
+
+        tx.spend_bundle = partial.bundle(ctx, tx.spend_bundle)?.into();
+
+        Ok(tx)
+    }
+
+    /// Accepts an offer, by paying the requested assets and claiming the offered assets to the change puzzle hash.
+    ///
+    /// The offer must have been parsed with the [`SpendContext`]'s allocator. The spend bundle includes the
+    /// maker's coin spends and signature, so only the required signatures need to be added to it.
+    pub fn take_offer(
+        self,
+        ctx: &mut SpendContext,
+        coins: &SpendableCoins,
+        offer: ParsedOffer,
+        constants: &AggSigConstants,
+    ) -> Result<UnsignedTransaction, DriverError> {
+        let puzzle_hash = self.change_puzzle_hash();
+        let offered = OfferedCoins::parse(ctx, &offer)?;
+
+        let mut requested_fungibles = Vec::new();
+        let mut requested_nfts = Vec::new();
+
+        for (payment_puzzle_hash, (puzzle, notarized_payments)) in &offer.requested_payments {
+            let amount: u64 = notarized_payments
+                .iter()
+                .flat_map(|notarized_payment| &notarized_payment.payments)
+                .map(|payment| payment.amount)
+                .sum();
+
+            if *payment_puzzle_hash == SETTLEMENT_PAYMENTS_PUZZLE_HASH.into() {
+                requested_fungibles.push((None, amount, notarized_payments.clone()));
+            } else if let Some(cat) = CatLayer::<Puzzle>::parse_puzzle(&ctx.allocator, *puzzle)?
+                .filter(|cat| {
+                    cat.inner_puzzle.curried_puzzle_hash() == SETTLEMENT_PAYMENTS_PUZZLE_HASH
+                })
+            {
+                requested_fungibles.push((Some(cat.asset_id), amount, notarized_payments.clone()));
+            } else if let Some((info, _)) = NftInfo::<HashedPtr>::parse(&ctx.allocator, *puzzle)?
+                .filter(|(info, _)| info.p2_puzzle_hash == SETTLEMENT_PAYMENTS_PUZZLE_HASH.into())
+            {
+                requested_nfts.push((info, notarized_payments.clone()));
+            } else {
+                return Err(DriverError::UnsupportedAsset);
+            }
+        }
+
+        let nft_infos: Vec<NftInfo<HashedPtr>> =
+            requested_nfts.iter().map(|(info, _)| *info).collect();
+
+        // The maker pays the royalties of the requested NFTs out of the offered fungible assets,
+        // so the trade price is what's left after the royalties are deducted.
+        let offered_fungibles = offered
+            .fungible_amounts()
+            .into_iter()
+            .map(|(asset_id, total)| {
+                let amount = base_amount(&ctx.allocator, total, &nft_infos)?;
+                let royalties = royalties(&ctx.allocator, amount, &nft_infos)?;
+                let payout = total - royalty_total(&royalties);
+                Ok((asset_id, amount, royalties, payout))
+            })
+            .collect::<Result<Vec<_>, DriverError>>()?;
+
+        let mut builder = self;
+
+        for (asset_id, amount, _) in &requested_fungibles {
+            builder = builder.action(match asset_id {
+                None => Action::SendXch {
+                    puzzle_hash: SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(),
+                    amount: *amount,
+                    memos: Vec::new(),
+                },
+                Some(asset_id) => Action::SendCat {
+                    asset_id: *asset_id,
+                    puzzle_hash: SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(),
+                    amount: *amount,
+                    memos: Vec::new(),
+                },
+            });
+        }
+
+        for (info, _) in &requested_nfts {
+            let trade_prices = offered_fungibles
+                .iter()
+                .map(|(asset_id, amount, _, _)| {
+                    Ok(TradePrice {
+                        amount: trade_price(*amount, nft_infos.len())?,
+                        puzzle_hash: settlement_puzzle_hash(*asset_id),
+                    })
+                })
+                .collect::<Result<Vec<_>, DriverError>>()?;
+
+            builder = builder.lock_nft(info.launcher_id, trade_prices);
+        }
+
+        let selected = builder.select(coins)?;
+        let nonce = Offer::nonce(selected.coin_ids());
+
+        let receive = |amount: u64| NotarizedPayment {
+            nonce,
+            payments: vec![hinted_payment(puzzle_hash, amount)],
+        };
+
+        let mut assertions = Conditions::new();
+
+        for (asset_id, _, _, payout) in &offered_fungibles {
+            if *payout > 0 {
+                assertions = assertions.with(payment_assertion(
+                    settlement_puzzle_hash(*asset_id),
+                    &receive(*payout),
+                ));
+            }
+        }
+
+        for nft in &offered.nfts {
+            assertions = assertions.with(payment_assertion(nft.coin.puzzle_hash, &receive(1)));
+        }
+
+        let mut tx = builder
+            .conditions(assertions)
+            .build_selected(ctx, &selected, constants)?;
+
+        // Fulfill the requested payments with the coins that were just sent to the settlement puzzle.
+        for (asset_id, _, notarized_payments) in requested_fungibles {
+            let solution = SettlementPaymentsSolution { notarized_payments };
+
+            if let Some(asset_id) = asset_id {
+                let cat = tx
+                    .sent_cats
+                    .iter()
+                    .find(|cat| cat.asset_id == asset_id)
+                    .copied()
+                    .ok_or(DriverError::MissingCoin)?;
+                let inner_spend = SettlementLayer.construct_spend(ctx, solution)?;
+                Cat::spend_all(ctx, &[CatSpend::new(cat, inner_spend)])?;
+            } else {
+                let coin = tx
+                    .sent_coins
+                    .iter()
+                    .find(|coin| coin.puzzle_hash == SETTLEMENT_PAYMENTS_PUZZLE_HASH.into())
+                    .copied()
+                    .ok_or(DriverError::MissingCoin)?;
+                let coin_spend = SettlementLayer.construct_coin_spend(ctx, coin, solution)?;
+                ctx.insert(coin_spend);
+            }
+        }
+
+        for (info, notarized_payments) in requested_nfts {
+            let nft = tx
+                .locked_nfts
+                .iter()
+                .find(|nft| nft.info.launcher_id == info.launcher_id)
+                .copied()
+                .ok_or(DriverError::MissingCoin)?;
+            let _nft = nft.unlock_settlement(ctx, notarized_payments)?;
+        }
+
+        // Claim the offered assets, and pay the royalties of the requested NFTs out of them.
+        for (asset_id, _, royalties, payout) in offered_fungibles {
+            let mut notarized_payments = Vec::new();
+
+            if payout > 0 {
+                notarized_payments.push(receive(payout));
+            }
+
+            for (info, payments) in nft_infos.iter().zip(royalties) {
+                if payments.is_empty() {
+                    continue;
+                }
+
+                notarized_payments.push(NotarizedPayment {
+                    nonce: info.launcher_id,
+                    payments,
+                });
+            }
+
+            offered.claim(ctx, asset_id, notarized_payments)?;
+        }
+
+        for nft in offered.nfts {
+            let _nft = nft.unlock_settlement(ctx, vec![receive(1)])?;
+        }
+
+        tx.spend_bundle.coin_spends.extend(ctx.take());
+        tx.spend_bundle = SpendBundle::aggregate(&[
+            SpendBundle::new(offer.coin_spends, offer.aggregated_signature),
+            tx.spend_bundle,
+        ]);
+
+        Ok(tx)
+    }
+}
+
+/// The coins that the maker of an offer locked into the settlement payments puzzle.
+#[derive(Debug, Default, Clone)]
+pub(super) struct OfferedCoins {
+    pub(super) xch: Vec<Coin>,
+    pub(super) cats: IndexMap<Bytes32, Vec<Cat>>,
+    pub(super) nfts: Vec<Nft<HashedPtr>>,
+}
+
+impl OfferedCoins {
+    pub(super) fn parse(ctx: &mut SpendContext, offer: &ParsedOffer) -> Result<Self, DriverError> {
+        let registry = AssetRegistry::new();
+        let mut offered = Self::default();
+
+        for coin_spend in &offer.coin_spends {
+            let puzzle = coin_spend.puzzle_reveal.to_clvm(&mut ctx.allocator)?;
+            let solution = coin_spend.solution.to_clvm(&mut ctx.allocator)?;
+            let output = run_puzzle(&mut ctx.allocator, puzzle, solution)?;
+            let conditions = Vec::<Condition>::from_clvm(&ctx.allocator, output)?;
+
+            for condition in conditions {
+                let Condition::CreateCoin(create_coin) = condition else {
+                    continue;
+                };
+
+                let coin = Coin::new(
+                    coin_spend.coin.coin_id(),
+                    create_coin.puzzle_hash,
+                    create_coin.amount,
+                );
+
+                match registry.decode_child(&mut ctx.allocator, coin_spend, coin)? {
+                    DecodedAsset::Xch(coin) if is_settlement(coin.puzzle_hash) => {
+                        offered.xch.push(coin);
+                    }
+                    DecodedAsset::Cat(cat) if is_settlement(cat.p2_puzzle_hash) => {
+                        offered.cats.entry(cat.asset_id).or_default().push(cat);
+                    }
+                    DecodedAsset::Nft(nft) if is_settlement(nft.info.p2_puzzle_hash) => {
+                        offered.nfts.push(nft);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        Ok(offered)
+    }
+
+    /// Adds the coins offered by another offer.
+    pub(super) fn extend(&mut self, other: Self) {
+        self.xch.extend(other.xch);
+
+        for (asset_id, cats) in other.cats {
+            self.cats.entry(asset_id).or_default().extend(cats);
+        }
+
+        self.nfts.extend(other.nfts);
+    }
+
+    pub(super) fn coin_ids(&self) -> Vec<Bytes32> {
+        self.xch
+            .iter()
+            .copied()
+            .chain(self.cats.values().flatten().map(|cat| cat.coin))
+            .chain(self.nfts.iter().map(|nft| nft.coin))
+            .map(|coin| coin.coin_id())
+            .collect()
+    }
+
+    pub(super) fn fungible_amounts(&self) -> Vec<(Option<Bytes32>, u64)> {
+        let xch = (!self.xch.is_empty())
+            .then(|| (None, self.xch.iter().map(|coin| coin.amount).sum()))
+            .into_iter();
+
+        let cats = self.cats.iter().map(|(asset_id, cats)| {
+            (
+                Some(*asset_id),
+                cats.iter().map(|cat| cat.coin.amount).sum(),
+            )
+        });
+
+        xch.chain(cats).collect()
+    }
+
+    /// Spends the settlement coins of an asset, with the first coin paying out all of the payments.
+    pub(super) fn claim(
+        &self,
+        ctx: &mut SpendContext,
+        asset_id: Option<Bytes32>,
+        notarized_payments: Vec<NotarizedPayment>,
+    ) -> Result<(), DriverError> {
+        let mut notarized_payments = Some(notarized_payments);
+        let mut next_solution = || SettlementPaymentsSolution {
+            notarized_payments: notarized_payments.take().unwrap_or_default(),
+        };
+
+        let Some(asset_id) = asset_id else {
+            for coin in &self.xch {
+                let coin_spend =
+                    SettlementLayer.construct_coin_spend(ctx, *coin, next_solution())?;
+                ctx.insert(coin_spend);
+            }
+            return Ok(());
+        };
+
+        let mut cat_spends = Vec::new();
+
+        for cat in &self.cats[&asset_id] {
+            let inner_spend = SettlementLayer.construct_spend(ctx, next_solution())?;
+            cat_spends.push(CatSpend::new(*cat, inner_spend));
+        }
+
+        Cat::spend_all(ctx, &cat_spends)
+    }
+}
+
+fn is_settlement(puzzle_hash: Bytes32) -> bool {
+    puzzle_hash == SETTLEMENT_PAYMENTS_PUZZLE_HASH.into()
+}
+
+/// The puzzle hash of the settlement payments puzzle for XCH, or the CAT with the given asset id.
+pub(super) fn settlement_puzzle_hash(asset_id: Option<Bytes32>) -> Bytes32 {
+    match asset_id {
+        Some(asset_id) => {
+            CatArgs::curry_tree_hash(asset_id, SETTLEMENT_PAYMENTS_PUZZLE_HASH).into()
+        }
+        None => SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(),
+    }
+}
+
+pub(super) fn hinted_payment(puzzle_hash: Bytes32, amount: u64) -> Payment {
+    Payment::with_memos(puzzle_hash, amount, vec![puzzle_hash.into()])
+}
+
+fn trade_price(amount: u64, nft_count: usize) -> Result<u64, DriverError> {
+    calculate_nft_trace_price(amount, nft_count).ok_or(DriverError::InvalidRoyalty)
+}
+
+/// Splits the amount evenly between the NFTs, and calculates the royalty payments of each.
+fn royalties(
+    allocator: &Allocator,
+    amount: u64,
+    nfts: &[NftInfo<HashedPtr>],
+) -> Result<Vec<Vec<Payment>>, DriverError> {
+    if nfts.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let trade_price = trade_price(amount, nfts.len())?;
+
+    nfts.iter()
+        .map(|info| info.royalty_payments(allocator, trade_price))
+        .collect()
+}
+
+fn royalty_total(royalties: &[Vec<Payment>]) -> u64 {
+    royalties
+        .iter()
+        .flatten()
+        .map(|payment| payment.amount)
+        .sum()
+}
+
+/// Finds the largest amount that can be paid out of the total, along with the royalties of the NFTs.
+fn base_amount(
+    allocator: &Allocator,
+    total: u64,
+    nfts: &[NftInfo<HashedPtr>],
+) -> Result<u64, DriverError> {
+    let (mut low, mut high) = (0, total);
+
+    while low < high {
+        let mid = low + (high - low).div_ceil(2);
+        let royalty = royalty_total(&royalties(allocator, mid, nfts)?);
+
+        if mid.checked_add(royalty).is_some_and(|sum| sum <= total) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+
+    Ok(low)
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_bls::SecretKey;
+    use chia_puzzles::nft::NftMetadata;
+    use chia_sdk_test::Simulator;
+
+    use crate::{
+        offers::test_utils::{balance, constants, sign_tx},
+        NftMint, NftTransferProgram, RoyaltySplit, SpendableCoins,
+    };
+
+    use super::*;
+
+    fn cat_puzzle_hash(asset_id: Bytes32, p2_puzzle_hash: Bytes32) -> Bytes32 {
+        CatArgs::curry_tree_hash(asset_id, p2_puzzle_hash.into()).into()
+    }
+
+    fn issue_cat(
+        sim: &mut Simulator,
+        ctx: &mut SpendContext,
+        sk: &SecretKey,
+        coin: Coin,
+        amount: u64,
+    ) -> anyhow::Result<Cat> {
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(coin);
+
+        let tx = TransactionBuilder::new(coin.puzzle_hash)
+            .key(sk.public_key())
+            .issue_cat(coin.puzzle_hash, amount)
+            .build(ctx, &coins, &constants())?;
+
+        sim.spend_coins(tx.spend_bundle.coin_spends, &[sk.clone()])?;
+
+        Ok(tx.issued_cats[0])
+    }
+
+    #[test]
+    fn test_cat_for_xch() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (alice_sk, _, alice_puzzle_hash, alice_coin) = sim.child_p2(1000, 1)?;
+        let (bob_sk, _, bob_puzzle_hash, bob_coin) = sim.child_p2(2000, 2)?;
+
+        let cat = issue_cat(&mut sim, ctx, &alice_sk, alice_coin, 1000)?;
+
+        let mut alice_coins = SpendableCoins::new();
+        alice_coins.add_cat(cat);
+
+        let tx = TransactionBuilder::new(alice_puzzle_hash)
+            .key(alice_sk.public_key())
+            .make_offer(
+                ctx,
+                &alice_coins,
+                &[OfferedAsset::Cat {
+                    asset_id: cat.asset_id,
+                    amount: 400,
+                }],
+                &[RequestedAsset::Xch(1500)],
+                &constants(),
+            )?;
+
+        let offer = Offer::from(sign_tx(&tx, &alice_sk)).parse(&mut ctx.allocator)?;
+
+        let mut bob_coins = SpendableCoins::new();
+        bob_coins.add_xch(bob_coin);
+
+        let tx = TransactionBuilder::new(bob_puzzle_hash)
+            .key(bob_sk.public_key())
+            .fee(100)
+            .take_offer(ctx, &bob_coins, offer, &constants())?;
+
+        sim.new_transaction(sign_tx(&tx, &bob_sk))?;
+
+        assert_eq!(balance(&sim, alice_puzzle_hash), 1500);
+        assert_eq!(
+            balance(&sim, cat_puzzle_hash(cat.asset_id, alice_puzzle_hash)),
+            600
+        );
+        assert_eq!(balance(&sim, bob_puzzle_hash), 400);
+        assert_eq!(
+            balance(&sim, cat_puzzle_hash(cat.asset_id, bob_puzzle_hash)),
+            400
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cat_for_cat() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (alice_sk, _, alice_puzzle_hash, alice_coin) = sim.child_p2(1000, 1)?;
+        let (bob_sk, _, bob_puzzle_hash, bob_coin) = sim.child_p2(500, 2)?;
+
+        let alice_cat = issue_cat(&mut sim, ctx, &alice_sk, alice_coin, 1000)?;
+        let bob_cat = issue_cat(&mut sim, ctx, &bob_sk, bob_coin, 500)?;
+
+        let mut alice_coins = SpendableCoins::new();
+        alice_coins.add_cat(alice_cat);
+
+        let tx = TransactionBuilder::new(alice_puzzle_hash)
+            .key(alice_sk.public_key())
+            .make_offer(
+                ctx,
+                &alice_coins,
+                &[OfferedAsset::Cat {
+                    asset_id: alice_cat.asset_id,
+                    amount: 1000,
+                }],
+                &[RequestedAsset::Cat {
+                    asset_id: bob_cat.asset_id,
+                    amount: 250,
+                }],
+                &constants(),
+            )?;
+
+        let offer = Offer::from(sign_tx(&tx, &alice_sk)).parse(&mut ctx.allocator)?;
+
+        let mut bob_coins = SpendableCoins::new();
+        bob_coins.add_cat(bob_cat);
+
+        let tx = TransactionBuilder::new(bob_puzzle_hash)
+            .key(bob_sk.public_key())
+            .take_offer(ctx, &bob_coins, offer, &constants())?;
+
+        sim.new_transaction(sign_tx(&tx, &bob_sk))?;
+
+        for (puzzle_hash, alice_amount, bob_amount) in
+            [(alice_puzzle_hash, 0, 250), (bob_puzzle_hash, 1000, 250)]
+        {
+            assert_eq!(
+                balance(&sim, cat_puzzle_hash(alice_cat.asset_id, puzzle_hash)),
+                alice_amount
+            );
+            assert_eq!(
+                balance(&sim, cat_puzzle_hash(bob_cat.asset_id, puzzle_hash)),
+                bob_amount
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nft_royalties() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (alice_sk, _, alice_puzzle_hash, alice_coin) = sim.child_p2(10_001, 1)?;
+        let (bob_sk, _, bob_puzzle_hash, bob_coin) = sim.child_p2(5000, 2)?;
+        let royalty_puzzle_hash = Bytes32::new([42; 32]);
+
+        let cat = issue_cat(&mut sim, ctx, &bob_sk, bob_coin, 5000)?;
+        let bob_xch = sim.new_coin(bob_puzzle_hash, 5000);
+
+        let mut alice_coins = SpendableCoins::new();
+        alice_coins.add_xch(alice_coin);
+
+        let tx = TransactionBuilder::new(alice_puzzle_hash)
+            .key(alice_sk.public_key())
+            .mint_nft(
+                NftMint {
+                    royalty_puzzle_hash,
+                    ..NftMint::new(NftMetadata::default(), alice_puzzle_hash, 300, None)
+                },
+                None,
+            )
+            .build(ctx, &alice_coins, &constants())?;
+
+        sim.new_transaction(sign_tx(&tx, &alice_sk))?;
+
+        let nft = tx.minted_nfts[0].clone();
+        let metadata = ctx.alloc(&nft.info.metadata)?;
+        let nft = nft.with_metadata(HashedPtr::from_ptr(&ctx.allocator, metadata));
+        let alice_xch = Coin::new(alice_coin.coin_id(), alice_puzzle_hash, 10_000);
+
+        // Alice offers the NFT for XCH and a CAT, and Bob pays the 3% royalty in both.
+        let mut alice_coins = SpendableCoins::new();
+        alice_coins.add_nft(nft);
+
+        let tx = TransactionBuilder::new(alice_puzzle_hash)
+            .key(alice_sk.public_key())
+            .make_offer(
+                ctx,
+                &alice_coins,
+                &[OfferedAsset::Nft(nft.info.launcher_id)],
+                &[
+                    RequestedAsset::Xch(1000),
+                    RequestedAsset::Cat {
+                        asset_id: cat.asset_id,
+                        amount: 2000,
+                    },
+                ],
+                &constants(),
+            )?;
+
+        let locked_nft = tx.locked_nfts[0];
+        let offer = Offer::from(sign_tx(&tx, &alice_sk)).parse(&mut ctx.allocator)?;
+
+        let mut bob_coins = SpendableCoins::new();
+        bob_coins.add_xch(bob_xch);
+        bob_coins.add_cat(cat);
+
+        let tx = TransactionBuilder::new(bob_puzzle_hash)
+            .key(bob_sk.public_key())
+            .take_offer(ctx, &bob_coins, offer, &constants())?;
+
+        sim.new_transaction(sign_tx(&tx, &bob_sk))?;
+
+        assert_eq!(balance(&sim, royalty_puzzle_hash), 30);
+        assert_eq!(
+            balance(&sim, cat_puzzle_hash(cat.asset_id, royalty_puzzle_hash)),
+            60
+        );
+        assert_eq!(balance(&sim, alice_puzzle_hash), 11_000);
+        assert_eq!(
+            balance(&sim, cat_puzzle_hash(cat.asset_id, alice_puzzle_hash)),
+            2000
+        );
+
+        let locked_spend = tx
+            .spend_bundle
+            .coin_spends
+            .iter()
+            .find(|coin_spend| coin_spend.coin == locked_nft.coin)
+            .expect("missing locked nft spend");
+        let child = sim.children(locked_nft.coin.coin_id())[0].coin;
+
+        let DecodedAsset::Nft(nft) =
+            AssetRegistry::new().decode_child(&mut ctx.allocator, locked_spend, child)?
+        else {
+            panic!("expected nft");
+        };
+        assert_eq!(nft.info.p2_puzzle_hash, bob_puzzle_hash);
+
+        // Alice offers XCH to buy the NFT back, and pays the royalty on top of the price.
+        let mut alice_coins = SpendableCoins::new();
+        alice_coins.add_xch(alice_xch);
+
+        let tx = TransactionBuilder::new(alice_puzzle_hash)
+            .key(alice_sk.public_key())
+            .make_offer(
+                ctx,
+                &alice_coins,
+                &[OfferedAsset::Xch(2000)],
+                &[RequestedAsset::Nft(Box::new(nft.info))],
+                &constants(),
+            )?;
+
+        let offer = Offer::from(sign_tx(&tx, &alice_sk)).parse(&mut ctx.allocator)?;
+
+        let mut bob_coins = SpendableCoins::new();
+        bob_coins.add_nft(nft);
+
+        let tx = TransactionBuilder::new(bob_puzzle_hash)
+            .key(bob_sk.public_key())
+            .take_offer(ctx, &bob_coins, offer, &constants())?;
+
+        sim.new_transaction(sign_tx(&tx, &bob_sk))?;
+
+        assert_eq!(balance(&sim, royalty_puzzle_hash), 90);
+        assert_eq!(balance(&sim, alice_puzzle_hash), 8940);
+        assert_eq!(balance(&sim, bob_puzzle_hash), 2000 + 3970);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nft_royalty_split() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (alice_sk, _, alice_puzzle_hash, alice_coin) = sim.child_p2(10_001, 1)?;
+        let (bob_sk, _, bob_puzzle_hash, bob_coin) = sim.child_p2(5000, 2)?;
+        let creator_puzzle_hash = Bytes32::new([42; 32]);
+        let artist_puzzle_hash = Bytes32::new([43; 32]);
+
+        let mut alice_coins = SpendableCoins::new();
+        alice_coins.add_xch(alice_coin);
+
+        let transfer_program = NftTransferProgram::royalty_split(
+            ctx,
+            &[
+                RoyaltySplit::new(creator_puzzle_hash, 200),
+                RoyaltySplit::new(artist_puzzle_hash, 100),
+            ],
+        )?;
+
+        let tx = TransactionBuilder::new(alice_puzzle_hash)
+            .key(alice_sk.public_key())
+            .mint_nft(
+                NftMint::new(NftMetadata::default(), alice_puzzle_hash, 0, None)
+                    .with_transfer_program(transfer_program),
+                None,
+            )
+            .build(ctx, &alice_coins, &constants())?;
+
+        sim.new_transaction(sign_tx(&tx, &alice_sk))?;
+
+        let nft = tx.minted_nfts[0].clone();
+        let metadata = ctx.alloc(&nft.info.metadata)?;
+        let nft = nft.with_metadata(HashedPtr::from_ptr(&ctx.allocator, metadata));
+        let alice_xch = Coin::new(alice_coin.coin_id(), alice_puzzle_hash, 10_000);
+
+        // Alice offers the NFT for XCH, and Bob pays each of the royalties on top of the price.
+        let mut alice_coins = SpendableCoins::new();
+        alice_coins.add_nft(nft);
+
+        let tx = TransactionBuilder::new(alice_puzzle_hash)
+            .key(alice_sk.public_key())
+            .make_offer(
+                ctx,
+                &alice_coins,
+                &[OfferedAsset::Nft(nft.info.launcher_id)],
+                &[RequestedAsset::Xch(1000)],
+                &constants(),
+            )?;
+
+        let locked_nft = tx.locked_nfts[0];
+        let offer = Offer::from(sign_tx(&tx, &alice_sk)).parse(&mut ctx.allocator)?;
+
+        let mut bob_coins = SpendableCoins::new();
+        bob_coins.add_xch(bob_coin);
+
+        let tx = TransactionBuilder::new(bob_puzzle_hash)
+            .key(bob_sk.public_key())
+            .take_offer(ctx, &bob_coins, offer, &constants())?;
+
+        sim.new_transaction(sign_tx(&tx, &bob_sk))?;
+
+        assert_eq!(balance(&sim, creator_puzzle_hash), 20);
+        assert_eq!(balance(&sim, artist_puzzle_hash), 10);
+        assert_eq!(balance(&sim, bob_puzzle_hash), 3970);
+
+        let locked_spend = tx
+            .spend_bundle
+            .coin_spends
+            .iter()
+            .find(|coin_spend| coin_spend.coin == locked_nft.coin)
+            .expect("missing locked nft spend");
+        let child = sim.children(locked_nft.coin.coin_id())[0].coin;
+
+        let DecodedAsset::Nft(nft) =
+            AssetRegistry::new().decode_child(&mut ctx.allocator, locked_spend, child)?
+        else {
+            panic!("expected nft");
+        };
+        assert_eq!(nft.info.transfer_program, locked_nft.info.transfer_program);
+
+        // Alice offers XCH to buy the NFT back, and pays the royalties on top of the price.
+
+        let mut alice_coins = SpendableCoins::new();
+        alice_coins.add_xch(alice_xch);
+
+        let tx = TransactionBuilder::new(alice_puzzle_hash)
+            .key(alice_sk.public_key())
+            .make_offer(
+                ctx,
+                &alice_coins,
+                &[OfferedAsset::Xch(2000)],
+                &[RequestedAsset::Nft(Box::new(nft.info))],
+                &constants(),
+            )?;
+
+        let offer = Offer::from(sign_tx(&tx, &alice_sk)).parse(&mut ctx.allocator)?;
+
+        let mut bob_coins = SpendableCoins::new();
+        bob_coins.add_nft(nft);
+
+        let tx = TransactionBuilder::new(bob_puzzle_hash)
+            .key(bob_sk.public_key())
+            .take_offer(ctx, &bob_coins, offer, &constants())?;
+
+        sim.new_transaction(sign_tx(&tx, &bob_sk))?;
+
+        assert_eq!(balance(&sim, creator_puzzle_hash), 20 + 40);
+        assert_eq!(balance(&sim, artist_puzzle_hash), 10 + 20);
+        assert_eq!(balance(&sim, alice_puzzle_hash), 11_000 - 2060);
+        assert_eq!(balance(&sim, bob_puzzle_hash), 3970 + 2000);
+
+        Ok(())
+    }
+}
@@ -18,24 +18,61 @@ use once_cell::sync::Lazy;
 
 use crate::OfferError;
 
-static COMPRESSION_ZDICT: Lazy<Vec<u8>> = Lazy::new(|| {
-    let mut bytes = Vec::new();
-    bytes.extend_from_slice(&STANDARD_PUZZLE);
-    bytes.extend_from_slice(&CAT_PUZZLE_V1);
-    bytes.extend_from_slice(&SETTLEMENT_PAYMENTS_PUZZLE_V1);
-    bytes.extend_from_slice(&SINGLETON_TOP_LAYER_PUZZLE);
-    bytes.extend_from_slice(&NFT_STATE_LAYER_PUZZLE);
-    bytes.extend_from_slice(&NFT_OWNERSHIP_LAYER_PUZZLE);
-    bytes.extend_from_slice(&NFT_METADATA_UPDATER_PUZZLE);
-    bytes.extend_from_slice(&NFT_ROYALTY_TRANSFER_PUZZLE);
-    bytes.extend_from_slice(&CAT_PUZZLE);
-    bytes.extend_from_slice(&SETTLEMENT_PAYMENTS_PUZZLE);
-    bytes
+/// The compression version used by default, which is the latest one.
+pub const LATEST_OFFER_COMPRESSION_VERSION: u16 = 6;
+
+/// The dictionary for each compression version, which is built from the puzzles of every version up to it.
+static COMPRESSION_ZDICTS: Lazy<Vec<Vec<u8>>> = Lazy::new(|| {
+    let puzzles: [&[&[u8]]; LATEST_OFFER_COMPRESSION_VERSION as usize] = [
+        &[&STANDARD_PUZZLE, &CAT_PUZZLE_V1],
+        &[&SETTLEMENT_PAYMENTS_PUZZLE_V1],
+        &[
+            &SINGLETON_TOP_LAYER_PUZZLE,
+            &NFT_STATE_LAYER_PUZZLE,
+            &NFT_OWNERSHIP_LAYER_PUZZLE,
+            &NFT_METADATA_UPDATER_PUZZLE,
+            &NFT_ROYALTY_TRANSFER_PUZZLE,
+        ],
+        &[&CAT_PUZZLE],
+        &[&SETTLEMENT_PAYMENTS_PUZZLE],
+        // Version 6 has the same dictionary as version 5, to intentionally break compatibility with older clients.
+        &[],
+    ];
+
+    let mut zdicts = vec![Vec::new()];
+
+    for puzzles in puzzles {
+        let mut zdict = zdicts[zdicts.len() - 1].clone();
+
+        for puzzle in puzzles {
+            zdict.extend_from_slice(puzzle);
+        }
+
+        zdicts.push(zdict);
+    }
+
+    zdicts
 });
 
+fn zdict_for_version(version: u16) -> Result<&'static [u8], OfferError> {
+    COMPRESSION_ZDICTS
+        .get(usize::from(version))
+        .map(Vec::as_slice)
+        .ok_or(OfferError::UnsupportedVersion)
+}
+
 pub fn compress_offer_bytes(bytes: &[u8]) -> Result<Vec<u8>, OfferError> {
-    let mut output = 6u16.to_be_bytes().to_vec();
-    output.extend(zlib_compress(bytes, &COMPRESSION_ZDICT)?);
+    compress_offer_bytes_with_version(bytes, LATEST_OFFER_COMPRESSION_VERSION)
+}
+
+/// Compresses the offer with the dictionary of an older version, so that it can be decompressed by older clients.
+pub fn compress_offer_bytes_with_version(
+    bytes: &[u8],
+    version: u16,
+) -> Result<Vec<u8>, OfferError> {
+    let zdict = zdict_for_version(version)?;
+    let mut output = version.to_be_bytes().to_vec();
+    output.extend(zlib_compress(bytes, zdict)?);
     Ok(output)
 }
 
@@ -47,16 +84,16 @@ pub fn decompress_offer_bytes(bytes: &[u8]) -> Result<Vec<u8>, OfferError> {
 
     let version = u16::from_be_bytes(version_bytes);
 
-    if version > 6 {
-        return Err(OfferError::UnsupportedVersion);
-    }
-
-    zlib_decompress(&bytes[2..], &COMPRESSION_ZDICT)
+    zlib_decompress(&bytes[2..], zdict_for_version(version)?)
 }
 
 fn zlib_compress(input: &[u8], zdict: &[u8]) -> std::io::Result<Vec<u8>> {
     let mut compress = Compress::new(Compression::new(6), true);
-    compress.set_dictionary(zdict)?;
+
+    if !zdict.is_empty() {
+        compress.set_dictionary(zdict)?;
+    }
+
     let mut encoder = ZlibEncoder::new_with_compress(input, compress);
     let mut output = Vec::new();
     encoder.read_to_end(&mut output)?;
@@ -64,16 +101,25 @@ fn zlib_compress(input: &[u8], zdict: &[u8]) -> std::io::Result<Vec<u8>> {
 }
 
 fn zlib_decompress(input: &[u8], zdict: &[u8]) -> Result<Vec<u8>, OfferError> {
+    // Uncompressed input, such as a serialized spend bundle, doesn't start with a zlib header.
+    if !has_zlib_header(input) {
+        return Err(OfferError::NotCompressed);
+    }
+
     let mut decompress = Decompress::new(true);
 
-    if decompress
-        .decompress(input, &mut [], FlushDecompress::Finish)
-        .is_ok()
-    {
-        return Err(OfferError::NotCompressed);
+    // The input must need the dictionary, otherwise it was compressed without one.
+    if !zdict.is_empty() {
+        if decompress
+            .decompress(input, &mut [], FlushDecompress::Finish)
+            .is_ok()
+        {
+            return Err(OfferError::NotCompressed);
+        }
+
+        decompress.set_dictionary(zdict)?;
     }
 
-    decompress.set_dictionary(zdict)?;
     let i = decompress.total_in();
     let mut decoder = ZlibDecoder::new_with_decompress(&input[usize::try_from(i)?..], decompress);
     let mut output = Vec::new();
@@ -81,10 +127,19 @@ fn zlib_decompress(input: &[u8], zdict: &[u8]) -> Result<Vec<u8>, OfferError> {
     Ok(output)
 }
 
+/// Checks for the deflate compression method and a valid header checksum.
+fn has_zlib_header(input: &[u8]) -> bool {
+    match input {
+        [cmf, flg, ..] => cmf & 0x0f == 8 && u16::from_be_bytes([*cmf, *flg]) % 31 == 0,
+        _ => false,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use chia_protocol::SpendBundle;
     use chia_traits::Streamable;
+    use rstest::rstest;
 
     use super::*;
 
@@ -102,6 +157,71 @@ mod tests {
         assert_eq!(hex::encode(output), DECOMPRESSED_OFFER.trim());
     }
 
+    // The versioned vectors were generated from `decompressed.offer` by `test_data/generate_compressed.py`,
+    // a copy of `compress_object_with_puzzles` from Chia's `chia/wallet/util/puzzle_compression.py`.
+    // Compressed bytes depend on the zlib build, so the vectors are only decompressed and round tripped.
+    #[rstest]
+    #[case(0, COMPRESSED_OFFER_V0)]
+    #[case(1, COMPRESSED_OFFER_V1)]
+    #[case(2, COMPRESSED_OFFER_V2)]
+    #[case(3, COMPRESSED_OFFER_V3)]
+    #[case(4, COMPRESSED_OFFER_V4)]
+    #[case(5, COMPRESSED_OFFER_V5)]
+    #[case(6, COMPRESSED_OFFER)]
+    fn test_versioned_compression(#[case] version: u16, #[case] compressed: &str) {
+        let compressed_offer = hex::decode(compressed.trim()).unwrap();
+        assert_eq!(compressed_offer[0..2], version.to_be_bytes());
+
+        let output = decompress_offer_bytes(&compressed_offer).unwrap();
+        assert_eq!(hex::encode(&output), DECOMPRESSED_OFFER.trim());
+
+        let recompressed = compress_offer_bytes_with_version(&output, version).unwrap();
+        assert_eq!(recompressed[0..2], version.to_be_bytes());
+        assert_eq!(decompress_offer_bytes(&recompressed).unwrap(), output);
+    }
+
+    #[test]
+    fn test_unsupported_version() {
+        let decompressed_offer = hex::decode(DECOMPRESSED_OFFER.trim()).unwrap();
+
+        assert!(matches!(
+            compress_offer_bytes_with_version(&decompressed_offer, 7),
+            Err(OfferError::UnsupportedVersion)
+        ));
+
+        let mut compressed_offer = hex::decode(COMPRESSED_OFFER.trim()).unwrap();
+        compressed_offer[0..2].copy_from_slice(&7u16.to_be_bytes());
+
+        assert!(matches!(
+            decompress_offer_bytes(&compressed_offer),
+            Err(OfferError::UnsupportedVersion)
+        ));
+    }
+
+    #[rstest]
+    fn test_not_compressed(#[values(0, 1, 2, 3, 4, 5, 6)] version: u16) {
+        let decompressed_offer = hex::decode(DECOMPRESSED_OFFER.trim()).unwrap();
+
+        let mut uncompressed = version.to_be_bytes().to_vec();
+        uncompressed.extend_from_slice(&decompressed_offer);
+
+        assert!(matches!(
+            decompress_offer_bytes(&uncompressed),
+            Err(OfferError::NotCompressed)
+        ));
+
+        // An offer compressed without a dictionary can't be decompressed as a version that uses one.
+        if version > 0 {
+            let mut compressed = compress_offer_bytes_with_version(&decompressed_offer, 0).unwrap();
+            compressed[0..2].copy_from_slice(&version.to_be_bytes());
+
+            assert!(matches!(
+                decompress_offer_bytes(&compressed),
+                Err(OfferError::NotCompressed)
+            ));
+        }
+    }
+
     #[test]
     fn parse_spend_bundle() {
         let decompressed_offer = hex::decode(DECOMPRESSED_OFFER.trim()).unwrap();
@@ -109,5 +229,11 @@ mod tests {
     }
 
     const COMPRESSED_OFFER: &str = include_str!("./test_data/compressed.offer");
+    const COMPRESSED_OFFER_V0: &str = include_str!("./test_data/compressed_v0.offer");
+    const COMPRESSED_OFFER_V1: &str = include_str!("./test_data/compressed_v1.offer");
+    const COMPRESSED_OFFER_V2: &str = include_str!("./test_data/compressed_v2.offer");
+    const COMPRESSED_OFFER_V3: &str = include_str!("./test_data/compressed_v3.offer");
+    const COMPRESSED_OFFER_V4: &str = include_str!("./test_data/compressed_v4.offer");
+    const COMPRESSED_OFFER_V5: &str = include_str!("./test_data/compressed_v5.offer");
     const DECOMPRESSED_OFFER: &str = include_str!("./test_data/decompressed.offer");
 }
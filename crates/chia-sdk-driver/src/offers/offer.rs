@@ -7,8 +7,9 @@ use clvmr::Allocator;
 use indexmap::IndexMap;
 
 use crate::{
-    compress_offer_bytes, decode_offer_data, decompress_offer_bytes, encode_offer_data, Make,
-    OfferBuilder, OfferError, ParsedOffer, Puzzle, Take,
+    compress_offer_bytes, compress_offer_bytes_with_version, decode_offer_data,
+    decompress_offer_bytes, encode_offer_data, Make, OfferBuilder, OfferError, ParsedOffer, Puzzle,
+    Take,
 };
 
 #[derive(Debug, Clone)]
@@ -46,6 +47,10 @@ impl Offer {
         compress_offer_bytes(&self.to_bytes()?)
     }
 
+    pub fn compress_with_version(&self, version: u16) -> Result<Vec<u8>, OfferError> {
+        compress_offer_bytes_with_version(&self.to_bytes()?, version)
+    }
+
     pub fn decompress(bytes: &[u8]) -> Result<Self, OfferError> {
         Self::from_bytes(&decompress_offer_bytes(bytes)?)
     }
@@ -54,6 +59,10 @@ impl Offer {
         encode_offer_data(&self.compress()?)
     }
 
+    pub fn encode_with_version(&self, version: u16) -> Result<String, OfferError> {
+        encode_offer_data(&self.compress_with_version(version)?)
+    }
+
     pub fn decode(text: &str) -> Result<Self, OfferError> {
         Self::decompress(&decode_offer_data(text)?)
     }
@@ -0,0 +1,215 @@
+use chia_bls::Signature;
+use chia_protocol::{Bytes32, SpendBundle};
+use chia_puzzles::offer::NotarizedPayment;
+use indexmap::IndexMap;
+
+use crate::{DriverError, Offer, ParsedOffer, SpendContext};
+
+use super::asset_offer::{hinted_payment, settlement_puzzle_hash, OfferedCoins};
+
+/// Settles several offers against each other, by paying the requested payments of each offer
+/// out of the assets locked in the settlement payments puzzle by the others.
+///
+/// Anything that's offered but not requested is paid to the surplus puzzle hash, so that arbitrage
+/// between the offers can be collected. The offers must have been parsed with the [`SpendContext`]'s allocator.
+///
+/// If the offers can't be settled, the [`SpendContext`] is left with the coin spends it had beforehand.
+pub fn settle_offers(
+    ctx: &mut SpendContext,
+    offers: Vec<ParsedOffer>,
+    surplus_puzzle_hash: Bytes32,
+) -> Result<SpendBundle, DriverError> {
+    let existing = ctx.take();
+
+    match settle(ctx, offers, surplus_puzzle_hash) {
+        Ok(spend_bundle) => Ok(SpendBundle::aggregate(&[
+            SpendBundle::new(existing, Signature::default()),
+            spend_bundle,
+        ])),
+        Err(error) => {
+            // Discard the partial coin spends, and restore the ones that were there beforehand.
+            ctx.take();
+
+            for coin_spend in existing {
+                ctx.insert(coin_spend);
+            }
+
+            Err(error)
+        }
+    }
+}
+
+fn settle(
+    ctx: &mut SpendContext,
+    offers: Vec<ParsedOffer>,
+    surplus_puzzle_hash: Bytes32,
+) -> Result<SpendBundle, DriverError> {
+    let mut offered = OfferedCoins::default();
+    let mut requested = IndexMap::<Bytes32, Vec<NotarizedPayment>>::new();
+    let mut spend_bundles = Vec::new();
+
+    for offer in offers {
+        offered.extend(OfferedCoins::parse(ctx, &offer)?);
+
+        let mut builder = offer.take();
+
+        while let Some((puzzle, notarized_payments)) = builder.fulfill() {
+            requested
+                .entry(puzzle.curried_puzzle_hash().into())
+                .or_default()
+                .extend(notarized_payments);
+        }
+
+        spend_bundles.push(builder.bundle(SpendBundle::new(Vec::new(), Signature::default())));
+    }
+
+    let nonce = Offer::nonce(offered.coin_ids());
+    let surplus = |amount: u64| NotarizedPayment {
+        nonce,
+        payments: vec![hinted_payment(surplus_puzzle_hash, amount)],
+    };
+
+    for (asset_id, total) in offered.fungible_amounts() {
+        let puzzle_hash = settlement_puzzle_hash(asset_id);
+        let mut notarized_payments = requested.shift_remove(&puzzle_hash).unwrap_or_default();
+
+        let amount: u64 = notarized_payments
+            .iter()
+            .flat_map(|notarized_payment| &notarized_payment.payments)
+            .map(|payment| payment.amount)
+            .sum();
+
+        if amount > total {
+            return Err(DriverError::UnfulfilledPayment(puzzle_hash));
+        }
+
+        if total > amount {
+            notarized_payments.push(surplus(total - amount));
+        }
+
+        offered.claim(ctx, asset_id, notarized_payments)?;
+    }
+
+    for nft in &offered.nfts {
+        let notarized_payments = requested
+            .shift_remove(&nft.coin.puzzle_hash)
+            .unwrap_or_else(|| vec![surplus(1)]);
+
+        // The NFT can only be sent to a single recipient.
+        let recipients = notarized_payments
+            .iter()
+            .flat_map(|notarized_payment| &notarized_payment.payments)
+            .filter(|payment| payment.amount % 2 == 1)
+            .count();
+
+        if recipients != 1 {
+            return Err(DriverError::UnfulfilledPayment(nft.coin.puzzle_hash));
+        }
+
+        let _nft = nft.unlock_settlement(ctx, notarized_payments)?;
+    }
+
+    if let Some(puzzle_hash) = requested.keys().next() {
+        return Err(DriverError::UnfulfilledPayment(*puzzle_hash));
+    }
+
+    spend_bundles.push(SpendBundle::new(ctx.take(), Signature::default()));
+
+    Ok(SpendBundle::aggregate(&spend_bundles))
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_puzzles::cat::CatArgs;
+    use chia_sdk_test::Simulator;
+
+    use crate::{
+        offers::test_utils::{balance, constants, sign_tx},
+        OfferedAsset, RequestedAsset, SpendableCoins, TransactionBuilder,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_settle_offers() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (alice_sk, _, alice_puzzle_hash, alice_coin) = sim.child_p2(1000, 1)?;
+        let (bob_sk, _, bob_puzzle_hash, bob_coin) = sim.child_p2(600, 2)?;
+        let surplus_puzzle_hash = Bytes32::new([42; 32]);
+
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(alice_coin);
+
+        let tx = TransactionBuilder::new(alice_puzzle_hash)
+            .key(alice_sk.public_key())
+            .issue_cat(alice_puzzle_hash, 1000)
+            .build(ctx, &coins, &constants())?;
+
+        sim.new_transaction(sign_tx(&tx, &alice_sk))?;
+
+        let cat = tx.issued_cats[0];
+        let cat_puzzle_hash = |puzzle_hash: Bytes32| -> Bytes32 {
+            CatArgs::curry_tree_hash(cat.asset_id, puzzle_hash.into()).into()
+        };
+
+        // Alice sells 1000 CAT for 500 XCH, and Bob buys 800 CAT for 600 XCH.
+        let mut coins = SpendableCoins::new();
+        coins.add_cat(cat);
+
+        let tx = TransactionBuilder::new(alice_puzzle_hash)
+            .key(alice_sk.public_key())
+            .make_offer(
+                ctx,
+                &coins,
+                &[OfferedAsset::Cat {
+                    asset_id: cat.asset_id,
+                    amount: 1000,
+                }],
+                &[RequestedAsset::Xch(500)],
+                &constants(),
+            )?;
+        let alice_offer = Offer::from(sign_tx(&tx, &alice_sk));
+
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(bob_coin);
+
+        let tx = TransactionBuilder::new(bob_puzzle_hash)
+            .key(bob_sk.public_key())
+            .make_offer(
+                ctx,
+                &coins,
+                &[OfferedAsset::Xch(600)],
+                &[RequestedAsset::Cat {
+                    asset_id: cat.asset_id,
+                    amount: 800,
+                }],
+                &constants(),
+            )?;
+        let bob_offer = Offer::from(sign_tx(&tx, &bob_sk));
+
+        // A single offer can't be settled on its own.
+        let offer = alice_offer.clone().parse(&mut ctx.allocator)?;
+        assert!(matches!(
+            settle_offers(ctx, vec![offer], surplus_puzzle_hash),
+            Err(DriverError::UnfulfilledPayment(..))
+        ));
+
+        // The partial coin spends aren't left behind.
+        assert_eq!(ctx.iter().count(), 0);
+
+        let offers = vec![
+            alice_offer.parse(&mut ctx.allocator)?,
+            bob_offer.parse(&mut ctx.allocator)?,
+        ];
+        sim.new_transaction(settle_offers(ctx, offers, surplus_puzzle_hash)?)?;
+
+        assert_eq!(balance(&sim, alice_puzzle_hash), 500);
+        assert_eq!(balance(&sim, cat_puzzle_hash(bob_puzzle_hash)), 800);
+        assert_eq!(balance(&sim, surplus_puzzle_hash), 100);
+        assert_eq!(balance(&sim, cat_puzzle_hash(surplus_puzzle_hash)), 200);
+
+        Ok(())
+    }
+}
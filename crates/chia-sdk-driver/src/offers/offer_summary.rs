@@ -0,0 +1,402 @@
+use chia_protocol::{Bytes32, Coin};
+use chia_puzzles::{
+    nft::NftMetadata,
+    offer::{SETTLEMENT_PAYMENTS_PUZZLE_HASH, SETTLEMENT_PAYMENTS_PUZZLE_HASH_V1},
+};
+use chia_sdk_types::{run_puzzle, Condition};
+use clvm_traits::{FromClvm, ToClvm};
+use clvmr::Allocator;
+use indexmap::IndexMap;
+
+use crate::{
+    calculate_nft_trace_price, AssetRegistry, Cat1Layer, CatLayer, DecodedAsset, DriverError,
+    HashedPtr, Layer, NftInfo, ParsedOffer, Puzzle,
+};
+
+/// An NFT on either side of an offer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OfferNft {
+    pub info: NftInfo<HashedPtr>,
+    /// The metadata, if it's in the standard NFT metadata format.
+    pub metadata: Option<NftMetadata>,
+}
+
+/// The assets on one side of an offer, grouped by asset.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OfferAssets {
+    pub xch: u64,
+    pub cats: IndexMap<Bytes32, u64>,
+    pub cat1s: IndexMap<Bytes32, u64>,
+    pub nfts: IndexMap<Bytes32, OfferNft>,
+    /// The puzzle hashes of requested payments whose puzzle isn't recognized.
+    pub unknown: Vec<Bytes32>,
+}
+
+/// A royalty that is implied by trading an NFT for fungible assets.
+/// If the asset id is [`None`], the royalty is paid in XCH.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OfferRoyalty {
+    pub launcher_id: Bytes32,
+    pub royalty_puzzle_hash: Bytes32,
+    pub asset_id: Option<Bytes32>,
+    pub amount: u64,
+}
+
+/// The timelocks that the maker's coin spends must be confirmed before.
+/// If either is reached, the offer can no longer be accepted.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct OfferExpiration {
+    pub height: Option<u32>,
+    pub seconds: Option<u64>,
+}
+
+impl OfferExpiration {
+    /// Whether the offer can no longer be included in the next block after the peak.
+    pub fn is_expired(&self, peak_height: u32, peak_timestamp: u64) -> bool {
+        self.height.is_some_and(|height| peak_height + 1 >= height)
+            || self
+                .seconds
+                .is_some_and(|seconds| peak_timestamp >= seconds)
+    }
+}
+
+/// A human readable summary of a [`ParsedOffer`], which can be shown before accepting it.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OfferSummary {
+    /// The assets that the maker locked into the settlement payments puzzle.
+    pub offered: OfferAssets,
+    /// The assets that the maker requested in return.
+    pub requested: OfferAssets,
+    /// The royalties of the offered NFTs, which are paid by the taker out of the requested assets.
+    pub offered_royalties: Vec<OfferRoyalty>,
+    /// The royalties of the requested NFTs, which are paid by the maker out of the offered assets.
+    pub requested_royalties: Vec<OfferRoyalty>,
+    /// The fee reserved by the maker's coin spends.
+    pub fee: u64,
+    pub expiration: OfferExpiration,
+}
+
+impl OfferSummary {
+    /// Summarizes the offer, using the same allocator that was used to parse it.
+    pub fn new(allocator: &mut Allocator, offer: &ParsedOffer) -> Result<Self, DriverError> {
+        let registry = AssetRegistry::new();
+        let mut summary = Self::default();
+
+        for coin_spend in &offer.coin_spends {
+            let puzzle = coin_spend.puzzle_reveal.to_clvm(allocator)?;
+            let solution = coin_spend.solution.to_clvm(allocator)?;
+            let output = run_puzzle(allocator, puzzle, solution)?;
+            let conditions = Vec::<Condition>::from_clvm(allocator, output)?;
+
+            for condition in conditions {
+                match condition {
+                    Condition::ReserveFee(reserve_fee) => summary.fee += reserve_fee.amount,
+                    Condition::AssertBeforeHeightAbsolute(condition) => {
+                        summary.expiration.height = Some(
+                            summary
+                                .expiration
+                                .height
+                                .map_or(condition.height, |height| height.min(condition.height)),
+                        );
+                    }
+                    Condition::AssertBeforeSecondsAbsolute(condition) => {
+                        summary.expiration.seconds = Some(
+                            summary
+                                .expiration
+                                .seconds
+                                .map_or(condition.seconds, |seconds| {
+                                    seconds.min(condition.seconds)
+                                }),
+                        );
+                    }
+                    Condition::CreateCoin(create_coin) => {
+                        let coin = Coin::new(
+                            coin_spend.coin.coin_id(),
+                            create_coin.puzzle_hash,
+                            create_coin.amount,
+                        );
+
+                        let decoded = registry.decode_child(allocator, coin_spend, coin)?;
+                        summary.add_offered(allocator, &decoded);
+                    }
+                    _ => {}
+                }
+            }
+        }
+
+        for (puzzle_hash, (puzzle, notarized_payments)) in &offer.requested_payments {
+            let amount: u64 = notarized_payments
+                .iter()
+                .flat_map(|notarized_payment| &notarized_payment.payments)
+                .map(|payment| payment.amount)
+                .sum();
+
+            summary.add_requested(allocator, *puzzle_hash, *puzzle, amount)?;
+        }
+
+        summary.offered_royalties = royalties(allocator, &summary.offered.nfts, &summary.requested);
+        summary.requested_royalties =
+            royalties(allocator, &summary.requested.nfts, &summary.offered);
+
+        Ok(summary)
+    }
+
+    fn add_offered(&mut self, allocator: &Allocator, decoded: &DecodedAsset) {
+        match decoded {
+            DecodedAsset::Xch(coin) if is_settlement(coin.puzzle_hash) => {
+                self.offered.xch += coin.amount;
+            }
+            DecodedAsset::Cat(cat) if is_settlement(cat.p2_puzzle_hash) => {
+                *self.offered.cats.entry(cat.asset_id).or_default() += cat.coin.amount;
+            }
+            DecodedAsset::Cat1(cat) if is_settlement(cat.p2_puzzle_hash) => {
+                *self.offered.cat1s.entry(cat.asset_id).or_default() += cat.coin.amount;
+            }
+            DecodedAsset::Nft(nft) if is_settlement(nft.info.p2_puzzle_hash) => {
+                self.offered
+                    .nfts
+                    .insert(nft.info.launcher_id, offer_nft(allocator, nft.info));
+            }
+            _ => {}
+        }
+    }
+
+    fn add_requested(
+        &mut self,
+        allocator: &Allocator,
+        puzzle_hash: Bytes32,
+        puzzle: Puzzle,
+        amount: u64,
+    ) -> Result<(), DriverError> {
+        if is_settlement(puzzle_hash) {
+            self.requested.xch += amount;
+        } else if let Some(cat) = CatLayer::<Puzzle>::parse_puzzle(allocator, puzzle)? {
+            *self.requested.cats.entry(cat.asset_id).or_default() += amount;
+        } else if let Some(cat) = Cat1Layer::<Puzzle>::parse_puzzle(allocator, puzzle)? {
+            *self.requested.cat1s.entry(cat.asset_id).or_default() += amount;
+        } else if let Some((info, _)) = NftInfo::<HashedPtr>::parse(allocator, puzzle)? {
+            self.requested
+                .nfts
+                .insert(info.launcher_id, offer_nft(allocator, info));
+        } else {
+            self.requested.unknown.push(puzzle_hash);
+        }
+
+        Ok(())
+    }
+}
+
+fn is_settlement(puzzle_hash: Bytes32) -> bool {
+    puzzle_hash == SETTLEMENT_PAYMENTS_PUZZLE_HASH.into()
+        || puzzle_hash == SETTLEMENT_PAYMENTS_PUZZLE_HASH_V1.into()
+}
+
+fn offer_nft(allocator: &Allocator, info: NftInfo<HashedPtr>) -> OfferNft {
+    let metadata = NftMetadata::from_clvm(allocator, info.metadata.ptr()).ok();
+    OfferNft { info, metadata }
+}
+
+/// Splits the trade price of the fungible assets evenly between the NFTs, and calculates the royalty of each.
+fn royalties(
+    allocator: &Allocator,
+    nfts: &IndexMap<Bytes32, OfferNft>,
+    payment: &OfferAssets,
+) -> Vec<OfferRoyalty> {
+    let mut royalties = Vec::new();
+
+    if nfts.is_empty() {
+        return royalties;
+    }
+
+    let assets = (payment.xch > 0)
+        .then_some((None, payment.xch))
+        .into_iter()
+        .chain(
+            payment
+                .cats
+                .iter()
+                .map(|(asset_id, amount)| (Some(*asset_id), *amount)),
+        );
+
+    for (asset_id, amount) in assets {
+        let Some(trade_price) = calculate_nft_trace_price(amount, nfts.len()) else {
+            continue;
+        };
+
+        for nft in nfts.values() {
+            let Ok(payments) = nft.info.royalty_payments(allocator, trade_price) else {
+                continue;
+            };
+
+            for payment in payments {
+                if payment.amount == 0 {
+                    continue;
+                }
+
+                royalties.push(OfferRoyalty {
+                    launcher_id: nft.info.launcher_id,
+                    royalty_puzzle_hash: payment.puzzle_hash,
+                    asset_id,
+                    amount: payment.amount,
+                });
+            }
+        }
+    }
+
+    royalties
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_bls::Signature;
+    use chia_protocol::SpendBundle;
+    use chia_puzzles::offer::Payment;
+    use chia_sdk_test::Simulator;
+    use chia_sdk_types::{Conditions, TradePrice};
+
+    use crate::{Launcher, NftMint, Offer, SettlementLayer, SpendContext, StandardLayer};
+
+    use super::*;
+
+    #[test]
+    fn test_nft_for_xch_and_cat_summary() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (_sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+        let fee_coin = sim.new_coin(puzzle_hash, 10);
+        let asset_id = Bytes32::new([1; 32]);
+
+        let metadata = NftMetadata {
+            data_uris: vec!["https://example.com/nft.png".to_string()],
+            ..Default::default()
+        };
+
+        let (mint_nft, nft) = Launcher::new(coin.coin_id(), 1)
+            .mint_nft(ctx, NftMint::new(metadata.clone(), puzzle_hash, 300, None))?;
+        p2.spend(ctx, coin, mint_nft)?;
+        ctx.take();
+
+        let settlement = ctx.settlement_payments_puzzle()?;
+        let cat_settlement = CatLayer::new(asset_id, SettlementLayer).construct_puzzle(ctx)?;
+
+        let (assertions, builder) = Offer::build(vec![nft.coin.coin_id()])
+            .request(ctx, &settlement, vec![Payment::new(puzzle_hash, 1000)])?
+            .request(ctx, &cat_settlement, vec![Payment::new(puzzle_hash, 500)])?
+            .finish();
+
+        let _nft = nft.clone().lock_settlement(
+            ctx,
+            &p2,
+            Vec::<TradePrice>::new(),
+            Conditions::new().extend(assertions),
+        )?;
+
+        p2.spend(
+            ctx,
+            fee_coin,
+            Conditions::new()
+                .reserve_fee(10)
+                .assert_before_height_absolute(100)
+                .assert_before_height_absolute(50),
+        )?;
+
+        let spend_bundle = SpendBundle::new(ctx.take(), Signature::default());
+        let offer = builder.bundle(ctx, spend_bundle)?;
+        let parsed = offer.parse(&mut ctx.allocator)?;
+        let summary = parsed.summary(&mut ctx.allocator)?;
+
+        assert_eq!(summary.offered.xch, 0);
+        assert_eq!(summary.offered.nfts.len(), 1);
+
+        let offered_nft = &summary.offered.nfts[&nft.info.launcher_id];
+        assert_eq!(offered_nft.info.royalty_ten_thousandths, 300);
+        assert_eq!(offered_nft.metadata, Some(metadata));
+
+        assert_eq!(summary.requested.xch, 1000);
+        assert_eq!(summary.requested.cats[&asset_id], 500);
+
+        assert_eq!(
+            summary.offered_royalties,
+            [
+                OfferRoyalty {
+                    launcher_id: nft.info.launcher_id,
+                    royalty_puzzle_hash: puzzle_hash,
+                    asset_id: None,
+                    amount: 30,
+                },
+                OfferRoyalty {
+                    launcher_id: nft.info.launcher_id,
+                    royalty_puzzle_hash: puzzle_hash,
+                    asset_id: Some(asset_id),
+                    amount: 15,
+                }
+            ]
+        );
+        assert!(summary.requested_royalties.is_empty());
+
+        assert_eq!(summary.fee, 10);
+        assert_eq!(summary.expiration.height, Some(50));
+        assert_eq!(summary.expiration.seconds, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xch_for_nft_summary() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (_sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+        let xch_coin = sim.new_coin(puzzle_hash, 2000);
+
+        let (mint_nft, nft) = Launcher::new(coin.coin_id(), 1).mint_nft(
+            ctx,
+            NftMint::new(NftMetadata::default(), puzzle_hash, 500, None),
+        )?;
+        p2.spend(ctx, coin, mint_nft)?;
+        ctx.take();
+
+        let settlement = ctx.settlement_payments_puzzle()?;
+        let nft_settlement = nft
+            .info
+            .clone()
+            .into_layers(settlement)
+            .construct_puzzle(ctx)?;
+
+        let (assertions, builder) = Offer::build(vec![xch_coin.coin_id()])
+            .request(ctx, &nft_settlement, vec![Payment::new(puzzle_hash, 1)])?
+            .finish();
+
+        p2.spend(
+            ctx,
+            xch_coin,
+            Conditions::new()
+                .create_coin(SETTLEMENT_PAYMENTS_PUZZLE_HASH.into(), 2000, Vec::new())
+                .extend(assertions),
+        )?;
+
+        let spend_bundle = SpendBundle::new(ctx.take(), Signature::default());
+        let offer = builder.bundle(ctx, spend_bundle)?;
+        let parsed = offer.parse(&mut ctx.allocator)?;
+        let summary = parsed.summary(&mut ctx.allocator)?;
+
+        assert_eq!(summary.offered.xch, 2000);
+        assert!(summary.requested.nfts.contains_key(&nft.info.launcher_id));
+        assert_eq!(summary.requested.xch, 0);
+        assert!(summary.offered_royalties.is_empty());
+        assert_eq!(
+            summary.requested_royalties,
+            [OfferRoyalty {
+                launcher_id: nft.info.launcher_id,
+                royalty_puzzle_hash: puzzle_hash,
+                asset_id: None,
+                amount: 100,
+            }]
+        );
+        assert_eq!(summary.fee, 0);
+        assert_eq!(summary.expiration, OfferExpiration::default());
+
+        Ok(())
+    }
+}
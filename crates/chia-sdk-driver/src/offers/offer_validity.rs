@@ -0,0 +1,327 @@
+use std::collections::HashSet;
+
+use chia_bls::aggregate_verify;
+use chia_protocol::{Bytes32, Coin, CoinState};
+use chia_sdk_signer::{AggSigConstants, RequiredSignature};
+use chia_sdk_utils::select_coins;
+use clvmr::Allocator;
+
+use crate::{
+    AssetRegistry, DecodedAsset, DriverError, ParsedOffer, SpendContext, SpendableCoins,
+    TransactionBuilder, UnsignedTransaction,
+};
+
+/// The result of checking whether an offer can still be taken.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct OfferValidity {
+    /// The maker's coins that have already been spent, which means the offer was taken or cancelled.
+    pub spent_coins: Vec<Bytes32>,
+    /// The maker's coins that couldn't be found on the blockchain.
+    pub missing_coins: Vec<Bytes32>,
+    /// Whether the aggregated signature is valid for the maker's coin spends.
+    pub valid_signature: bool,
+    /// Whether the offer's expiration has been reached.
+    pub expired: bool,
+}
+
+impl OfferValidity {
+    pub fn is_valid(&self) -> bool {
+        self.spent_coins.is_empty()
+            && self.missing_coins.is_empty()
+            && self.valid_signature
+            && !self.expired
+    }
+}
+
+impl ParsedOffer {
+    /// The coins that the maker spends, excluding any that are created by the offer itself.
+    pub fn input_coins(&self) -> Vec<Coin> {
+        let coin_ids: HashSet<Bytes32> = self
+            .coin_spends
+            .iter()
+            .map(|coin_spend| coin_spend.coin.coin_id())
+            .collect();
+
+        self.coin_spends
+            .iter()
+            .map(|coin_spend| coin_spend.coin)
+            .filter(|coin| !coin_ids.contains(&coin.parent_coin_info))
+            .collect()
+    }
+
+    /// Checks that the aggregated signature is valid for the maker's coin spends, on the network with the given constants.
+    pub fn verify_signature(
+        &self,
+        allocator: &mut Allocator,
+        constants: &AggSigConstants,
+    ) -> Result<bool, DriverError> {
+        let required_signatures =
+            RequiredSignature::from_coin_spends(allocator, &self.coin_spends, constants)?;
+
+        Ok(aggregate_verify(
+            &self.aggregated_signature,
+            required_signatures
+                .iter()
+                .map(|required| (required.public_key(), required.final_message())),
+        ))
+    }
+
+    /// Checks the offer against the coin states of its input coins, and the height and timestamp of the peak.
+    ///
+    /// The allocator must be the same one that was used to parse the offer.
+    pub fn validity(
+        &self,
+        allocator: &mut Allocator,
+        coin_states: &[CoinState],
+        constants: &AggSigConstants,
+        peak_height: u32,
+        peak_timestamp: u64,
+    ) -> Result<OfferValidity, DriverError> {
+        let mut validity = OfferValidity {
+            valid_signature: self.verify_signature(allocator, constants)?,
+            expired: self
+                .summary(allocator)?
+                .expiration
+                .is_expired(peak_height, peak_timestamp),
+            ..Default::default()
+        };
+
+        for coin in self.input_coins() {
+            let coin_id = coin.coin_id();
+
+            match coin_states.iter().find(|cs| cs.coin == coin) {
+                Some(cs) if cs.spent_height.is_some() => validity.spent_coins.push(coin_id),
+                Some(cs) if cs.created_height.is_some() => {}
+                _ => validity.missing_coins.push(coin_id),
+            }
+        }
+
+        Ok(validity)
+    }
+
+    /// Requests the coin states of the input coins from the peer, and checks the validity of the offer.
+    ///
+    /// The coin state response doesn't include the peak, so the height and timestamp of the latest
+    /// transaction block that the wallet has seen must be passed in.
+    ///
+    /// The allocator must be the same one that was used to parse the offer.
+    #[cfg(feature = "peer")]
+    pub async fn request_validity(
+        &self,
+        allocator: &mut Allocator,
+        peer: &chia_sdk_client::Peer,
+        genesis_challenge: Bytes32,
+        constants: &AggSigConstants,
+        peak_height: u32,
+        peak_timestamp: u64,
+    ) -> Result<OfferValidity, DriverError> {
+        let coin_ids = self
+            .input_coins()
+            .iter()
+            .map(Coin::coin_id)
+            .collect::<Vec<_>>();
+
+        let coin_states = peer
+            .request_coin_state(coin_ids, None, genesis_challenge, false)
+            .await?
+            .map_err(|rejection| DriverError::RejectCoinState(rejection.reason))?
+            .coin_states;
+
+        self.validity(
+            allocator,
+            &coin_states,
+            constants,
+            peak_height,
+            peak_timestamp,
+        )
+    }
+}
+
+impl TransactionBuilder {
+    /// Cancels an offer, by spending the maker's coins back to the change puzzle hash so that it can no longer be taken.
+    ///
+    /// The fee is paid out of the maker's XCH, and if that isn't enough the rest is selected from the spendable coins.
+    /// The offer must have been parsed with the [`SpendContext`]'s allocator.
+    pub fn cancel_offer(
+        self,
+        ctx: &mut SpendContext,
+        offer: &ParsedOffer,
+        coins: &SpendableCoins,
+        constants: &AggSigConstants,
+    ) -> Result<UnsignedTransaction, DriverError> {
+        let puzzle_hash = self.change_puzzle_hash();
+        let input_coins = offer.input_coins();
+        let registry = AssetRegistry::new();
+        let mut selected = SpendableCoins::new();
+        let mut builder = self;
+
+        for coin_spend in &offer.coin_spends {
+            if !input_coins.contains(&coin_spend.coin) {
+                continue;
+            }
+
+            match registry.decode_spend(&mut ctx.allocator, coin_spend)? {
+                DecodedAsset::Xch(coin) => selected.add_xch(coin),
+                DecodedAsset::Cat(cat) => selected.add_cat(cat),
+                DecodedAsset::Nft(nft) => {
+                    builder = builder.transfer_nft(nft.info.launcher_id, puzzle_hash);
+                    selected.add_nft(nft);
+                }
+                DecodedAsset::Did(did) => {
+                    builder = builder.update_did(did.info.launcher_id, None);
+                    selected.add_did(did);
+                }
+                _ => return Err(DriverError::UnsupportedAsset),
+            }
+        }
+
+        for (asset_id, cats) in &selected.cats {
+            let amount = cats.iter().map(|cat| cat.coin.amount).sum();
+            builder = builder.send_cat(*asset_id, puzzle_hash, amount);
+        }
+
+        let offered_xch: u128 = selected
+            .xch
+            .iter()
+            .map(|coin| u128::from(coin.amount))
+            .sum();
+        let required_xch = builder.required_xch();
+
+        if required_xch > offered_xch {
+            let spendable = coins
+                .xch
+                .iter()
+                .filter(|coin| !selected.xch.contains(coin))
+                .copied()
+                .collect();
+
+            selected
+                .xch
+                .extend(select_coins(spendable, required_xch - offered_xch)?);
+        }
+
+        builder.build_selected(ctx, &selected, constants)
+    }
+}
+
+#[cfg(all(test, feature = "peer"))]
+mod tests {
+    use chia_bls::Signature;
+    use chia_protocol::SpendBundle;
+    use chia_puzzles::standard::StandardArgs;
+    use chia_sdk_test::{sign_transaction, test_secret_key, PeerSimulator};
+    use chia_sdk_types::Conditions;
+
+    use crate::{offers::test_utils::sign_tx, Offer, OfferedAsset, RequestedAsset, StandardLayer};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_offer_validity_and_cancel() -> anyhow::Result<()> {
+        let sim = PeerSimulator::new().await?;
+        let peer = sim.connect().await?;
+        let ctx = &mut SpendContext::new();
+
+        let genesis_challenge = sim.config().constants.genesis_challenge;
+        let constants = AggSigConstants::from(&sim.config().constants);
+
+        let sk = test_secret_key()?;
+        let puzzle_hash = StandardArgs::curry_tree_hash(sk.public_key()).into();
+        let coin = sim.mint_coin(puzzle_hash, 1000).await;
+
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(coin);
+
+        // The offer can only be included in the next block.
+        let tx = TransactionBuilder::new(puzzle_hash)
+            .key(sk.public_key())
+            .conditions(Conditions::new().assert_before_height_absolute(sim.height().await + 2))
+            .make_offer(
+                ctx,
+                &coins,
+                &[OfferedAsset::Xch(500)],
+                &[RequestedAsset::Cat {
+                    asset_id: Bytes32::new([1; 32]),
+                    amount: 100,
+                }],
+                &constants,
+            )?;
+
+        let spend_bundle = sign_tx(&tx, &sk);
+        let offer = Offer::from(spend_bundle.clone()).parse(&mut ctx.allocator)?;
+        assert_eq!(offer.input_coins(), [coin]);
+
+        // The simulator doesn't have timestamps, but the offer only expires by height.
+        let validity = offer
+            .request_validity(
+                &mut ctx.allocator,
+                &peer,
+                genesis_challenge,
+                &constants,
+                sim.height().await,
+                0,
+            )
+            .await?;
+        assert!(validity.is_valid());
+
+        let unsigned = Offer::from(SpendBundle::new(
+            spend_bundle.coin_spends,
+            Signature::default(),
+        ))
+        .parse(&mut ctx.allocator)?;
+        assert!(!unsigned.verify_signature(&mut ctx.allocator, &constants)?);
+
+        // The offer expires once the next block would reach its height.
+        let other_coin = sim.mint_coin(puzzle_hash, 1).await;
+        StandardLayer::new(sk.public_key()).spend(
+            ctx,
+            other_coin,
+            Conditions::new().reserve_fee(1),
+        )?;
+        let coin_spends = ctx.take();
+        let signature = sign_transaction(&coin_spends, &[sk.clone()])?;
+        let ack = peer
+            .send_transaction(SpendBundle::new(coin_spends, signature))
+            .await?;
+        assert_eq!(ack.error, None);
+
+        let validity = offer
+            .request_validity(
+                &mut ctx.allocator,
+                &peer,
+                genesis_challenge,
+                &constants,
+                sim.height().await,
+                0,
+            )
+            .await?;
+        assert!(validity.expired);
+        assert!(validity.spent_coins.is_empty());
+
+        let tx = TransactionBuilder::new(puzzle_hash)
+            .key(sk.public_key())
+            .fee(100)
+            .cancel_offer(ctx, &offer, &SpendableCoins::new(), &constants)?;
+
+        let ack = peer.send_transaction(sign_tx(&tx, &sk)).await?;
+        assert_eq!(ack.error, None);
+
+        let validity = offer
+            .request_validity(
+                &mut ctx.allocator,
+                &peer,
+                genesis_challenge,
+                &constants,
+                sim.height().await,
+                0,
+            )
+            .await?;
+        assert_eq!(validity.spent_coins, [coin.coin_id()]);
+        assert!(!validity.is_valid());
+
+        let change = Coin::new(coin.coin_id(), puzzle_hash, 900);
+        assert!(sim.coin_state(change.coin_id()).await.is_some());
+
+        Ok(())
+    }
+}
@@ -1,9 +1,13 @@
 use chia_bls::Signature;
 use chia_protocol::{Bytes32, CoinSpend};
-use chia_puzzles::offer::NotarizedPayment;
+use chia_puzzles::offer::{
+    NotarizedPayment, SETTLEMENT_PAYMENTS_PUZZLE_HASH, SETTLEMENT_PAYMENTS_PUZZLE_HASH_V1,
+};
+use clvm_traits::{match_quote, FromClvm, MatchByte};
+use clvmr::{Allocator, NodePtr};
 use indexmap::IndexMap;
 
-use crate::{OfferBuilder, Puzzle, Take};
+use crate::{DriverError, OfferBuilder, OfferSummary, Puzzle, Take};
 
 #[derive(Debug, Default, Clone)]
 pub struct ParsedOffer {
@@ -16,4 +20,91 @@ impl ParsedOffer {
     pub fn take(self) -> OfferBuilder<Take> {
         OfferBuilder::from_parsed_offer(self)
     }
+
+    /// Determines which version of the settlement payments puzzle the requested payments use.
+    /// Returns [`None`] if none of the requested payment puzzles contain a known settlement puzzle.
+    ///
+    /// The allocator must be the same one that was used to parse the offer.
+    pub fn settlement_version(&self, allocator: &Allocator) -> Option<SettlementVersion> {
+        self.requested_payments
+            .values()
+            .find_map(|(puzzle, _)| SettlementVersion::parse(allocator, *puzzle))
+    }
+
+    /// Summarizes the offered and requested assets, royalties, fee, and expiration.
+    ///
+    /// The allocator must be the same one that was used to parse the offer.
+    pub fn summary(&self, allocator: &mut Allocator) -> Result<OfferSummary, DriverError> {
+        OfferSummary::new(allocator, self)
+    }
+}
+
+/// The version of the settlement payments puzzle, which was updated alongside the CAT standard.
+/// Offers made before CAT2 was released use [`SettlementVersion::V1`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SettlementVersion {
+    V1,
+    V2,
+}
+
+impl SettlementVersion {
+    /// Finds the settlement payments puzzle in a requested payment puzzle, by looking
+    /// through the curried arguments of any outer layers (such as CAT1, CAT2, or NFTs).
+    pub fn parse(allocator: &Allocator, puzzle: Puzzle) -> Option<Self> {
+        let puzzle_hash = puzzle.curried_puzzle_hash();
+
+        if puzzle_hash == SETTLEMENT_PAYMENTS_PUZZLE_HASH {
+            return Some(Self::V2);
+        }
+
+        if puzzle_hash == SETTLEMENT_PAYMENTS_PUZZLE_HASH_V1 {
+            return Some(Self::V1);
+        }
+
+        let mut args = puzzle.as_curried()?.args;
+
+        // Each curried argument is in the form `(c (q . arg) rest)`.
+        while let Ok((_, ((_, arg), (rest, ())))) =
+            <(MatchByte<4>, (match_quote!(NodePtr), (NodePtr, ())))>::from_clvm(allocator, args)
+        {
+            if let Some(version) = Self::parse(allocator, Puzzle::parse(allocator, arg)) {
+                return Some(version);
+            }
+            args = rest;
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Cat1Layer, CatLayer, Layer, SettlementLayer, SettlementV1Layer, SpendContext};
+
+    use super::*;
+
+    #[test]
+    fn test_settlement_version() -> anyhow::Result<()> {
+        let mut ctx = SpendContext::new();
+        let asset_id = Bytes32::new([1; 32]);
+
+        let v1 = SettlementV1Layer.construct_puzzle(&mut ctx)?;
+        let v2 = SettlementLayer.construct_puzzle(&mut ctx)?;
+        let cat1 = Cat1Layer::new(asset_id, SettlementV1Layer).construct_puzzle(&mut ctx)?;
+        let cat2 = CatLayer::new(asset_id, SettlementLayer).construct_puzzle(&mut ctx)?;
+        let unknown = ctx.alloc(&"Hello, world!")?;
+
+        for (ptr, expected) in [
+            (v1, Some(SettlementVersion::V1)),
+            (v2, Some(SettlementVersion::V2)),
+            (cat1, Some(SettlementVersion::V1)),
+            (cat2, Some(SettlementVersion::V2)),
+            (unknown, None),
+        ] {
+            let puzzle = Puzzle::parse(&ctx.allocator, ptr);
+            assert_eq!(SettlementVersion::parse(&ctx.allocator, puzzle), expected);
+        }
+
+        Ok(())
+    }
 }
@@ -0,0 +1,90 @@
+"""Generates the `compressed_v0.offer` to `compressed_v5.offer` test vectors from `decompressed.offer`.
+
+This is a copy of `compress_object_with_puzzles` from Chia's `chia/wallet/util/puzzle_compression.py`,
+using Python's zlib rather than this crate. The puzzles for each dictionary are read from the source of
+the `chia-puzzles` crate that this workspace depends on, so that they match the ones used by `compress.rs`.
+
+Run it from anywhere in the workspace with `python3 generate_compressed.py`. Version 6 isn't written,
+since `compressed.offer` came from Chia itself. The output of zlib depends on its build, so rather than
+comparing bytes, the dictionaries are checked by decompressing `compressed.offer` with them.
+"""
+
+import json
+import os
+import re
+import subprocess
+import zlib
+
+TEST_DATA = os.path.dirname(os.path.abspath(__file__))
+
+
+def puzzles_dir():
+    metadata = json.loads(
+        subprocess.check_output(["cargo", "metadata", "--format-version", "1"], cwd=TEST_DATA)
+    )
+    package = next(package for package in metadata["packages"] if package["name"] == "chia-puzzles")
+    return os.path.join(os.path.dirname(package["manifest_path"]), "src", "puzzles")
+
+
+def puzzle(file, name):
+    with open(os.path.join(puzzles_dir(), f"{file}.rs")) as f:
+        text = f.read()
+    match = re.search(r"pub const " + name + r": \[u8; \d+\] = hex!\((.*?)\);", text, re.S)
+    return bytes.fromhex("".join(re.findall(r'"([0-9a-fA-F\s]*)"', match.group(1))))
+
+
+ZDICT = [
+    puzzle("standard", "STANDARD_PUZZLE") + puzzle("cat", "CAT_PUZZLE_V1"),
+    puzzle("offer", "SETTLEMENT_PAYMENTS_PUZZLE_V1"),
+    puzzle("singleton", "SINGLETON_TOP_LAYER_PUZZLE")
+    + puzzle("nft", "NFT_STATE_LAYER_PUZZLE")
+    + puzzle("nft", "NFT_OWNERSHIP_LAYER_PUZZLE")
+    + puzzle("nft", "NFT_METADATA_UPDATER_PUZZLE")
+    + puzzle("nft", "NFT_ROYALTY_TRANSFER_PUZZLE"),
+    puzzle("cat", "CAT_PUZZLE"),
+    puzzle("offer", "SETTLEMENT_PAYMENTS_PUZZLE"),
+    b"",
+]
+
+
+def zdict_for_version(version):
+    summed = b""
+    for version_dict in ZDICT[0:version]:
+        summed += version_dict
+    return summed
+
+
+def compress_with_zdict(blob, zdict):
+    comp_obj = zlib.compressobj(zdict=zdict)
+    return comp_obj.compress(blob) + comp_obj.flush()
+
+
+def compress_object_with_puzzles(object_bytes, version):
+    version_blob = version.to_bytes(length=2, byteorder="big")
+    return version_blob + compress_with_zdict(object_bytes, zdict_for_version(version))
+
+
+def decompress_object_with_puzzles(compressed_object_blob):
+    version = int.from_bytes(compressed_object_blob[0:2], "big")
+    decomp_obj = zlib.decompressobj(zdict=zdict_for_version(version))
+    return decomp_obj.decompress(compressed_object_blob[2:]) + decomp_obj.flush()
+
+
+def read_hex(name):
+    with open(os.path.join(TEST_DATA, name)) as f:
+        return bytes.fromhex(f.read().strip())
+
+
+def main():
+    offer = read_hex("decompressed.offer")
+
+    if decompress_object_with_puzzles(read_hex("compressed.offer")) != offer:
+        raise SystemExit("compressed.offer doesn't decompress with the version 6 dictionary")
+
+    for version in range(6):
+        with open(os.path.join(TEST_DATA, f"compressed_v{version}.offer"), "w") as f:
+            f.write(compress_object_with_puzzles(offer, version).hex() + "\n")
+
+
+if __name__ == "__main__":
+    main()
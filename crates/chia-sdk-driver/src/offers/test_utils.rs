@@ -0,0 +1,31 @@
+use chia_bls::{sign, SecretKey};
+use chia_protocol::{Bytes32, SpendBundle};
+use chia_sdk_signer::AggSigConstants;
+use chia_sdk_test::Simulator;
+use chia_sdk_types::TESTNET11_CONSTANTS;
+
+use crate::UnsignedTransaction;
+
+pub(super) fn constants() -> AggSigConstants {
+    AggSigConstants::new(TESTNET11_CONSTANTS.agg_sig_me_additional_data)
+}
+
+/// Signs every required signature of the transaction with the same key.
+pub(super) fn sign_tx(tx: &UnsignedTransaction, sk: &SecretKey) -> SpendBundle {
+    let mut spend_bundle = tx.spend_bundle.clone();
+
+    for required in &tx.required_signatures {
+        spend_bundle.aggregated_signature += &sign(sk, required.final_message());
+    }
+
+    spend_bundle
+}
+
+/// The total amount of the unspent coins with the puzzle hash.
+pub(super) fn balance(sim: &Simulator, puzzle_hash: Bytes32) -> u64 {
+    sim.lookup_puzzle_hashes([puzzle_hash].into_iter().collect(), false)
+        .into_iter()
+        .filter(|cs| cs.spent_height.is_none())
+        .map(|cs| cs.coin.amount)
+        .sum()
+}
@@ -3,12 +3,14 @@ mod did;
 mod intermediate_launcher;
 mod launcher;
 mod nft;
+mod singleton;
 
 pub use cat::*;
 pub use did::*;
 pub use intermediate_launcher::*;
 pub use launcher::*;
 pub use nft::*;
+pub use singleton::*;
 
 #[cfg(feature = "chip-0035")]
 mod datalayer;
@@ -9,12 +9,16 @@ use clvm_traits::{clvm_quote, FromClvm};
 use clvm_utils::CurriedProgram;
 use clvmr::{Allocator, NodePtr};
 
-use crate::{CatLayer, DriverError, Layer, Puzzle, Spend, SpendContext};
+use crate::{CatLayer, DriverError, Layer, Puzzle, Spend, SpendContext, SpendWithConditions};
 
+mod cat1;
 mod cat_spend;
+mod cat_tails;
 mod single_cat_spend;
 
+pub use cat1::*;
 pub use cat_spend::*;
+pub use cat_tails::*;
 pub use single_cat_spend::*;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -85,6 +89,89 @@ impl Cat {
         )
     }
 
+    /// Issues a CAT which can only be issued once, but can be melted by its owners at any time.
+    pub fn meltable_single_issuance_eve(
+        ctx: &mut SpendContext,
+        parent_coin_id: Bytes32,
+        amount: u64,
+        extra_conditions: Conditions,
+    ) -> Result<(Conditions, Cat), DriverError> {
+        let meltable_genesis_by_coin_id_ptr = ctx.meltable_genesis_by_coin_id_tail_puzzle()?;
+
+        let tail = ctx.alloc(&CurriedProgram {
+            program: meltable_genesis_by_coin_id_ptr,
+            args: MeltableGenesisByCoinIdTailArgs::new(parent_coin_id),
+        })?;
+
+        Self::create_and_spend_eve(
+            ctx,
+            parent_coin_id,
+            ctx.tree_hash(tail).into(),
+            amount,
+            extra_conditions.run_cat_tail(tail, NodePtr::NIL),
+        )
+    }
+
+    /// Issues a CAT from a parent coin, where the TAIL commits to the puzzle hash of that coin.
+    /// This allows the asset id to be known before the parent coin exists.
+    pub fn genesis_by_puzzle_hash_eve(
+        ctx: &mut SpendContext,
+        parent_coin: Coin,
+        amount: u64,
+        extra_conditions: Conditions,
+    ) -> Result<(Conditions, Cat), DriverError> {
+        let genesis_by_puzzle_hash_ptr = ctx.genesis_by_puzzle_hash_tail_puzzle()?;
+
+        let tail = ctx.alloc(&CurriedProgram {
+            program: genesis_by_puzzle_hash_ptr,
+            args: GenesisByPuzzleHashTailArgs::new(parent_coin.puzzle_hash),
+        })?;
+
+        let tail_solution = ctx.alloc(&GenesisByPuzzleHashTailSolution::new(
+            parent_coin.parent_coin_info,
+            parent_coin.amount,
+        ))?;
+
+        Self::create_and_spend_eve(
+            ctx,
+            parent_coin.coin_id(),
+            ctx.tree_hash(tail).into(),
+            amount,
+            extra_conditions.run_cat_tail(tail, tail_solution),
+        )
+    }
+
+    /// Issues a CAT with the delegated TAIL. The delegated spend is signed by the public key,
+    /// and its solution is appended to the arguments that are passed to the delegated puzzle.
+    pub fn delegated_issuance_eve(
+        ctx: &mut SpendContext,
+        parent_coin_id: Bytes32,
+        public_key: PublicKey,
+        delegated_spend: Spend,
+        amount: u64,
+        extra_conditions: Conditions,
+    ) -> Result<(Conditions, Cat), DriverError> {
+        let delegated_tail_ptr = ctx.delegated_tail_puzzle()?;
+
+        let tail = ctx.alloc(&CurriedProgram {
+            program: delegated_tail_ptr,
+            args: DelegatedTailArgs::new(public_key),
+        })?;
+
+        let tail_solution = ctx.alloc(&DelegatedTailSolution::new(
+            delegated_spend.puzzle,
+            delegated_spend.solution,
+        ))?;
+
+        Self::create_and_spend_eve(
+            ctx,
+            parent_coin_id,
+            ctx.tree_hash(tail).into(),
+            amount,
+            extra_conditions.run_cat_tail(tail, tail_solution),
+        )
+    }
+
     /// Creates and spends an eve CAT with the provided conditions.
     /// To issue the CAT, you will need to reveal the TAIL puzzle and solution.
     /// This can be done with the [`RunCatTail`] condition.
@@ -128,54 +215,71 @@ impl Cat {
     ///
     /// Each item is a CAT and the inner spend for that CAT.
     pub fn spend_all(ctx: &mut SpendContext, cat_spends: &[CatSpend]) -> Result<(), DriverError> {
-        let len = cat_spends.len();
-
-        let mut total_delta = 0;
-
-        for (index, cat_spend) in cat_spends.iter().enumerate() {
-            let CatSpend {
-                cat,
-                inner_spend,
-                extra_delta,
-            } = cat_spend;
-
-            // Calculate the delta and add it to the subtotal.
-            let output = ctx.run(inner_spend.puzzle, inner_spend.solution)?;
-            let conditions: Vec<NodePtr> = ctx.extract(output)?;
+        let ring: Vec<(Coin, Spend, i64)> = cat_spends
+            .iter()
+            .map(|cat_spend| {
+                (
+                    cat_spend.cat.coin,
+                    cat_spend.inner_spend,
+                    cat_spend.extra_delta,
+                )
+            })
+            .collect();
 
-            let create_coins = conditions
-                .into_iter()
-                .filter_map(|ptr| ctx.extract::<CreateCoin>(ptr).ok());
+        for (cat_spend, single_spend) in cat_spends.iter().zip(ring_spends(ctx, &ring)?) {
+            cat_spend.cat.spend(ctx, single_spend)?;
+        }
 
-            let delta = create_coins.fold(
-                i128::from(cat.coin.amount) - i128::from(*extra_delta),
-                |delta, create_coin| delta - i128::from(create_coin.amount),
-            );
+        Ok(())
+    }
 
-            let prev_subtotal = total_delta;
-            total_delta += delta;
+    /// Creates a [`CatSpend`] which reveals the TAIL to issue additional supply.
+    /// The provided conditions must create outputs with the amount of this coin plus the amount issued.
+    pub fn reissue<P>(
+        &self,
+        ctx: &mut SpendContext,
+        p2: &P,
+        tail: Spend,
+        amount: u64,
+        conditions: Conditions,
+    ) -> Result<CatSpend, DriverError>
+    where
+        P: SpendWithConditions,
+    {
+        let inner_spend =
+            p2.spend_with_conditions(ctx, conditions.run_cat_tail(tail.puzzle, tail.solution))?;
 
-            // Find information of neighboring coins on the ring.
-            let prev = &cat_spends[if index == 0 { len - 1 } else { index - 1 }];
-            let next = &cat_spends[if index == len - 1 { 0 } else { index + 1 }];
+        Ok(CatSpend::with_extra_delta(
+            *self,
+            inner_spend,
+            amount.try_into()?,
+        ))
+    }
 
-            cat.spend(
-                ctx,
-                SingleCatSpend {
-                    inner_spend: *inner_spend,
-                    prev_coin_id: prev.cat.coin.coin_id(),
-                    next_coin_proof: CoinProof {
-                        parent_coin_info: next.cat.coin.parent_coin_info,
-                        inner_puzzle_hash: ctx.tree_hash(next.inner_spend.puzzle).into(),
-                        amount: next.cat.coin.amount,
-                    },
-                    prev_subtotal: prev_subtotal.try_into()?,
-                    extra_delta: *extra_delta,
-                },
-            )?;
-        }
+    /// Creates a [`CatSpend`] which reveals the TAIL to melt some of the supply back into XCH.
+    /// The provided conditions must create outputs with the amount of this coin minus the amount melted.
+    ///
+    /// The melted amount is released as XCH in the spend bundle, so it must either be claimed
+    /// by another spend or it will be used as a fee.
+    pub fn melt<P>(
+        &self,
+        ctx: &mut SpendContext,
+        p2: &P,
+        tail: Spend,
+        amount: u64,
+        conditions: Conditions,
+    ) -> Result<CatSpend, DriverError>
+    where
+        P: SpendWithConditions,
+    {
+        let inner_spend =
+            p2.spend_with_conditions(ctx, conditions.run_cat_tail(tail.puzzle, tail.solution))?;
 
-        Ok(())
+        Ok(CatSpend::with_extra_delta(
+            *self,
+            inner_spend,
+            -i64::try_from(amount)?,
+        ))
     }
 
     /// Creates a coin spend for this CAT.
@@ -221,6 +325,54 @@ impl Cat {
     }
 }
 
+/// Calculates the [`SingleCatSpend`] for each coin in a ring, given its inner spend and extra delta.
+/// The ring announcements work the same way for both CAT1 and CAT2.
+fn ring_spends(
+    ctx: &mut SpendContext,
+    ring: &[(Coin, Spend, i64)],
+) -> Result<Vec<SingleCatSpend>, DriverError> {
+    let len = ring.len();
+
+    let mut total_delta = 0;
+    let mut single_spends = Vec::with_capacity(len);
+
+    for (index, &(coin, inner_spend, extra_delta)) in ring.iter().enumerate() {
+        // Calculate the delta and add it to the subtotal.
+        let output = ctx.run(inner_spend.puzzle, inner_spend.solution)?;
+        let conditions: Vec<NodePtr> = ctx.extract(output)?;
+
+        let create_coins = conditions
+            .into_iter()
+            .filter_map(|ptr| ctx.extract::<CreateCoin>(ptr).ok());
+
+        let delta = create_coins.fold(
+            i128::from(coin.amount) + i128::from(extra_delta),
+            |delta, create_coin| delta - i128::from(create_coin.amount),
+        );
+
+        let prev_subtotal = total_delta;
+        total_delta += delta;
+
+        // Find information of neighboring coins on the ring.
+        let (prev_coin, _, _) = ring[if index == 0 { len - 1 } else { index - 1 }];
+        let (next_coin, next_inner_spend, _) = ring[if index == len - 1 { 0 } else { index + 1 }];
+
+        single_spends.push(SingleCatSpend {
+            inner_spend,
+            prev_coin_id: prev_coin.coin_id(),
+            next_coin_proof: CoinProof {
+                parent_coin_info: next_coin.parent_coin_info,
+                inner_puzzle_hash: ctx.tree_hash(next_inner_spend.puzzle).into(),
+                amount: next_coin.amount,
+            },
+            prev_subtotal: prev_subtotal.try_into()?,
+            extra_delta,
+        });
+    }
+
+    Ok(single_spends)
+}
+
 impl Cat {
     pub fn parse_children(
         allocator: &mut Allocator,
@@ -540,4 +692,269 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_meltable_single_issuance_cat() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (issue_cat, cat) = Cat::meltable_single_issuance_eve(
+            ctx,
+            coin.coin_id(),
+            1000,
+            Conditions::new().create_coin(puzzle_hash, 1000, vec![puzzle_hash.into()]),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let cat = cat.wrapped_child(puzzle_hash, 1000);
+        assert_eq!(
+            cat.asset_id,
+            MeltableGenesisByCoinIdTailArgs::curry_tree_hash(coin.coin_id()).into()
+        );
+
+        // Melt part of the CAT and claim the released value with an XCH spend.
+        let meltable_genesis_by_coin_id_ptr = ctx.meltable_genesis_by_coin_id_tail_puzzle()?;
+        let tail = ctx.alloc(&CurriedProgram {
+            program: meltable_genesis_by_coin_id_ptr,
+            args: MeltableGenesisByCoinIdTailArgs::new(coin.coin_id()),
+        })?;
+
+        let xch = sim.new_coin(puzzle_hash, 1);
+
+        let cat_spend = cat.melt(
+            ctx,
+            &p2,
+            Spend::new(tail, NodePtr::NIL),
+            300,
+            Conditions::new().create_coin(puzzle_hash, 700, vec![puzzle_hash.into()]),
+        )?;
+        Cat::spend_all(ctx, &[cat_spend])?;
+        p2.spend(
+            ctx,
+            xch,
+            Conditions::new().create_coin(puzzle_hash, 301, Vec::new()),
+        )?;
+
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        assert!(sim
+            .coin_state(cat.wrapped_child(puzzle_hash, 700).coin.coin_id())
+            .is_some());
+        assert!(sim
+            .coin_state(Coin::new(xch.coin_id(), puzzle_hash, 301).coin_id())
+            .is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_meltable_cat_cannot_reissue() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (issue_cat, cat) = Cat::meltable_single_issuance_eve(
+            ctx,
+            coin.coin_id(),
+            1000,
+            Conditions::new().create_coin(puzzle_hash, 1000, vec![puzzle_hash.into()]),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let meltable_genesis_by_coin_id_ptr = ctx.meltable_genesis_by_coin_id_tail_puzzle()?;
+
+        let tail = ctx.alloc(&CurriedProgram {
+            program: meltable_genesis_by_coin_id_ptr,
+            args: MeltableGenesisByCoinIdTailArgs::new(coin.coin_id()),
+        })?;
+
+        let cat_spend = cat.wrapped_child(puzzle_hash, 1000).reissue(
+            ctx,
+            &p2,
+            Spend::new(tail, NodePtr::NIL),
+            500,
+            Conditions::new().create_coin(puzzle_hash, 1500, vec![puzzle_hash.into()]),
+        )?;
+        Cat::spend_all(ctx, &[cat_spend])?;
+
+        // The TAIL raises an error, so the spend can't even be signed.
+        assert!(matches!(
+            sim.spend_coins(ctx.take(), &[sk]).unwrap_err(),
+            SimulatorError::Signer(_)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_genesis_by_puzzle_hash_cat() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (issue_cat, cat) = Cat::genesis_by_puzzle_hash_eve(
+            ctx,
+            coin,
+            1,
+            Conditions::new().create_coin(puzzle_hash, 1, vec![puzzle_hash.into()]),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let cat = cat.wrapped_child(puzzle_hash, 1);
+        assert_eq!(
+            cat.asset_id,
+            GenesisByPuzzleHashTailArgs::curry_tree_hash(puzzle_hash).into()
+        );
+        assert!(sim.coin_state(cat.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_delegated_issuance_cat() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        // The delegated puzzle is a single issuance TAIL, which is signed by the key.
+        let genesis_by_coin_id_ptr = ctx.genesis_by_coin_id_tail_puzzle()?;
+        let delegated_puzzle = ctx.alloc(&CurriedProgram {
+            program: genesis_by_coin_id_ptr,
+            args: GenesisByCoinIdTailArgs::new(coin.coin_id()),
+        })?;
+        let delegated_solution = ctx.alloc(&[NodePtr::NIL])?;
+
+        let (issue_cat, cat) = Cat::delegated_issuance_eve(
+            ctx,
+            coin.coin_id(),
+            pk,
+            Spend::new(delegated_puzzle, delegated_solution),
+            1,
+            Conditions::new().create_coin(puzzle_hash, 1, vec![puzzle_hash.into()]),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let cat = cat.wrapped_child(puzzle_hash, 1);
+        assert_eq!(cat.asset_id, DelegatedTailArgs::curry_tree_hash(pk).into());
+        assert!(sim.coin_state(cat.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cat_reissue() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(3)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (issue_cat, cat) = Cat::multi_issuance_eve(
+            ctx,
+            coin.coin_id(),
+            pk,
+            3,
+            Conditions::new()
+                .create_coin(puzzle_hash, 1, vec![puzzle_hash.into()])
+                .create_coin(puzzle_hash, 2, vec![puzzle_hash.into()]),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let everything_with_signature_ptr = ctx.everything_with_signature_tail_puzzle()?;
+
+        let tail = ctx.alloc(&CurriedProgram {
+            program: everything_with_signature_ptr,
+            args: EverythingWithSignatureTailArgs::new(pk),
+        })?;
+
+        // The extra supply has to be backed by XCH from somewhere else in the spend bundle.
+        let xch = sim.new_coin(puzzle_hash, 5);
+
+        let cat_spends = [
+            cat.wrapped_child(puzzle_hash, 1).reissue(
+                ctx,
+                &p2,
+                Spend::new(tail, NodePtr::NIL),
+                5,
+                Conditions::new().create_coin(puzzle_hash, 6, vec![puzzle_hash.into()]),
+            )?,
+            CatSpend::new(
+                cat.wrapped_child(puzzle_hash, 2),
+                p2.spend_with_conditions(
+                    ctx,
+                    Conditions::new().create_coin(puzzle_hash, 2, vec![puzzle_hash.into()]),
+                )?,
+            ),
+        ];
+        Cat::spend_all(ctx, &cat_spends)?;
+        p2.spend(ctx, xch, Conditions::new())?;
+
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let parent = cat.wrapped_child(puzzle_hash, 1);
+        assert!(sim
+            .coin_state(parent.wrapped_child(puzzle_hash, 6).coin.coin_id())
+            .is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cat_melt_ring() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(3)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (issue_cat, cat) = Cat::multi_issuance_eve(
+            ctx,
+            coin.coin_id(),
+            pk,
+            3,
+            Conditions::new()
+                .create_coin(puzzle_hash, 1, vec![puzzle_hash.into()])
+                .create_coin(puzzle_hash, 2, vec![puzzle_hash.into()]),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let everything_with_signature_ptr = ctx.everything_with_signature_tail_puzzle()?;
+
+        let tail = ctx.alloc(&CurriedProgram {
+            program: everything_with_signature_ptr,
+            args: EverythingWithSignatureTailArgs::new(pk),
+        })?;
+
+        // The melt happens on the first coin, so the subtotal of the second coin depends on it.
+        let cat_spends = [
+            cat.wrapped_child(puzzle_hash, 1).melt(
+                ctx,
+                &p2,
+                Spend::new(tail, NodePtr::NIL),
+                1,
+                Conditions::new(),
+            )?,
+            CatSpend::new(
+                cat.wrapped_child(puzzle_hash, 2),
+                p2.spend_with_conditions(
+                    ctx,
+                    Conditions::new().create_coin(puzzle_hash, 2, vec![puzzle_hash.into()]),
+                )?,
+            ),
+        ];
+        Cat::spend_all(ctx, &cat_spends)?;
+
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        Ok(())
+    }
 }
@@ -0,0 +1,255 @@
+use chia_protocol::{Bytes32, Coin};
+use chia_puzzles::{cat::CatSolution, LineageProof};
+use chia_sdk_types::{run_puzzle, Condition};
+use clvm_traits::FromClvm;
+use clvmr::{Allocator, NodePtr};
+
+use crate::{cat1_puzzle_hash, Cat1Layer, DriverError, Layer, Puzzle, Spend, SpendContext};
+
+use super::{ring_spends, SingleCatSpend};
+
+/// A coin wrapped in the legacy CAT1 puzzle.
+///
+/// CAT1 has been deprecated in favor of CAT2, so this should only be used to parse historical
+/// spends or to move balances that were never migrated. For everything else, use [`Cat`](crate::Cat).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Cat1 {
+    pub coin: Coin,
+    pub lineage_proof: Option<LineageProof>,
+    pub asset_id: Bytes32,
+    pub p2_puzzle_hash: Bytes32,
+}
+
+/// A [`Cat1`] and the inner spend used to spend it as part of a ring.
+#[derive(Debug, Clone, Copy)]
+pub struct Cat1Spend {
+    pub cat: Cat1,
+    pub inner_spend: Spend,
+    pub extra_delta: i64,
+}
+
+impl Cat1Spend {
+    pub fn new(cat: Cat1, inner_spend: Spend) -> Self {
+        Self {
+            cat,
+            inner_spend,
+            extra_delta: 0,
+        }
+    }
+
+    pub fn with_extra_delta(cat: Cat1, inner_spend: Spend, extra_delta: i64) -> Self {
+        Self {
+            cat,
+            inner_spend,
+            extra_delta,
+        }
+    }
+}
+
+impl Cat1 {
+    pub fn new(
+        coin: Coin,
+        lineage_proof: Option<LineageProof>,
+        asset_id: Bytes32,
+        p2_puzzle_hash: Bytes32,
+    ) -> Self {
+        Self {
+            coin,
+            lineage_proof,
+            asset_id,
+            p2_puzzle_hash,
+        }
+    }
+
+    /// Creates coin spends for one or more CAT1 coins in a ring.
+    /// The ring works the same way as it does for CAT2, so you cannot mix the two versions.
+    pub fn spend_all(ctx: &mut SpendContext, cat_spends: &[Cat1Spend]) -> Result<(), DriverError> {
+        let ring: Vec<(Coin, Spend, i64)> = cat_spends
+            .iter()
+            .map(|cat_spend| {
+                (
+                    cat_spend.cat.coin,
+                    cat_spend.inner_spend,
+                    cat_spend.extra_delta,
+                )
+            })
+            .collect();
+
+        for (cat_spend, single_spend) in cat_spends.iter().zip(ring_spends(ctx, &ring)?) {
+            cat_spend.cat.spend(ctx, single_spend)?;
+        }
+
+        Ok(())
+    }
+
+    /// Creates a coin spend for this CAT1 coin.
+    pub fn spend(&self, ctx: &mut SpendContext, spend: SingleCatSpend) -> Result<(), DriverError> {
+        let cat_layer = Cat1Layer::new(self.asset_id, spend.inner_spend.puzzle);
+
+        let puzzle = cat_layer.construct_puzzle(ctx)?;
+        let solution = cat_layer.construct_solution(
+            ctx,
+            CatSolution {
+                lineage_proof: self.lineage_proof,
+                prev_coin_id: spend.prev_coin_id,
+                this_coin_info: self.coin,
+                next_coin_proof: spend.next_coin_proof,
+                prev_subtotal: spend.prev_subtotal,
+                extra_delta: spend.extra_delta,
+                inner_puzzle_solution: spend.inner_spend.solution,
+            },
+        )?;
+
+        ctx.spend(self.coin, Spend::new(puzzle, solution))
+    }
+
+    /// Returns the lineage proof that would be used by each child.
+    pub fn child_lineage_proof(&self) -> LineageProof {
+        LineageProof {
+            parent_parent_coin_info: self.coin.parent_coin_info,
+            parent_inner_puzzle_hash: self.p2_puzzle_hash,
+            parent_amount: self.coin.amount,
+        }
+    }
+
+    /// Creates a wrapped spendable CAT1 for a given output.
+    #[must_use]
+    pub fn wrapped_child(&self, p2_puzzle_hash: Bytes32, amount: u64) -> Self {
+        let puzzle_hash = cat1_puzzle_hash(self.asset_id, p2_puzzle_hash.into());
+        Self {
+            coin: Coin::new(self.coin.coin_id(), puzzle_hash.into(), amount),
+            lineage_proof: Some(self.child_lineage_proof()),
+            asset_id: self.asset_id,
+            p2_puzzle_hash,
+        }
+    }
+
+    pub fn parse_children(
+        allocator: &mut Allocator,
+        parent_coin: Coin,
+        parent_puzzle: Puzzle,
+        parent_solution: NodePtr,
+    ) -> Result<Option<Vec<Self>>, DriverError>
+    where
+        Self: Sized,
+    {
+        let Some(parent_layer) = Cat1Layer::<Puzzle>::parse_puzzle(allocator, parent_puzzle)?
+        else {
+            return Ok(None);
+        };
+        let parent_solution = Cat1Layer::<Puzzle>::parse_solution(allocator, parent_solution)?;
+
+        let output = run_puzzle(
+            allocator,
+            parent_layer.inner_puzzle.ptr(),
+            parent_solution.inner_puzzle_solution,
+        )?;
+        let conditions = Vec::<Condition>::from_clvm(allocator, output)?;
+
+        let outputs = conditions
+            .into_iter()
+            .filter_map(Condition::into_create_coin)
+            .map(|create_coin| {
+                // Calculate what the wrapped puzzle hash would be for the created coin.
+                // This is because we're running the inner layer.
+                let wrapped_puzzle_hash =
+                    cat1_puzzle_hash(parent_layer.asset_id, create_coin.puzzle_hash.into());
+
+                Self {
+                    coin: Coin::new(
+                        parent_coin.coin_id(),
+                        wrapped_puzzle_hash.into(),
+                        create_coin.amount,
+                    ),
+                    lineage_proof: Some(LineageProof {
+                        parent_parent_coin_info: parent_coin.parent_coin_info,
+                        parent_inner_puzzle_hash: parent_layer
+                            .inner_puzzle
+                            .curried_puzzle_hash()
+                            .into(),
+                        parent_amount: parent_coin.amount,
+                    }),
+                    asset_id: parent_layer.asset_id,
+                    p2_puzzle_hash: create_coin.puzzle_hash,
+                }
+            })
+            .collect();
+
+        Ok(Some(outputs))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_protocol::Bytes32;
+    use chia_sdk_test::Simulator;
+    use chia_sdk_types::Conditions;
+    use clvm_traits::ToClvm;
+
+    use crate::{Cat, SpendWithConditions, StandardLayer};
+
+    use super::*;
+
+    /// CAT1 can no longer be issued, so this creates a coin with a made up parent CAT1 lineage.
+    fn historic_cat1(sim: &mut Simulator, p2_puzzle_hash: Bytes32, amount: u64) -> Cat1 {
+        let asset_id = Bytes32::new([42; 32]);
+
+        let parent = Cat1::new(
+            Coin::new(
+                Bytes32::new([7; 32]),
+                cat1_puzzle_hash(asset_id, p2_puzzle_hash.into()).into(),
+                amount,
+            ),
+            None,
+            asset_id,
+            p2_puzzle_hash,
+        );
+
+        let cat = parent.wrapped_child(p2_puzzle_hash, amount);
+        sim.insert_coin(cat.coin);
+        cat
+    }
+
+    #[test]
+    fn test_cat1_spends() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, _coin) = sim.new_p2(0)?;
+        let p2 = StandardLayer::new(pk);
+
+        let first = historic_cat1(&mut sim, puzzle_hash, 5);
+        let second = historic_cat1(&mut sim, puzzle_hash, 3);
+
+        // Combine both coins into a single output.
+        let cat_spends = [
+            Cat1Spend::new(
+                first,
+                p2.spend_with_conditions(
+                    ctx,
+                    Conditions::new().create_coin(puzzle_hash, 8, vec![puzzle_hash.into()]),
+                )?,
+            ),
+            Cat1Spend::new(second, p2.spend_with_conditions(ctx, Conditions::new())?),
+        ];
+
+        Cat1::spend_all(ctx, &cat_spends)?;
+
+        let coin_spends = ctx.take();
+        let coin_spend = coin_spends[0].clone();
+        sim.spend_coins(coin_spends, &[sk])?;
+
+        let puzzle = coin_spend.puzzle_reveal.to_clvm(&mut ctx.allocator)?;
+        let puzzle = Puzzle::parse(&ctx.allocator, puzzle);
+        let solution = coin_spend.solution.to_clvm(&mut ctx.allocator)?;
+
+        let children = Cat1::parse_children(&mut ctx.allocator, first.coin, puzzle, solution)?
+            .expect("not a CAT1");
+        assert_eq!(children, [first.wrapped_child(puzzle_hash, 8)]);
+        assert!(sim.coin_state(children[0].coin.coin_id()).is_some());
+
+        // The CAT2 parser must not recognize the CAT1 spend.
+        assert!(Cat::parse_children(&mut ctx.allocator, first.coin, puzzle, solution)?.is_none());
+
+        Ok(())
+    }
+}
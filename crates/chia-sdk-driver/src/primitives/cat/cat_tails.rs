@@ -0,0 +1,177 @@
+use chia_bls::PublicKey;
+use chia_protocol::Bytes32;
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::{CurriedProgram, ToTreeHash, TreeHash};
+use clvmr::NodePtr;
+use hex_literal::hex;
+
+/// The delegated TAIL allows the owner of a key to run any TAIL program they sign.
+/// This is commonly used to issue more supply in later transactions, by signing a
+/// new single issuance TAIL each time.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(curry)]
+pub struct DelegatedTailArgs {
+    pub public_key: PublicKey,
+}
+
+impl DelegatedTailArgs {
+    pub fn new(public_key: PublicKey) -> Self {
+        Self { public_key }
+    }
+
+    pub fn curry_tree_hash(public_key: PublicKey) -> TreeHash {
+        CurriedProgram {
+            program: DELEGATED_TAIL_PUZZLE_HASH,
+            args: DelegatedTailArgs { public_key },
+        }
+        .tree_hash()
+    }
+}
+
+/// The solution for the delegated TAIL. The tree hash of the delegated puzzle must be
+/// signed by the public key, and `delegated_solution` is appended to the arguments
+/// that the CAT passes to every TAIL program.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub struct DelegatedTailSolution<P = NodePtr, S = NodePtr> {
+    pub delegated_puzzle: P,
+    pub delegated_solution: S,
+}
+
+impl<P, S> DelegatedTailSolution<P, S> {
+    pub fn new(delegated_puzzle: P, delegated_solution: S) -> Self {
+        Self {
+            delegated_puzzle,
+            delegated_solution,
+        }
+    }
+}
+
+/// The genesis by puzzle hash TAIL allows a single issuance from any coin
+/// whose parent has the given puzzle hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(curry)]
+pub struct GenesisByPuzzleHashTailArgs {
+    pub genesis_puzzle_hash: Bytes32,
+}
+
+impl GenesisByPuzzleHashTailArgs {
+    pub fn new(genesis_puzzle_hash: Bytes32) -> Self {
+        Self {
+            genesis_puzzle_hash,
+        }
+    }
+
+    pub fn curry_tree_hash(genesis_puzzle_hash: Bytes32) -> TreeHash {
+        CurriedProgram {
+            program: GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE_HASH,
+            args: GenesisByPuzzleHashTailArgs {
+                genesis_puzzle_hash,
+            },
+        }
+        .tree_hash()
+    }
+}
+
+/// The solution for the genesis by puzzle hash TAIL, which is used to prove
+/// that the parent coin has the genesis puzzle hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub struct GenesisByPuzzleHashTailSolution {
+    pub parent_parent_coin_info: Bytes32,
+    pub parent_amount: u64,
+}
+
+impl GenesisByPuzzleHashTailSolution {
+    pub fn new(parent_parent_coin_info: Bytes32, parent_amount: u64) -> Self {
+        Self {
+            parent_parent_coin_info,
+            parent_amount,
+        }
+    }
+}
+
+/// The meltable genesis by coin id TAIL works like the single issuance TAIL,
+/// except that anyone who owns the CAT can reveal it to melt their coins back into XCH.
+/// The supply can never be increased after the initial issuance.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(curry)]
+pub struct MeltableGenesisByCoinIdTailArgs {
+    pub genesis_coin_id: Bytes32,
+}
+
+impl MeltableGenesisByCoinIdTailArgs {
+    pub fn new(genesis_coin_id: Bytes32) -> Self {
+        Self { genesis_coin_id }
+    }
+
+    pub fn curry_tree_hash(genesis_coin_id: Bytes32) -> TreeHash {
+        CurriedProgram {
+            program: MELTABLE_GENESIS_BY_COIN_ID_TAIL_PUZZLE_HASH,
+            args: MeltableGenesisByCoinIdTailArgs { genesis_coin_id },
+        }
+        .tree_hash()
+    }
+}
+
+pub const DELEGATED_TAIL_PUZZLE: [u8; 180] = hex!(
+    "
+    ff02ffff01ff04ffff04ff04ffff04ff05ffff04ffff02ff06ffff04ff02ffff
+    04ff82027fff80808080ff80808080ffff02ff82027fffff04ff0bffff04ff17
+    ffff04ff2fffff04ff5fffff04ff81bfff82057f80808080808080ffff04ffff
+    01ff31ff02ffff03ffff07ff0580ffff01ff0bffff0102ffff02ff06ffff04ff
+    02ffff04ff09ff80808080ffff02ff06ffff04ff02ffff04ff0dff8080808080
+    ffff01ff0bffff0101ff058080ff0180ff018080
+    "
+);
+
+pub const DELEGATED_TAIL_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "999c3696e167f8a79d938adc11feba3a3dcb39ccff69a426d570706e7b8ec399"
+));
+
+pub const GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE: [u8; 57] = hex!(
+    "
+    ff02ffff03ff2fffff01ff0880ffff01ff02ffff03ffff09ffff0bff82013fff
+    02ff8202bf80ff2d80ff80ffff01ff088080ff018080ff0180
+    "
+);
+
+pub const GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "de5a6e06d41518be97ff6365694f4f89475dda773dede267caa33da63b434e36"
+));
+
+/// ```text
+/// (mod (GENESIS_ID Truths parent_is_cat lineage_proof delta inner_conditions _)
+///   (include cat_truths.clib)
+///   (if delta
+///       (if (> 0 delta) () (x))
+///       (if (= (my_parent_cat_truth Truths) GENESIS_ID) () (x))
+///   )
+/// )
+/// ```
+pub const MELTABLE_GENESIS_BY_COIN_ID_TAIL_PUZZLE: [u8; 67] = hex!(
+    "
+    ff02ffff03ff2fffff01ff02ffff03ffff15ff80ff2f80ff80ffff01ff088080
+    ff0180ffff01ff02ffff03ffff09ff2dff0280ff80ffff01ff088080ff018080
+    ff0180
+    "
+);
+
+pub const MELTABLE_GENESIS_BY_COIN_ID_TAIL_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "9d246fe6ec583afe36c861395b474f712b4183c94023092b10194fae1a3d11d3"
+));
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_puzzle_hash;
+
+    use super::*;
+
+    #[test]
+    fn test_puzzle_hashes() -> anyhow::Result<()> {
+        assert_puzzle_hash!(DELEGATED_TAIL_PUZZLE => DELEGATED_TAIL_PUZZLE_HASH);
+        assert_puzzle_hash!(GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE => GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE_HASH);
+        assert_puzzle_hash!(MELTABLE_GENESIS_BY_COIN_ID_TAIL_PUZZLE => MELTABLE_GENESIS_BY_COIN_ID_TAIL_PUZZLE_HASH);
+        Ok(())
+    }
+}
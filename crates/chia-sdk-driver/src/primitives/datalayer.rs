@@ -1,6 +1,16 @@
+mod datalayer_tree;
 mod datastore;
 mod datastore_info;
 mod datastore_launcher;
+mod mirror;
 
+pub use datalayer_tree::*;
 pub use datastore::*;
 pub use datastore_info::*;
+pub use mirror::*;
+
+#[cfg(feature = "peer")]
+mod datastore_history;
+
+#[cfg(feature = "peer")]
+pub use datastore_history::*;
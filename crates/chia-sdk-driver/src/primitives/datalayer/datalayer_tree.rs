@@ -0,0 +1,517 @@
+use std::collections::HashMap;
+
+use chia_protocol::{Bytes, Bytes32};
+use clvm_utils::{tree_hash_atom, tree_hash_pair};
+
+use crate::DriverError;
+
+/// Calculates the hash of a terminal node, which is the tree hash of `(key . value)`.
+pub fn datalayer_leaf_hash(key: &[u8], value: &[u8]) -> Bytes32 {
+    tree_hash_pair(tree_hash_atom(key), tree_hash_atom(value)).into()
+}
+
+/// Calculates the hash of an internal node, which is the tree hash of `(left . right)`.
+pub fn datalayer_internal_hash(left: Bytes32, right: Bytes32) -> Bytes32 {
+    tree_hash_pair(left.into(), right.into()).into()
+}
+
+/// A change to the key value pairs of a [`DataLayerTree`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DataLayerChange {
+    /// Inserts a new key, which must not already exist.
+    Insert { key: Bytes, value: Bytes },
+    /// Inserts a new key, or replaces the value of an existing key in place.
+    Upsert { key: Bytes, value: Bytes },
+    /// Deletes an existing key.
+    Delete { key: Bytes },
+}
+
+/// The side of the combined hash that the other hash is on.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofSide {
+    Left,
+    Right,
+}
+
+/// A single step from a node up to its parent in a [`DataLayerProof`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DataLayerProofLayer {
+    pub other_hash_side: ProofSide,
+    pub other_hash: Bytes32,
+    pub combined_hash: Bytes32,
+}
+
+/// A proof that a key value pair is included in a datalayer tree with a given root hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataLayerProof {
+    pub key: Bytes,
+    pub value: Bytes,
+    /// The layers from the terminal node up to the root.
+    pub layers: Vec<DataLayerProofLayer>,
+}
+
+impl DataLayerProof {
+    pub fn node_hash(&self) -> Bytes32 {
+        datalayer_leaf_hash(&self.key, &self.value)
+    }
+
+    /// The root hash that the proof claims to be included in.
+    pub fn root_hash(&self) -> Bytes32 {
+        self.layers
+            .last()
+            .map_or_else(|| self.node_hash(), |layer| layer.combined_hash)
+    }
+
+    /// Checks that every layer of the proof hashes up to the next one, and that the result is the given root hash.
+    pub fn verify(&self, root_hash: Bytes32) -> bool {
+        let mut hash = self.node_hash();
+
+        for layer in &self.layers {
+            hash = match layer.other_hash_side {
+                ProofSide::Left => datalayer_internal_hash(layer.other_hash, hash),
+                ProofSide::Right => datalayer_internal_hash(hash, layer.other_hash),
+            };
+
+            if hash != layer.combined_hash {
+                return false;
+            }
+        }
+
+        hash == root_hash
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    hash: Bytes32,
+    parent: Option<usize>,
+    kind: NodeKind,
+}
+
+#[derive(Debug, Clone)]
+enum NodeKind {
+    Internal { left: usize, right: usize },
+    Terminal { key: Bytes, value: Bytes },
+}
+
+/// A local copy of the key value pairs in a datalayer store, used to calculate its root hash.
+///
+/// Terminal and internal nodes are hashed the same way as Chia's datalayer, so a tree with the same
+/// shape has the same root hash. New keys are placed by walking down the tree with bits of their leaf
+/// hash, which is deterministic but isn't guaranteed to match where Chia's datalayer would place them,
+/// so the root hash is only reproducible by applying the same changes in the same order to this type.
+/// The root hash of an empty tree is all zeros.
+#[derive(Debug, Default, Clone)]
+pub struct DataLayerTree {
+    nodes: Vec<Node>,
+    free: Vec<usize>,
+    root: Option<usize>,
+    terminals: HashMap<Vec<u8>, usize>,
+}
+
+impl DataLayerTree {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn root_hash(&self) -> Bytes32 {
+        self.root
+            .map_or_else(Bytes32::default, |root| self.nodes[root].hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.terminals.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terminals.is_empty()
+    }
+
+    pub fn get(&self, key: &[u8]) -> Option<&Bytes> {
+        let index = *self.terminals.get(key)?;
+        match &self.nodes[index].kind {
+            NodeKind::Terminal { value, .. } => Some(value),
+            NodeKind::Internal { .. } => None,
+        }
+    }
+
+    /// Inserts a new key value pair, and returns the new root hash.
+    pub fn insert(&mut self, key: Bytes, value: Bytes) -> Result<Bytes32, DriverError> {
+        if self.terminals.contains_key(key.as_ref()) {
+            return Err(DriverError::DuplicateDataLayerKey(key));
+        }
+
+        let seed = datalayer_leaf_hash(&key, &value);
+        let key_bytes = key.to_vec();
+        let terminal = self.alloc(Node {
+            hash: seed,
+            parent: None,
+            kind: NodeKind::Terminal { key, value },
+        });
+        self.terminals.insert(key_bytes, terminal);
+
+        let Some(root) = self.root else {
+            self.root = Some(terminal);
+            return Ok(seed);
+        };
+
+        // The seed picks a path down the tree to the terminal node that the new one is paired with.
+        let mut reference = root;
+        let mut depth = 0;
+
+        while let NodeKind::Internal { left, right } = self.nodes[reference].kind {
+            let bit = (seed[31 - depth / 8] >> (depth % 8)) & 1;
+            reference = if bit == 0 { left } else { right };
+            depth += 1;
+        }
+
+        let (left, right) = if seed[0] < 128 {
+            (terminal, reference)
+        } else {
+            (reference, terminal)
+        };
+
+        let parent = self.nodes[reference].parent;
+        let internal = self.alloc(Node {
+            hash: datalayer_internal_hash(self.nodes[left].hash, self.nodes[right].hash),
+            parent,
+            kind: NodeKind::Internal { left, right },
+        });
+
+        self.nodes[terminal].parent = Some(internal);
+        self.nodes[reference].parent = Some(internal);
+        self.replace_child(parent, reference, internal);
+        self.rehash(parent);
+
+        Ok(self.root_hash())
+    }
+
+    /// Inserts a key value pair, or replaces the value if the key already exists, and returns the new root hash.
+    pub fn upsert(&mut self, key: Bytes, value: Bytes) -> Result<Bytes32, DriverError> {
+        let Some(&index) = self.terminals.get(key.as_ref()) else {
+            return self.insert(key, value);
+        };
+
+        self.nodes[index].hash = datalayer_leaf_hash(&key, &value);
+        self.nodes[index].kind = NodeKind::Terminal { key, value };
+        self.rehash(self.nodes[index].parent);
+
+        Ok(self.root_hash())
+    }
+
+    /// Deletes an existing key, and returns the new root hash.
+    pub fn delete(&mut self, key: &[u8]) -> Result<Bytes32, DriverError> {
+        let Some(terminal) = self.terminals.remove(key) else {
+            return Err(DriverError::MissingDataLayerKey(key.to_vec().into()));
+        };
+
+        let parent = self.nodes[terminal].parent;
+        self.free.push(terminal);
+
+        let Some(parent) = parent else {
+            self.root = None;
+            return Ok(self.root_hash());
+        };
+
+        // The sibling of the deleted node takes the place of their parent.
+        let NodeKind::Internal { left, right } = self.nodes[parent].kind else {
+            unreachable!("the parent of a node must be an internal node");
+        };
+        let sibling = if left == terminal { right } else { left };
+
+        let grandparent = self.nodes[parent].parent;
+        self.nodes[sibling].parent = grandparent;
+        self.replace_child(grandparent, parent, sibling);
+        self.free.push(parent);
+        self.rehash(grandparent);
+
+        Ok(self.root_hash())
+    }
+
+    /// Applies a batch of changes in order, and returns the new root hash.
+    ///
+    /// Each change is applied the same way as calling [`DataLayerTree::insert`], [`DataLayerTree::upsert`] or
+    /// [`DataLayerTree::delete`] individually. Unlike Chia's `insert_batch`, new keys aren't built into a
+    /// balanced subtree first, so the resulting shape differs from a batch update in Chia's datalayer.
+    /// If any of the changes fail, the tree is left unchanged.
+    pub fn apply(
+        &mut self,
+        changes: impl IntoIterator<Item = DataLayerChange>,
+    ) -> Result<Bytes32, DriverError> {
+        let mut tree = self.clone();
+
+        for change in changes {
+            match change {
+                DataLayerChange::Insert { key, value } => tree.insert(key, value)?,
+                DataLayerChange::Upsert { key, value } => tree.upsert(key, value)?,
+                DataLayerChange::Delete { key } => tree.delete(&key)?,
+            };
+        }
+
+        *self = tree;
+
+        Ok(self.root_hash())
+    }
+
+    /// Generates a proof that the key and its current value are included in the tree.
+    pub fn proof(&self, key: &[u8]) -> Option<DataLayerProof> {
+        let mut index = *self.terminals.get(key)?;
+
+        let NodeKind::Terminal { key, value } = &self.nodes[index].kind else {
+            return None;
+        };
+
+        let mut layers = Vec::new();
+
+        while let Some(parent) = self.nodes[index].parent {
+            let NodeKind::Internal { left, right } = self.nodes[parent].kind else {
+                return None;
+            };
+
+            let (other_hash_side, other) = if left == index {
+                (ProofSide::Right, right)
+            } else {
+                (ProofSide::Left, left)
+            };
+
+            layers.push(DataLayerProofLayer {
+                other_hash_side,
+                other_hash: self.nodes[other].hash,
+                combined_hash: self.nodes[parent].hash,
+            });
+
+            index = parent;
+        }
+
+        Some(DataLayerProof {
+            key: key.clone(),
+            value: value.clone(),
+            layers,
+        })
+    }
+
+    fn alloc(&mut self, node: Node) -> usize {
+        if let Some(index) = self.free.pop() {
+            self.nodes[index] = node;
+            index
+        } else {
+            self.nodes.push(node);
+            self.nodes.len() - 1
+        }
+    }
+
+    fn replace_child(&mut self, parent: Option<usize>, old: usize, new: usize) {
+        let Some(parent) = parent else {
+            self.root = Some(new);
+            return;
+        };
+
+        if let NodeKind::Internal { left, right } = &mut self.nodes[parent].kind {
+            if *left == old {
+                *left = new;
+            } else if *right == old {
+                *right = new;
+            }
+        }
+    }
+
+    fn rehash(&mut self, mut index: Option<usize>) {
+        while let Some(current) = index {
+            if let NodeKind::Internal { left, right } = self.nodes[current].kind {
+                self.nodes[current].hash =
+                    datalayer_internal_hash(self.nodes[left].hash, self.nodes[right].hash);
+            }
+            index = self.nodes[current].parent;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use hex_literal::hex;
+
+    use crate::SpendContext;
+
+    use super::*;
+
+    fn pair(index: u32) -> (Bytes, Bytes) {
+        (
+            format!("key{index}").into_bytes().into(),
+            format!("value{index}").into_bytes().into(),
+        )
+    }
+
+    #[test]
+    fn test_leaf_hash() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        let (key, value) = pair(0);
+        let ptr = ctx.alloc(&(key.clone(), value.clone()))?;
+
+        assert_eq!(datalayer_leaf_hash(&key, &value), ctx.tree_hash(ptr).into());
+
+        Ok(())
+    }
+
+    // These were computed with Python's `hashlib`, from the node hashing formulas rather than this crate.
+    // They weren't generated by Chia's datalayer, since its reference implementation isn't available here.
+    #[test]
+    fn test_known_hashes() -> anyhow::Result<()> {
+        let leaf_a = datalayer_leaf_hash(b"a", b"1");
+        let leaf_b = datalayer_leaf_hash(b"b", b"2");
+
+        assert_eq!(
+            leaf_a,
+            Bytes32::new(hex!(
+                "fccd5e0348c13dee1e5bcd05a539220004478d5668b0584d3f26a088417a42b6"
+            ))
+        );
+        assert_eq!(
+            leaf_b,
+            Bytes32::new(hex!(
+                "6c5f9681dcdd49511629484bafbf2871a440acbc04794de6bfc51a58b02ba773"
+            ))
+        );
+        assert_eq!(
+            datalayer_internal_hash(leaf_a, leaf_b),
+            Bytes32::new(hex!(
+                "61cc2651a4f8ad01004ad7d4d34f8f3f8ae119cab4d4f3e5eb087c77888977d3"
+            ))
+        );
+        assert_eq!(
+            datalayer_leaf_hash(b"", b""),
+            Bytes32::new(hex!(
+                "52db9ef97986e7382ef78b8eae2dacdbb2ce823ed1396a0fb2f7f120a2b40a63"
+            ))
+        );
+
+        // The first byte of the new leaf hash is below 128, so it's placed on the left.
+        let mut tree = DataLayerTree::new();
+        tree.insert(b"a".to_vec().into(), b"1".to_vec().into())?;
+        assert_eq!(
+            tree.insert(b"b".to_vec().into(), b"2".to_vec().into())?,
+            Bytes32::new(hex!(
+                "7cdafcddcb2f183ed3ae83b7ef3762f576149c248770af671fb222e537794bc3"
+            ))
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_insert_and_delete() -> anyhow::Result<()> {
+        let mut tree = DataLayerTree::new();
+        assert_eq!(tree.root_hash(), Bytes32::default());
+
+        let (key, value) = pair(0);
+        let root = tree.insert(key.clone(), value.clone())?;
+        assert_eq!(root, datalayer_leaf_hash(&key, &value));
+
+        let mut roots = vec![root];
+
+        for index in 1..50 {
+            let (key, value) = pair(index);
+            roots.push(tree.insert(key, value)?);
+        }
+
+        assert_eq!(tree.len(), 50);
+        assert_eq!(tree.get(&pair(7).0), Some(&pair(7).1));
+        assert!(matches!(
+            tree.insert(pair(7).0, pair(8).1),
+            Err(DriverError::DuplicateDataLayerKey(..))
+        ));
+
+        // Deleting the most recently inserted key restores the previous tree.
+        for index in (1..50).rev() {
+            assert_eq!(tree.delete(&pair(index).0)?, roots[index as usize - 1]);
+        }
+
+        assert_eq!(tree.delete(&pair(0).0)?, Bytes32::default());
+        assert!(tree.is_empty());
+        assert!(matches!(
+            tree.delete(&pair(0).0),
+            Err(DriverError::MissingDataLayerKey(..))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_batch_update() -> anyhow::Result<()> {
+        let mut tree = DataLayerTree::new();
+        let mut expected = DataLayerTree::new();
+
+        let changes: Vec<DataLayerChange> = (0..20)
+            .map(|index| {
+                let (key, value) = pair(index);
+                DataLayerChange::Insert { key, value }
+            })
+            .chain([
+                DataLayerChange::Delete { key: pair(3).0 },
+                DataLayerChange::Upsert {
+                    key: pair(5).0,
+                    value: pair(50).1,
+                },
+            ])
+            .collect();
+
+        for index in 0..20 {
+            let (key, value) = pair(index);
+            expected.insert(key, value)?;
+        }
+        expected.delete(&pair(3).0)?;
+        expected.upsert(pair(5).0, pair(50).1)?;
+
+        assert_eq!(tree.apply(changes)?, expected.root_hash());
+        assert_eq!(tree.get(&pair(5).0), Some(&pair(50).1));
+
+        // A failed batch doesn't change the tree.
+        let root_hash = tree.root_hash();
+        assert!(tree
+            .apply([
+                DataLayerChange::Delete { key: pair(0).0 },
+                DataLayerChange::Delete { key: pair(3).0 },
+            ])
+            .is_err());
+        assert_eq!(tree.root_hash(), root_hash);
+        assert_eq!(tree.len(), 19);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inclusion_proofs() -> anyhow::Result<()> {
+        let mut tree = DataLayerTree::new();
+
+        for index in 0..30 {
+            let (key, value) = pair(index);
+            tree.insert(key, value)?;
+        }
+
+        let root_hash = tree.root_hash();
+
+        for index in 0..30 {
+            let proof = tree.proof(&pair(index).0).expect("missing proof");
+            assert_eq!(proof.root_hash(), root_hash);
+            assert!(proof.verify(root_hash));
+        }
+
+        assert_eq!(tree.proof(&pair(30).0), None);
+
+        // A proof is only valid for the value that was proven.
+        let mut proof = tree.proof(&pair(0).0).expect("missing proof");
+        proof.value = pair(1).1;
+        assert!(!proof.verify(root_hash));
+
+        // And only for the current root.
+        let proof = tree.proof(&pair(0).0).expect("missing proof");
+        let new_root_hash = tree.upsert(pair(1).0, pair(2).1)?;
+        assert!(!proof.verify(new_root_hash));
+        assert!(tree
+            .proof(&pair(0).0)
+            .expect("missing proof")
+            .verify(new_root_hash));
+
+        Ok(())
+    }
+}
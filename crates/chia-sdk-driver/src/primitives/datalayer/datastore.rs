@@ -347,6 +347,7 @@ where
                 state_layer.metadata_updater_puzzle_hash,
                 inner_new_metadata_condition,
             )?
+            .new_metadata
         } else {
             state_layer.metadata
         };
@@ -534,7 +535,7 @@ impl<M> DataStore<M> {
         let new_puzzle_hash = if new_delegated_puzzles.is_empty() {
             new_inner_puzzle_hash
         } else {
-            let new_merkle_root = get_merkle_tree(ctx, new_delegated_puzzles.clone())?.root;
+            let new_merkle_root = get_merkle_tree(ctx, new_delegated_puzzles.clone())?.root();
             DelegationLayerArgs::curry_tree_hash(
                 launcher_id,
                 new_inner_puzzle_hash,
@@ -821,7 +822,7 @@ pub mod tests {
         // admin: remove writer from delegated puzzles
         let delegated_puzzles = vec![admin_delegated_puzzle, oracle_delegated_puzzle];
         let new_merkle_tree = get_merkle_tree(ctx, delegated_puzzles.clone())?;
-        let new_merkle_root = new_merkle_tree.root;
+        let new_merkle_root = new_merkle_tree.root();
 
         let new_merkle_root_condition = UpdateDataStoreMerkleRoot {
             new_merkle_root,
@@ -1038,7 +1039,7 @@ pub mod tests {
             let new_merkle_tree = get_merkle_tree(ctx, dst_delegated_puzzles.clone())?;
 
             let new_merkle_root_condition = UpdateDataStoreMerkleRoot {
-                new_merkle_root: new_merkle_tree.root,
+                new_merkle_root: new_merkle_tree.root(),
                 memos: DataStore::<DataStoreMetadata>::get_recreation_memos(
                     src_datastore.info.launcher_id,
                     owner_puzzle_hash.into(),
@@ -1948,7 +1949,7 @@ pub mod tests {
         let merkle_tree = get_merkle_tree(ctx, delegated_puzzles.clone())?;
 
         let delegation_layer =
-            DelegationLayer::new(Bytes32::default(), Bytes32::default(), merkle_tree.root);
+            DelegationLayer::new(Bytes32::default(), Bytes32::default(), merkle_tree.root());
 
         let puzzle_ptr = delegation_layer.construct_puzzle(ctx)?;
 
@@ -0,0 +1,230 @@
+use chia_protocol::{Bytes32, CoinState};
+use chia_sdk_client::Peer;
+use clvm_traits::{FromClvm, ToClvm};
+use clvmr::Allocator;
+
+use crate::{request_coin_state, trace_child, DriverError};
+
+use super::{DataStore, DataStoreMetadata, DelegatedPuzzle, MetadataWithRootHash};
+
+/// A single generation of a [`DataStore`], along with the height that its coin was created at.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DataStoreGeneration<M = DataStoreMetadata> {
+    pub height: Option<u32>,
+    pub datastore: DataStore<M>,
+}
+
+impl<M> DataStoreGeneration<M>
+where
+    M: MetadataWithRootHash,
+{
+    pub fn root_hash(&self) -> Bytes32 {
+        self.datastore.info.metadata.root_hash()
+    }
+
+    pub fn owner_puzzle_hash(&self) -> Bytes32 {
+        self.datastore.info.owner_puzzle_hash
+    }
+
+    pub fn delegated_puzzles(&self) -> &[DelegatedPuzzle] {
+        &self.datastore.info.delegated_puzzles
+    }
+}
+
+impl DataStoreGeneration<DataStoreMetadata> {
+    pub fn label(&self) -> Option<&str> {
+        self.datastore.info.metadata.label.as_deref()
+    }
+
+    pub fn description(&self) -> Option<&str> {
+        self.datastore.info.metadata.description.as_deref()
+    }
+}
+
+/// Every generation of a [`DataStore`] since it was launched, which can be synced incrementally from a peer.
+#[derive(Debug, Clone)]
+pub struct DataStoreHistory<M = DataStoreMetadata> {
+    launcher_id: Bytes32,
+    tip: Option<CoinState>,
+    generations: Vec<DataStoreGeneration<M>>,
+}
+
+impl<M> DataStoreHistory<M>
+where
+    M: ToClvm<Allocator> + FromClvm<Allocator> + MetadataWithRootHash,
+{
+    pub fn new(launcher_id: Bytes32) -> Self {
+        Self {
+            launcher_id,
+            tip: None,
+            generations: Vec::new(),
+        }
+    }
+
+    /// Resumes syncing from generations that were synced previously, starting at the last one.
+    pub fn resume(launcher_id: Bytes32, generations: Vec<DataStoreGeneration<M>>) -> Self {
+        Self {
+            launcher_id,
+            tip: None,
+            generations,
+        }
+    }
+
+    pub fn launcher_id(&self) -> Bytes32 {
+        self.launcher_id
+    }
+
+    /// The generations that have been synced so far, in order.
+    pub fn generations(&self) -> &[DataStoreGeneration<M>] {
+        &self.generations
+    }
+
+    pub fn latest(&self) -> Option<&DataStoreGeneration<M>> {
+        self.generations.last()
+    }
+
+    /// Whether the latest coin was spent without creating a child, as of the last sync.
+    pub fn is_melted(&self) -> bool {
+        self.tip.is_some_and(|tip| tip.spent_height.is_some())
+    }
+
+    /// Requests the generations that were created since the last sync, and returns them.
+    pub async fn sync(
+        &mut self,
+        allocator: &mut Allocator,
+        peer: &Peer,
+        genesis_challenge: Bytes32,
+    ) -> Result<&[DataStoreGeneration<M>], DriverError> {
+        let start = self.generations.len();
+
+        let mut tip = match self.tip {
+            Some(tip) if tip.spent_height.is_some() => tip,
+            _ => {
+                let coin_id = self.latest().map_or(self.launcher_id, |generation| {
+                    generation.datastore.coin.coin_id()
+                });
+                request_coin_state(peer, coin_id, genesis_challenge).await?
+            }
+        };
+
+        // The tip is kept in step with the generations, so that a failed sync can be resumed.
+        self.tip = Some(tip);
+
+        while let Some((child, datastores)) = trace_child(
+            allocator,
+            peer,
+            self.launcher_id,
+            &tip,
+            self.latest().map(|generation| &generation.datastore),
+        )
+        .await?
+        {
+            self.generations
+                .extend(datastores.into_iter().map(|datastore| DataStoreGeneration {
+                    height: child.created_height,
+                    datastore,
+                }));
+
+            tip = child;
+            self.tip = Some(tip);
+        }
+
+        Ok(&self.generations[start..])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_protocol::SpendBundle;
+    use chia_puzzles::standard::StandardArgs;
+    use chia_sdk_test::{sign_transaction, test_secret_key, PeerSimulator};
+    use chia_sdk_types::Conditions;
+
+    use crate::{Launcher, SpendContext, SpendWithConditions, StandardLayer};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_datastore_history() -> anyhow::Result<()> {
+        let sim = PeerSimulator::new().await?;
+        let peer = sim.connect().await?;
+        let genesis_challenge = sim.config().constants.genesis_challenge;
+        let ctx = &mut SpendContext::new();
+
+        let sk = test_secret_key()?;
+        let p2 = StandardLayer::new(sk.public_key());
+        let puzzle_hash = StandardArgs::curry_tree_hash(sk.public_key()).into();
+        let coin = sim.mint_coin(puzzle_hash, 1).await;
+
+        let (launch_singleton, mut datastore) = Launcher::new(coin.coin_id(), 1).mint_datastore(
+            ctx,
+            DataStoreMetadata::root_hash_only(Bytes32::default()),
+            puzzle_hash.into(),
+            vec![],
+        )?;
+        p2.spend(ctx, coin, launch_singleton)?;
+
+        let mut history = DataStoreHistory::<DataStoreMetadata>::new(datastore.info.launcher_id);
+
+        for index in 1..=3 {
+            let metadata = DataStoreMetadata {
+                root_hash: Bytes32::new([index; 32]),
+                label: Some(format!("label {index}")),
+                description: None,
+                bytes: None,
+            };
+
+            let conditions = Conditions::new()
+                .with(DataStore::<DataStoreMetadata>::owner_create_coin_condition(
+                    ctx,
+                    datastore.info.launcher_id,
+                    puzzle_hash,
+                    vec![],
+                    false,
+                )?)
+                .with(DataStore::new_metadata_condition(ctx, metadata)?);
+            let inner_spend = p2.spend_with_conditions(ctx, conditions)?;
+            let coin_spend = datastore.clone().spend(ctx, inner_spend)?;
+
+            datastore = DataStore::from_spend(&mut ctx.allocator, &coin_spend, &[])?
+                .expect("missing child datastore");
+            ctx.insert(coin_spend);
+
+            let coin_spends = ctx.take();
+            let signature = sign_transaction(&coin_spends, &[sk.clone()])?;
+            let ack = peer
+                .send_transaction(SpendBundle::new(coin_spends, signature))
+                .await?;
+            assert_eq!(ack.error, None);
+
+            // Only the generations created since the last sync are returned.
+            let new_generations = history
+                .sync(&mut ctx.allocator, &peer, genesis_challenge)
+                .await?;
+            assert_eq!(new_generations.len(), if index == 1 { 2 } else { 1 });
+        }
+
+        let generations = history.generations();
+        assert_eq!(generations.len(), 4);
+        assert_eq!(generations[0].root_hash(), Bytes32::default());
+        assert_eq!(generations[0].label(), None);
+        assert_eq!(generations[3].root_hash(), Bytes32::new([3; 32]));
+        assert_eq!(generations[3].label(), Some("label 3"));
+        assert_eq!(generations[3].owner_puzzle_hash(), puzzle_hash);
+        assert_eq!(generations[3].datastore, datastore);
+        assert!(generations
+            .iter()
+            .all(|generation| generation.height.is_some()));
+        assert!(!history.is_melted());
+
+        // Syncing can be resumed from a previously synced generation.
+        let mut resumed =
+            DataStoreHistory::resume(datastore.info.launcher_id, generations[..2].to_vec());
+        resumed
+            .sync(&mut ctx.allocator, &peer, genesis_challenge)
+            .await?;
+        assert_eq!(resumed.generations(), history.generations());
+
+        Ok(())
+    }
+}
@@ -208,7 +208,7 @@ impl<M> DataStoreInfo<M> {
                 DelegationLayer::new(
                     self.launcher_id,
                     self.owner_puzzle_hash,
-                    get_merkle_tree(ctx, self.delegated_puzzles)?.root,
+                    get_merkle_tree(ctx, self.delegated_puzzles)?.root(),
                 ),
             ),
         ))
@@ -244,7 +244,7 @@ impl<M> DataStoreInfo<M> {
                         mod_hash: DELEGATION_LAYER_PUZZLE_HASH.into(),
                         launcher_id: self.launcher_id,
                         owner_puzzle_hash: self.owner_puzzle_hash,
-                        merkle_root: get_merkle_tree(ctx, self.delegated_puzzles.clone())?.root,
+                        merkle_root: get_merkle_tree(ctx, self.delegated_puzzles.clone())?.root(),
                     },
                 }
                 .tree_hash(),
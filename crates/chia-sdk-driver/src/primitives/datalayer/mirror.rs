@@ -0,0 +1,413 @@
+use chia_protocol::{Bytes, Bytes32, Coin};
+use chia_sdk_types::{run_puzzle, Condition, Conditions};
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::{CurriedProgram, ToTreeHash, TreeHash};
+use clvmr::{Allocator, NodePtr};
+use hex_literal::hex;
+
+use crate::{DriverError, Spend, SpendContext, SpendWithConditions};
+
+/// The p2 parent puzzle can be spent by revealing the puzzle and solution of the coin that created it.
+/// The morpher is run on the tree hash of the parent's inner puzzle to calculate the parent puzzle hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(curry)]
+pub struct P2ParentArgs<M> {
+    pub morpher: M,
+}
+
+impl<M> P2ParentArgs<M> {
+    pub fn new(morpher: M) -> Self {
+        Self { morpher }
+    }
+}
+
+/// The solution for the p2 parent puzzle, which spends the parent's inner puzzle again.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+pub struct P2ParentSolution<P = NodePtr, S = NodePtr> {
+    pub parent_parent_id: Bytes32,
+    pub parent_inner_puzzle: P,
+    pub parent_amount: u64,
+    pub parent_solution: S,
+}
+
+/// A coin that advertises URLs that the data of a [`DataStore`](crate::DataStore) can be downloaded from.
+///
+/// Mirrors use the p2 parent puzzle without a morpher, so they can only be deleted by the owner of the
+/// coin that created them. They're hinted with the launcher id of the store, followed by the URLs.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Mirror {
+    pub coin: Coin,
+    /// The coin that created the mirror, whose p2 puzzle is used to delete it.
+    pub parent_coin: Coin,
+    pub launcher_id: Bytes32,
+    pub urls: Vec<String>,
+}
+
+impl Mirror {
+    /// The puzzle hash that all mirror coins share.
+    pub fn puzzle_hash() -> TreeHash {
+        CurriedProgram {
+            program: P2_PARENT_PUZZLE_HASH,
+            args: P2ParentArgs::new(1),
+        }
+        .tree_hash()
+    }
+
+    /// Creates a mirror as a child of the parent coin, and returns the conditions that the parent must output.
+    pub fn create(
+        parent_coin: Coin,
+        launcher_id: Bytes32,
+        amount: u64,
+        urls: Vec<String>,
+    ) -> (Conditions, Self) {
+        let mirror = Self {
+            coin: Coin::new(parent_coin.coin_id(), Self::puzzle_hash().into(), amount),
+            parent_coin,
+            launcher_id,
+            urls,
+        };
+
+        let conditions =
+            Conditions::new().create_coin(mirror.coin.puzzle_hash, amount, mirror.memos());
+
+        (conditions, mirror)
+    }
+
+    pub fn memos(&self) -> Vec<Bytes> {
+        let mut memos = vec![self.launcher_id.into()];
+        memos.extend(self.urls.iter().map(|url| url.as_bytes().to_vec().into()));
+        memos
+    }
+
+    /// Parses the mirrors created by a coin spend.
+    pub fn parse_children(
+        allocator: &mut Allocator,
+        parent_coin: Coin,
+        parent_puzzle: NodePtr,
+        parent_solution: NodePtr,
+    ) -> Result<Vec<Self>, DriverError> {
+        let output = run_puzzle(allocator, parent_puzzle, parent_solution)?;
+        let conditions = Vec::<Condition>::from_clvm(allocator, output)?;
+
+        let puzzle_hash: Bytes32 = Self::puzzle_hash().into();
+
+        Ok(conditions
+            .into_iter()
+            .filter_map(Condition::into_create_coin)
+            .filter(|create_coin| create_coin.puzzle_hash == puzzle_hash)
+            .filter_map(|create_coin| {
+                let (launcher_id, urls) = create_coin.memos.split_first()?;
+                let launcher_id = Bytes32::try_from(launcher_id.as_ref()).ok()?;
+                let urls = urls
+                    .iter()
+                    .map(|url| String::from_utf8(url.to_vec()).ok())
+                    .collect::<Option<Vec<String>>>()?;
+
+                Some(Self {
+                    coin: Coin::new(parent_coin.coin_id(), puzzle_hash, create_coin.amount),
+                    parent_coin,
+                    launcher_id,
+                    urls,
+                })
+            })
+            .collect())
+    }
+
+    /// Deletes the mirror by spending the p2 puzzle of the parent coin again, with the given conditions.
+    ///
+    /// The amount of the mirror is reserved as a fee unless the conditions create a coin with it.
+    pub fn delete<I>(
+        &self,
+        ctx: &mut SpendContext,
+        inner: &I,
+        conditions: Conditions,
+    ) -> Result<(), DriverError>
+    where
+        I: SpendWithConditions,
+    {
+        let inner_spend = inner.spend_with_conditions(ctx, conditions)?;
+
+        let p2_parent_puzzle = ctx.p2_parent_puzzle()?;
+        let puzzle = ctx.alloc(&CurriedProgram {
+            program: p2_parent_puzzle,
+            args: P2ParentArgs::new(1),
+        })?;
+        let solution = ctx.alloc(&P2ParentSolution {
+            parent_parent_id: self.parent_coin.parent_coin_info,
+            parent_inner_puzzle: inner_spend.puzzle,
+            parent_amount: self.parent_coin.amount,
+            parent_solution: inner_spend.solution,
+        })?;
+
+        ctx.spend(self.coin, Spend::new(puzzle, solution))
+    }
+}
+
+#[cfg(feature = "peer")]
+impl Mirror {
+    /// Requests the unspent mirrors of a store, by looking up coins hinted with its launcher id.
+    pub async fn fetch(
+        allocator: &mut Allocator,
+        peer: &chia_sdk_client::Peer,
+        launcher_id: Bytes32,
+        genesis_challenge: Bytes32,
+    ) -> Result<Vec<Self>, DriverError> {
+        let puzzle_hash: Bytes32 = Self::puzzle_hash().into();
+
+        let mut coin_states = Vec::new();
+        let mut previous_height = None;
+        let mut header_hash = genesis_challenge;
+
+        loop {
+            let response = peer
+                .request_puzzle_state(
+                    vec![launcher_id],
+                    previous_height,
+                    header_hash,
+                    chia_protocol::CoinStateFilters::new(false, true, true, 0),
+                    false,
+                )
+                .await?
+                .map_err(|rejection| DriverError::RejectPuzzleState(rejection.reason))?;
+
+            coin_states.extend(response.coin_states);
+
+            if response.is_finished {
+                break;
+            }
+
+            previous_height = Some(response.height);
+            header_hash = response.header_hash;
+        }
+
+        let mut mirrors = Vec::new();
+
+        for coin_state in coin_states {
+            if coin_state.coin.puzzle_hash != puzzle_hash || coin_state.spent_height.is_some() {
+                continue;
+            }
+
+            let parent_id = coin_state.coin.parent_coin_info;
+            let parent_state =
+                crate::request_coin_state(peer, parent_id, genesis_challenge).await?;
+
+            let Some(spent_height) = parent_state.spent_height else {
+                continue;
+            };
+
+            let response = peer
+                .request_puzzle_and_solution(parent_id, spent_height)
+                .await?
+                .map_err(|rejection| DriverError::RejectPuzzleSolution(rejection.coin_name))?;
+
+            let parent_puzzle = response.puzzle.to_clvm(allocator)?;
+            let parent_solution = response.solution.to_clvm(allocator)?;
+
+            mirrors.extend(
+                Self::parse_children(allocator, parent_state.coin, parent_puzzle, parent_solution)?
+                    .into_iter()
+                    .filter(|mirror| {
+                        mirror.coin == coin_state.coin && mirror.launcher_id == launcher_id
+                    }),
+            );
+        }
+
+        Ok(mirrors)
+    }
+}
+
+/// ```text
+/// (mod
+///   (
+///     MORPHER  ; For no morphing, 1
+///     parent_parent_id
+///     parent_inner_puz
+///     parent_amount
+///     parent_solution
+///   )
+///
+///   (include condition_codes.clib)
+///   (include curry-and-treehash.clib)
+///
+///   (c
+///     (list ASSERT_MY_PARENT_ID
+///       (calculate_coin_id parent_parent_id (a MORPHER (sha256tree parent_inner_puz)) parent_amount)
+///     )
+///     (a parent_inner_puz parent_solution)
+///   )
+/// )
+/// ```
+pub const P2_PARENT_PUZZLE: [u8; 242] = hex!(
+    "
+    ff02ffff01ff04ffff04ff08ffff04ffff02ff0affff04ff02ffff04ff0bffff
+    04ffff02ff05ffff02ff0effff04ff02ffff04ff17ff8080808080ffff04ff2f
+    ff808080808080ff808080ffff02ff17ff5f8080ffff04ffff01ffff4720ffff
+    02ffff03ffff22ffff09ffff0dff0580ff0c80ffff09ffff0dff0b80ff0c80ff
+    ff15ff17ffff0181ff8080ffff01ff0bff05ff0bff1780ffff01ff088080ff01
+    80ff02ffff03ffff07ff0580ffff01ff0bffff0102ffff02ff0effff04ff02ff
+    ff04ff09ff80808080ffff02ff0effff04ff02ffff04ff0dff8080808080ffff
+    01ff0bffff0101ff058080ff0180ff018080
+    "
+);
+
+pub const P2_PARENT_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "b10ce2d0b18dcf8c21ddfaf55d9b9f0adcbf1e0beb55b1a8b9cad9bbff4e5f22"
+));
+
+#[cfg(test)]
+mod tests {
+    use chia_bls::SecretKey;
+    use chia_puzzles::standard::StandardArgs;
+    use chia_sdk_test::{test_secret_keys, Simulator};
+
+    use crate::{
+        assert_puzzle_hash, tests::RootHash, DataStoreMetadata, Launcher, MetadataWithRootHash,
+        StandardLayer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_puzzle_hashes() -> anyhow::Result<()> {
+        assert_puzzle_hash!(P2_PARENT_PUZZLE => P2_PARENT_PUZZLE_HASH);
+        Ok(())
+    }
+
+    #[test]
+    fn test_mirror() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+
+        let [sk]: [SecretKey; 1] = test_secret_keys(1)?.try_into().unwrap();
+        let pk = sk.public_key();
+        let p2 = StandardLayer::new(pk);
+
+        let puzzle_hash = StandardArgs::curry_tree_hash(pk).into();
+        let coin = sim.new_coin(puzzle_hash, 1);
+
+        let ctx = &mut SpendContext::new();
+
+        let (launch_singleton, datastore) = Launcher::new(coin.coin_id(), 1).mint_datastore(
+            ctx,
+            DataStoreMetadata::root_hash_only(RootHash::Zero.value()),
+            puzzle_hash.into(),
+            vec![],
+        )?;
+        p2.spend(ctx, coin, launch_singleton)?;
+
+        let launcher_id = datastore.info.launcher_id;
+        let urls = vec![
+            "https://example.com".to_string(),
+            "http://127.0.0.1:8575".to_string(),
+        ];
+
+        let parent_coin = sim.new_coin(puzzle_hash, 100);
+        let (create_mirror, mirror) = Mirror::create(parent_coin, launcher_id, 100, urls);
+        p2.spend(ctx, parent_coin, create_mirror)?;
+
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        assert!(sim.coin_state(mirror.coin.coin_id()).is_some());
+        assert!(sim
+            .hinted_coins(launcher_id)
+            .contains(&mirror.coin.coin_id()));
+
+        // The mirror can be parsed from the spend of its parent.
+        let parent_puzzle = sim
+            .puzzle_reveal(parent_coin.coin_id())
+            .expect("missing puzzle")
+            .to_clvm(&mut ctx.allocator)?;
+        let parent_solution = sim
+            .solution(parent_coin.coin_id())
+            .expect("missing solution")
+            .to_clvm(&mut ctx.allocator)?;
+
+        assert_eq!(
+            Mirror::parse_children(
+                &mut ctx.allocator,
+                parent_coin,
+                parent_puzzle,
+                parent_solution
+            )?,
+            vec![mirror.clone()]
+        );
+
+        // The owner of the parent coin can delete the mirror and reclaim its amount.
+        mirror.delete(
+            ctx,
+            &p2,
+            Conditions::new().create_coin(puzzle_hash, 100, Vec::new()),
+        )?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let coin_state = sim
+            .coin_state(mirror.coin.coin_id())
+            .expect("missing mirror");
+        assert!(coin_state.spent_height.is_some());
+
+        Ok(())
+    }
+
+    #[cfg(feature = "peer")]
+    #[tokio::test]
+    async fn test_fetch_mirrors() -> anyhow::Result<()> {
+        use chia_protocol::SpendBundle;
+        use chia_puzzles::standard::StandardArgs;
+        use chia_sdk_test::{sign_transaction, test_secret_key, PeerSimulator};
+
+        let sim = PeerSimulator::new().await?;
+        let peer = sim.connect().await?;
+        let genesis_challenge = sim.config().constants.genesis_challenge;
+        let ctx = &mut SpendContext::new();
+
+        let sk = test_secret_key()?;
+        let p2 = StandardLayer::new(sk.public_key());
+        let puzzle_hash = StandardArgs::curry_tree_hash(sk.public_key()).into();
+        let coin = sim.mint_coin(puzzle_hash, 1).await;
+        let parent_coin = sim.mint_coin(puzzle_hash, 1).await;
+
+        let (launch_singleton, datastore) = Launcher::new(coin.coin_id(), 1).mint_datastore(
+            ctx,
+            DataStoreMetadata::root_hash_only(RootHash::Zero.value()),
+            puzzle_hash.into(),
+            vec![],
+        )?;
+        p2.spend(ctx, coin, launch_singleton)?;
+
+        let launcher_id = datastore.info.launcher_id;
+        let (create_mirror, mirror) = Mirror::create(
+            parent_coin,
+            launcher_id,
+            1,
+            vec!["https://example.com".to_string()],
+        );
+        p2.spend(ctx, parent_coin, create_mirror)?;
+
+        let coin_spends = ctx.take();
+        let signature = sign_transaction(&coin_spends, &[sk.clone()])?;
+        let ack = peer
+            .send_transaction(SpendBundle::new(coin_spends, signature))
+            .await?;
+        assert_eq!(ack.error, None);
+
+        // Only the coins with the mirror puzzle hash are returned.
+        let mirrors =
+            Mirror::fetch(&mut ctx.allocator, &peer, launcher_id, genesis_challenge).await?;
+        assert_eq!(mirrors, vec![mirror.clone()]);
+
+        mirror.delete(ctx, &p2, Conditions::new())?;
+
+        let coin_spends = ctx.take();
+        let signature = sign_transaction(&coin_spends, &[sk])?;
+        let ack = peer
+            .send_transaction(SpendBundle::new(coin_spends, signature))
+            .await?;
+        assert_eq!(ack.error, None);
+
+        let mirrors =
+            Mirror::fetch(&mut ctx.allocator, &peer, launcher_id, genesis_challenge).await?;
+        assert!(mirrors.is_empty());
+
+        Ok(())
+    }
+}
@@ -15,8 +15,10 @@ use crate::{
 
 mod did_info;
 mod did_launcher;
+mod did_recovery;
 
 pub use did_info::*;
+pub use did_recovery::*;
 
 #[must_use]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -65,6 +67,14 @@ where
             .with_p2_puzzle_hash(p2_puzzle_hash)
             .with_metadata(metadata);
 
+        self.child_with_info(info)
+    }
+
+    /// Creates a wrapped spendable DID for the child, with the given info.
+    pub fn child_with_info<N>(&self, info: DidInfo<N>) -> Did<N>
+    where
+        N: ToTreeHash,
+    {
         let inner_puzzle_hash = info.inner_puzzle_hash();
 
         Did {
@@ -1,5 +1,9 @@
+use chia_bls::SecretKey;
 use chia_protocol::Bytes32;
 use chia_puzzles::{did::DidArgs, singleton::SingletonStruct};
+use chia_sdk_signer::{
+    sign_message_for_puzzle_hash, verify_message_for_puzzle_hash, SignedMessage,
+};
 use clvm_traits::{FromClvm, ToClvm};
 use clvm_utils::{ToTreeHash, TreeHash};
 use clvmr::Allocator;
@@ -111,6 +115,24 @@ impl<M> DidInfo<M> {
             self.metadata.tree_hash(),
         )
     }
+
+    /// Signs a CHIP-0002 message as the DID, with the key of its current p2 puzzle hash.
+    pub fn sign_message(
+        &self,
+        secret_keys: &[SecretKey],
+        message: &[u8],
+    ) -> Result<SignedMessage, DriverError> {
+        Ok(sign_message_for_puzzle_hash(
+            secret_keys,
+            self.p2_puzzle_hash,
+            message,
+        )?)
+    }
+
+    /// Verifies that a CHIP-0002 message was signed by the current owner of the DID.
+    pub fn verify_message(&self, signed_message: &SignedMessage, message: &[u8]) -> bool {
+        verify_message_for_puzzle_hash(signed_message, self.p2_puzzle_hash, message)
+    }
 }
 
 #[cfg(test)]
@@ -155,4 +177,35 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_did_sign_message() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, _puzzle_hash, coin) = sim.child_p2(1, 0)?;
+        let (bob_sk, _, bob_puzzle_hash, _) = sim.child_p2(0, 1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+
+        let signed_message = did
+            .info
+            .sign_message(&[bob_sk.clone(), sk.clone()], b"Hello")?;
+        assert_eq!(signed_message.public_key, pk);
+        assert!(did.info.verify_message(&signed_message, b"Hello"));
+
+        // Once the DID has been transferred, only the new owner can sign as it.
+        let did = did.transfer(ctx, &p2, bob_puzzle_hash, Conditions::new())?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        assert!(!did.info.verify_message(&signed_message, b"Hello"));
+        assert!(did.info.sign_message(&[sk], b"Hello").is_err());
+
+        let signed_message = did.info.sign_message(&[bob_sk], b"Hello")?;
+        assert!(did.info.verify_message(&signed_message, b"Hello"));
+
+        Ok(())
+    }
 }
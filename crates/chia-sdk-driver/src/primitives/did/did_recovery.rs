@@ -0,0 +1,416 @@
+use chia_bls::PublicKey;
+use chia_protocol::{Bytes32, Coin};
+use chia_puzzles::{singleton::SingletonSolution, CoinProof};
+use chia_sdk_types::Conditions;
+use clvm_traits::{clvm_quote, FromClvm, ToClvm};
+use clvm_utils::ToTreeHash;
+use clvmr::{Allocator, NodePtr};
+
+use crate::{
+    DidLayer, DriverError, Layer, SingletonLayer, Spend, SpendContext, SpendWithConditions,
+};
+
+use super::Did;
+
+/// Calculates the recovery list hash that is curried into the DID puzzle for the given recovery DIDs.
+pub fn did_recovery_list_hash(recovery_dids: &[Bytes32]) -> Bytes32 {
+    recovery_dids.to_vec().tree_hash().into()
+}
+
+/// A request to recover a specific DID coin to a new p2 puzzle hash.
+///
+/// Each DID in the recovery list attests to the request with [`Did::attest_recovery`],
+/// and the owner of the public key signs the new inner puzzle hash to complete the recovery.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DidRecoveryRequest {
+    /// The coin id of the DID being recovered.
+    pub coin_id: Bytes32,
+    pub new_p2_puzzle_hash: Bytes32,
+    pub new_inner_puzzle_hash: Bytes32,
+    pub public_key: PublicKey,
+}
+
+impl DidRecoveryRequest {
+    /// The puzzle of the message coin that each recovery DID creates to attest to this request.
+    pub fn message_puzzle(&self, ctx: &mut SpendContext) -> Result<NodePtr, DriverError> {
+        let conditions = Conditions::new()
+            .create_coin_announcement(self.coin_id.to_vec().into())
+            .agg_sig_unsafe(self.public_key, self.new_inner_puzzle_hash.to_vec().into());
+        ctx.alloc(&clvm_quote!(conditions))
+    }
+}
+
+/// An attestation from a recovery DID, which is consumed by the recovery spend.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DidAttestation {
+    /// The launcher id of the DID that attested to the recovery.
+    pub launcher_id: Bytes32,
+    /// The DID coin that was spent to create the message coin.
+    pub coin_proof: CoinProof,
+    pub message_coin: Coin,
+    pub request: DidRecoveryRequest,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, ToClvm, FromClvm)]
+#[clvm(list)]
+struct DidRecoveryModeSolution {
+    mode: u8,
+    amount: u64,
+    new_inner_puzzle_hash: Bytes32,
+    recovery_coins: Vec<Option<CoinProof>>,
+    public_key: PublicKey,
+    recovery_list_reveal: Vec<Bytes32>,
+    coin_id: Bytes32,
+}
+
+impl<M> Did<M>
+where
+    M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+{
+    /// Sets the recovery list hash and the number of verifications required to recover the DID.
+    pub fn update_recovery_list<I>(
+        self,
+        ctx: &mut SpendContext,
+        inner: &I,
+        recovery_list_hash: Option<Bytes32>,
+        num_verifications_required: u64,
+        extra_conditions: Conditions,
+    ) -> Result<Did<M>, DriverError>
+    where
+        I: SpendWithConditions,
+    {
+        let mut info = self.info.clone();
+        info.recovery_list_hash = recovery_list_hash;
+        info.num_verifications_required = num_verifications_required;
+
+        self.spend_with(
+            ctx,
+            inner,
+            extra_conditions.create_coin(
+                info.inner_puzzle_hash().into(),
+                self.coin.amount,
+                vec![info.p2_puzzle_hash.into()],
+            ),
+        )?;
+
+        Ok(self.child_with_info(info))
+    }
+
+    /// Creates a request to recover this DID to a new p2 puzzle hash, signed by the given public key.
+    pub fn recovery_request(
+        &self,
+        new_p2_puzzle_hash: Bytes32,
+        public_key: PublicKey,
+    ) -> DidRecoveryRequest {
+        DidRecoveryRequest {
+            coin_id: self.coin.coin_id(),
+            new_p2_puzzle_hash,
+            new_inner_puzzle_hash: self
+                .info
+                .clone()
+                .with_p2_puzzle_hash(new_p2_puzzle_hash)
+                .inner_puzzle_hash()
+                .into(),
+            public_key,
+        }
+    }
+
+    /// Attests to the recovery of another DID by creating a message coin, and recreates this DID.
+    pub fn attest_recovery<I>(
+        self,
+        ctx: &mut SpendContext,
+        inner: &I,
+        request: DidRecoveryRequest,
+        extra_conditions: Conditions,
+    ) -> Result<(Did<M>, DidAttestation), DriverError>
+    where
+        I: SpendWithConditions,
+    {
+        let message_puzzle = request.message_puzzle(ctx)?;
+        let message_puzzle_hash = ctx.tree_hash(message_puzzle).into();
+
+        let attestation = DidAttestation {
+            launcher_id: self.info.launcher_id,
+            coin_proof: CoinProof {
+                parent_coin_info: self.coin.parent_coin_info,
+                inner_puzzle_hash: self.info.inner_puzzle_hash().into(),
+                amount: self.coin.amount,
+            },
+            message_coin: Coin::new(self.coin.coin_id(), message_puzzle_hash, 0),
+            request,
+        };
+
+        let did = self.update(
+            ctx,
+            inner,
+            extra_conditions.create_coin(message_puzzle_hash, 0, Vec::new()),
+        )?;
+
+        Ok((did, attestation))
+    }
+
+    /// Recovers this DID to the new p2 puzzle hash of the request, by consuming the attestations.
+    ///
+    /// The current p2 puzzle must be revealed, but its owner doesn't need to sign the spend.
+    /// Instead, the public key of the request must sign the new inner puzzle hash.
+    ///
+    /// The attestations are matched to the recovery DIDs by launcher id, so they can be in any order.
+    /// At least the number of verifications required by the DID must be provided. Although the solution
+    /// can leave out a recovery DID, the branch of the deployed DID puzzle that skips it drops an argument
+    /// and always raises, so every DID in the recovery list must attest for the spend to be valid.
+    pub fn recover(
+        self,
+        ctx: &mut SpendContext,
+        p2_puzzle: NodePtr,
+        request: DidRecoveryRequest,
+        recovery_dids: Vec<Bytes32>,
+        attestations: &[DidAttestation],
+    ) -> Result<Did<M>, DriverError> {
+        if request.coin_id != self.coin.coin_id() {
+            return Err(DriverError::InvalidRecoveryRequest);
+        }
+
+        if self.info.recovery_list_hash != Some(did_recovery_list_hash(&recovery_dids)) {
+            return Err(DriverError::InvalidRecoveryRequest);
+        }
+
+        let matched: Vec<Option<&DidAttestation>> = recovery_dids
+            .iter()
+            .map(|launcher_id| {
+                attestations.iter().find(|attestation| {
+                    attestation.launcher_id == *launcher_id && attestation.request == request
+                })
+            })
+            .collect();
+
+        let provided = matched.iter().flatten().count();
+        let required = self.info.num_verifications_required;
+
+        if (provided as u64) < required {
+            return Err(DriverError::InsufficientAttestations(provided, required));
+        }
+
+        if let Some(index) = matched.iter().position(Option::is_none) {
+            return Err(DriverError::MissingAttestation(recovery_dids[index]));
+        }
+
+        let message_puzzle = request.message_puzzle(ctx)?;
+
+        for attestation in matched.iter().flatten() {
+            ctx.spend(
+                attestation.message_coin,
+                Spend::new(message_puzzle, NodePtr::NIL),
+            )?;
+        }
+
+        let recovery_coins = matched
+            .into_iter()
+            .map(|attestation| attestation.map(|attestation| attestation.coin_proof))
+            .collect();
+
+        self.spend_recovery(ctx, p2_puzzle, request, recovery_dids, recovery_coins)
+    }
+
+    fn spend_recovery(
+        self,
+        ctx: &mut SpendContext,
+        p2_puzzle: NodePtr,
+        request: DidRecoveryRequest,
+        recovery_dids: Vec<Bytes32>,
+        recovery_coins: Vec<Option<CoinProof>>,
+    ) -> Result<Did<M>, DriverError> {
+        let did_puzzle = DidLayer::new(
+            self.info.launcher_id,
+            self.info.recovery_list_hash,
+            self.info.num_verifications_required,
+            self.info.metadata.clone(),
+            p2_puzzle,
+        )
+        .construct_puzzle(ctx)?;
+        let layer = SingletonLayer::new(self.info.launcher_id, did_puzzle);
+
+        let puzzle = layer.construct_puzzle(ctx)?;
+        let inner_solution = ctx.alloc(&DidRecoveryModeSolution {
+            mode: 0,
+            amount: self.coin.amount,
+            new_inner_puzzle_hash: request.new_inner_puzzle_hash,
+            recovery_coins,
+            public_key: request.public_key,
+            recovery_list_reveal: recovery_dids,
+            coin_id: self.coin.coin_id(),
+        })?;
+        let solution = layer.construct_solution(
+            ctx,
+            SingletonSolution {
+                lineage_proof: self.proof,
+                amount: self.coin.amount,
+                inner_solution,
+            },
+        )?;
+
+        ctx.spend(self.coin, Spend::new(puzzle, solution))?;
+
+        let metadata = self.info.metadata.clone();
+
+        Ok(self.wrapped_child(request.new_p2_puzzle_hash, metadata))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_sdk_test::{Simulator, SimulatorError};
+
+    use crate::{Launcher, StandardLayer};
+
+    use super::*;
+
+    #[allow(clippy::similar_names)]
+    #[test]
+    fn test_recover_did() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (alice_sk, alice_pk, alice_puzzle_hash, alice_coin) = sim.child_p2(1, 0)?;
+        let (bob_sk, bob_pk, _, bob_coin) = sim.child_p2(1, 1)?;
+        let (charlie_sk, charlie_pk, _, charlie_coin) = sim.child_p2(1, 2)?;
+        let (dave_sk, dave_pk, _, dave_coin) = sim.child_p2(1, 3)?;
+        let (recovery_sk, recovery_pk, recovery_puzzle_hash, _) = sim.child_p2(0, 4)?;
+
+        let alice = StandardLayer::new(alice_pk);
+        let bob = StandardLayer::new(bob_pk);
+        let charlie = StandardLayer::new(charlie_pk);
+        let dave = StandardLayer::new(dave_pk);
+
+        let (create_did, bob_did) =
+            Launcher::new(bob_coin.coin_id(), 1).create_simple_did(ctx, &bob)?;
+        bob.spend(ctx, bob_coin, create_did)?;
+
+        let (create_did, charlie_did) =
+            Launcher::new(charlie_coin.coin_id(), 1).create_simple_did(ctx, &charlie)?;
+        charlie.spend(ctx, charlie_coin, create_did)?;
+
+        let (create_did, dave_did) =
+            Launcher::new(dave_coin.coin_id(), 1).create_simple_did(ctx, &dave)?;
+        dave.spend(ctx, dave_coin, create_did)?;
+
+        let (create_did, alice_did) =
+            Launcher::new(alice_coin.coin_id(), 1).create_simple_did(ctx, &alice)?;
+        alice.spend(ctx, alice_coin, create_did)?;
+
+        // Alice sets Bob, Charlie and Dave as her recovery DIDs, and any two of them can recover it.
+        let recovery_dids = vec![
+            bob_did.info.launcher_id,
+            charlie_did.info.launcher_id,
+            dave_did.info.launcher_id,
+        ];
+        let alice_did = alice_did.update_recovery_list(
+            ctx,
+            &alice,
+            Some(did_recovery_list_hash(&recovery_dids)),
+            2,
+            Conditions::new(),
+        )?;
+        sim.spend_coins(
+            ctx.take(),
+            &[
+                alice_sk.clone(),
+                bob_sk.clone(),
+                charlie_sk.clone(),
+                dave_sk.clone(),
+            ],
+        )?;
+
+        assert_eq!(alice_did.info.num_verifications_required, 2);
+        assert_eq!(alice_did.info.p2_puzzle_hash, alice_puzzle_hash);
+
+        // After losing her key, Alice asks Bob, Charlie and Dave to attest to the recovery.
+        let request = alice_did.recovery_request(recovery_puzzle_hash, recovery_pk);
+
+        let (_bob_did, bob_attestation) =
+            bob_did.attest_recovery(ctx, &bob, request, Conditions::new())?;
+        let (_charlie_did, charlie_attestation) =
+            charlie_did.attest_recovery(ctx, &charlie, request, Conditions::new())?;
+        let (_dave_did, dave_attestation) =
+            dave_did.attest_recovery(ctx, &dave, request, Conditions::new())?;
+        sim.spend_coins(ctx.take(), &[bob_sk, charlie_sk, dave_sk])?;
+
+        let alice_p2_puzzle = alice.construct_puzzle(ctx)?;
+
+        // The recovery needs as many attestations as the DID requires.
+        assert!(matches!(
+            alice_did.recover(
+                ctx,
+                alice_p2_puzzle,
+                request,
+                recovery_dids.clone(),
+                &[bob_attestation],
+            ),
+            Err(DriverError::InsufficientAttestations(1, 2))
+        ));
+        assert_eq!(ctx.iter().count(), 0);
+
+        // Two attestations would be enough, but the DID puzzle raises when a recovery DID is skipped.
+        assert!(matches!(
+            alice_did.recover(
+                ctx,
+                alice_p2_puzzle,
+                request,
+                recovery_dids.clone(),
+                &[bob_attestation, dave_attestation],
+            ),
+            Err(DriverError::MissingAttestation(launcher_id)) if launcher_id == charlie_did.info.launcher_id
+        ));
+        assert_eq!(ctx.iter().count(), 0);
+
+        let message_puzzle = request.message_puzzle(ctx)?;
+        for attestation in [bob_attestation, dave_attestation] {
+            ctx.spend(
+                attestation.message_coin,
+                Spend::new(message_puzzle, NodePtr::NIL),
+            )?;
+        }
+        let _alice_did = alice_did.spend_recovery(
+            ctx,
+            alice_p2_puzzle,
+            request,
+            recovery_dids.clone(),
+            vec![
+                Some(bob_attestation.coin_proof),
+                None,
+                Some(dave_attestation.coin_proof),
+            ],
+        )?;
+        assert!(matches!(
+            sim.spend_coins(ctx.take(), &[recovery_sk.clone()]),
+            Err(SimulatorError::Signer(_))
+        ));
+
+        // And must be signed by the new public key.
+        let attestations = [dave_attestation, bob_attestation, charlie_attestation];
+
+        let _alice_did = alice_did.recover(
+            ctx,
+            alice_p2_puzzle,
+            request,
+            recovery_dids.clone(),
+            &attestations,
+        )?;
+        assert!(matches!(
+            sim.spend_coins(ctx.take(), &[alice_sk]).unwrap_err(),
+            SimulatorError::MissingKey
+        ));
+
+        let alice_did =
+            alice_did.recover(ctx, alice_p2_puzzle, request, recovery_dids, &attestations)?;
+        sim.spend_coins(ctx.take(), &[recovery_sk.clone()])?;
+
+        assert_eq!(alice_did.info.p2_puzzle_hash, recovery_puzzle_hash);
+
+        // The recovered DID can be spent with the new key.
+        let recovery = StandardLayer::new(recovery_pk);
+        let _alice_did = alice_did.update(ctx, &recovery, Conditions::new())?;
+        sim.spend_coins(ctx.take(), &[recovery_sk])?;
+
+        Ok(())
+    }
+}
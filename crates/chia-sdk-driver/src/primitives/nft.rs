@@ -14,20 +14,28 @@ use clvm_utils::{tree_hash, ToTreeHash};
 use clvmr::{sha2::Sha256, Allocator, NodePtr};
 
 use crate::{
-    DriverError, Layer, NftOwnershipLayer, NftStateLayer, Puzzle, RoyaltyTransferLayer,
+    DriverError, Layer, NftOwnershipLayer, NftStateLayer, NftTransferLayer, Puzzle,
     SettlementLayer, SingletonLayer, Spend, SpendContext, SpendWithConditions,
 };
 
+#[cfg(feature = "chip-0007")]
+mod chip0007_metadata;
 mod did_owner;
 mod metadata_update;
+mod nft_bulk_mint;
 mod nft_info;
 mod nft_launcher;
 mod nft_mint;
+mod nft_transfer_program;
 
+#[cfg(feature = "chip-0007")]
+pub use chip0007_metadata::*;
 pub use did_owner::*;
 pub use metadata_update::*;
+pub use nft_bulk_mint::*;
 pub use nft_info::*;
 pub use nft_mint::*;
+pub use nft_transfer_program::*;
 
 /// Everything that is required to spend an NFT coin.
 #[must_use]
@@ -180,11 +188,48 @@ where
         )?;
         let output = ctx.extract::<NewMetadataOutput<N, NodePtr>>(ptr)?;
 
-        Ok(self.wrapped_child(
+        let mut child = self.wrapped_child(
             p2_puzzle_hash,
             self.info.current_owner,
             output.metadata_info.new_metadata,
-        ))
+        );
+
+        // The metadata updater can replace itself with a different one.
+        child.info.metadata_updater_puzzle_hash = output.metadata_info.new_updater_puzzle_hash;
+        child.coin.puzzle_hash =
+            SingletonArgs::curry_tree_hash(child.info.launcher_id, child.info.inner_puzzle_hash())
+                .into();
+
+        Ok(child)
+    }
+
+    /// Updates the metadata of this NFT with its metadata updater, without changing the owner.
+    pub fn update_metadata<I, U>(
+        self,
+        ctx: &mut SpendContext,
+        inner: &I,
+        update: &U,
+        extra_conditions: Conditions,
+    ) -> Result<Nft<M>, DriverError>
+    where
+        I: SpendWithConditions,
+        U: MetadataUpdater,
+        M: ToTreeHash,
+    {
+        if update.updater_puzzle_hash() != self.info.metadata_updater_puzzle_hash.into() {
+            return Err(DriverError::MetadataUpdaterMismatch);
+        }
+
+        let metadata_update = update.spend(ctx)?;
+        let p2_puzzle_hash = self.info.p2_puzzle_hash;
+
+        self.transfer_with_metadata(
+            ctx,
+            inner,
+            p2_puzzle_hash,
+            metadata_update,
+            extra_conditions,
+        )
     }
 
     /// Transfers this NFT to a new p2 puzzle hash.
@@ -231,6 +276,10 @@ where
         M: ToTreeHash,
         I: SpendWithConditions,
     {
+        if !self.info.transfer_program.is_tradable() {
+            return Err(DriverError::UntradableNft);
+        }
+
         let transfer_condition = TransferNft::new(None, trade_prices, None);
 
         let (conditions, nft) = self.transfer_with_condition(
@@ -378,7 +427,7 @@ where
         };
 
         let Some(inner_layers) =
-            NftStateLayer::<M, NftOwnershipLayer<RoyaltyTransferLayer, Puzzle>>::parse_puzzle(
+            NftStateLayer::<M, NftOwnershipLayer<NftTransferLayer, Puzzle>>::parse_puzzle(
                 allocator,
                 singleton_layer.inner_puzzle,
             )?
@@ -387,7 +436,7 @@ where
         };
 
         let parent_solution = SingletonLayer::<
-            NftStateLayer<M, NftOwnershipLayer<RoyaltyTransferLayer, Puzzle>>,
+            NftStateLayer<M, NftOwnershipLayer<NftTransferLayer, Puzzle>>,
         >::parse_solution(allocator, parent_solution)?;
 
         let inner_puzzle = inner_layers.inner_puzzle.inner_puzzle;
@@ -426,21 +475,12 @@ where
         }
 
         if let Some(new_metadata) = new_metadata {
-            let metadata_updater_solution = clvm_list!(
-                layers.inner_puzzle.metadata.clone(),
-                layers.inner_puzzle.metadata_updater_puzzle_hash,
-                new_metadata.updater_solution
-            )
-            .to_clvm(allocator)?;
-
-            let output = run_puzzle(
+            let output = NftStateLayer::<M, NodePtr>::get_next_metadata(
                 allocator,
-                new_metadata.updater_puzzle_reveal,
-                metadata_updater_solution,
+                &layers.inner_puzzle.metadata,
+                layers.inner_puzzle.metadata_updater_puzzle_hash,
+                new_metadata,
             )?;
-
-            let output =
-                NewMetadataOutput::<M, NodePtr>::from_clvm(allocator, output)?.metadata_info;
             layers.inner_puzzle.metadata = output.new_metadata;
             layers.inner_puzzle.metadata_updater_puzzle_hash = output.new_updater_puzzle_hash;
         }
@@ -657,6 +697,88 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_custom_metadata_updater() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let mint = NftMint::new(
+            NftMetadata {
+                data_uris: vec!["example.com".to_string()],
+                data_hash: Some(Bytes32::default()),
+                ..Default::default()
+            },
+            puzzle_hash,
+            300,
+            None,
+        )
+        .with_custom_metadata_updater(KEY_VALUE_METADATA_UPDATER_PUZZLE_HASH.into());
+
+        let (mint_nft, nft) = Launcher::new(coin.coin_id(), 1).mint_nft(ctx, mint)?;
+        p2.spend(ctx, coin, mint_nft)?;
+
+        // The default metadata updater can't be used.
+        let update = MetadataUpdate::NewDataUri("another.com".to_string());
+        assert!(matches!(
+            nft.clone()
+                .update_metadata(ctx, &p2, &update, Conditions::new()),
+            Err(DriverError::MetadataUpdaterMismatch)
+        ));
+
+        let update = KeyValueMetadataUpdate::new(vec![
+            ("u".to_string(), ctx.alloc(&["another.com"])?),
+            ("h".to_string(), ctx.alloc(&Bytes32::new([1; 32]))?),
+        ]);
+
+        let parent_nft = nft.clone();
+        let nft = nft.update_metadata(ctx, &p2, &update, Conditions::new())?;
+
+        assert_eq!(
+            nft.info.metadata,
+            NftMetadata {
+                data_uris: vec!["another.com".to_string()],
+                data_hash: Some(Bytes32::new([1; 32])),
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            nft.info.metadata_updater_puzzle_hash,
+            KEY_VALUE_METADATA_UPDATER_PUZZLE_HASH.into()
+        );
+
+        let child_nft = nft.clone();
+        let _nft = nft.transfer(ctx, &p2, puzzle_hash, Conditions::new())?;
+
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let parent_puzzle = sim
+            .puzzle_reveal(parent_nft.coin.coin_id())
+            .expect("missing puzzle");
+
+        let parent_solution = sim
+            .solution(parent_nft.coin.coin_id())
+            .expect("missing solution");
+
+        let parent_puzzle = parent_puzzle.to_clvm(&mut ctx.allocator)?;
+        let parent_puzzle = Puzzle::parse(&ctx.allocator, parent_puzzle);
+        let parent_solution = parent_solution.to_clvm(&mut ctx.allocator)?;
+
+        let new_child_nft = Nft::<NftMetadata>::parse_child(
+            &mut ctx.allocator,
+            parent_nft.coin,
+            parent_puzzle,
+            parent_solution,
+        )?
+        .expect("child is not an NFT");
+
+        assert_eq!(new_child_nft, child_nft);
+
+        Ok(())
+    }
+
     #[test]
     fn test_parse_nft() -> anyhow::Result<()> {
         let mut sim = Simulator::new();
@@ -0,0 +1,300 @@
+use std::io;
+
+use chia_protocol::Bytes32;
+use chia_puzzles::nft::NftMetadata;
+use clvmr::sha2::Sha256;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+pub enum Chip0007Error {
+    #[error("io error: {0}")]
+    Io(#[from] io::Error),
+
+    #[error("json error: {0}")]
+    Json(#[from] serde_json::Error),
+
+    #[error("expected CHIP-0007 format, but found {0}")]
+    InvalidFormat(String),
+
+    #[error("expected hash {expected}, but found {found}")]
+    HashMismatch { expected: Bytes32, found: Bytes32 },
+
+    #[error("there are no uris to resolve")]
+    MissingUri,
+}
+
+/// The off-chain metadata of an NFT, as defined by [CHIP-0007](https://github.com/Chia-Network/chips/blob/main/CHIPs/chip-0007.md).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Chip0007Metadata {
+    pub format: String,
+    pub name: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub minting_tool: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sensitive_content: Option<SensitiveContent>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_number: Option<u64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub series_total: Option<u64>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<NftAttribute>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub collection: Option<NftCollection>,
+    /// Any additional data that isn't covered by the standard.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+}
+
+impl Chip0007Metadata {
+    pub const FORMAT: &'static str = "CHIP-0007";
+
+    pub fn new(name: String, description: String) -> Self {
+        Self {
+            format: Self::FORMAT.to_string(),
+            name,
+            description,
+            minting_tool: None,
+            sensitive_content: None,
+            series_number: None,
+            series_total: None,
+            attributes: Vec::new(),
+            collection: None,
+            data: None,
+        }
+    }
+
+    /// Parses the JSON metadata, and checks that it's in the CHIP-0007 format.
+    pub fn from_json(bytes: &[u8]) -> Result<Self, Chip0007Error> {
+        let metadata: Self = serde_json::from_slice(bytes)?;
+
+        if metadata.format != Self::FORMAT {
+            return Err(Chip0007Error::InvalidFormat(metadata.format));
+        }
+
+        Ok(metadata)
+    }
+
+    pub fn to_json(&self) -> Result<Vec<u8>, Chip0007Error> {
+        Ok(serde_json::to_vec_pretty(self)?)
+    }
+}
+
+/// Whether the NFT contains sensitive content, or a list of the types of sensitive content it contains.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SensitiveContent {
+    Flag(bool),
+    Types(Vec<String>),
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct NftAttribute {
+    pub trait_type: AttributeValue,
+    pub value: AttributeValue,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_value: Option<serde_json::Number>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_value: Option<serde_json::Number>,
+}
+
+/// An attribute value, which can either be a string or a number.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum AttributeValue {
+    String(String),
+    Number(serde_json::Number),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NftCollection {
+    /// A UUID that is unique to the collection.
+    pub id: String,
+    pub name: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub attributes: Vec<CollectionAttribute>,
+}
+
+/// An attribute of the collection, such as its description, icon, banner, or website.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CollectionAttribute {
+    #[serde(rename = "type")]
+    pub kind: String,
+    pub value: AttributeValue,
+}
+
+/// Fetches the contents of the URIs in the on-chain metadata, such as over HTTP or from the file system.
+pub trait UriResolver {
+    fn resolve(&self, uri: &str) -> io::Result<Vec<u8>>;
+}
+
+impl<F> UriResolver for F
+where
+    F: Fn(&str) -> io::Result<Vec<u8>>,
+{
+    fn resolve(&self, uri: &str) -> io::Result<Vec<u8>> {
+        self(uri)
+    }
+}
+
+/// Checks that the SHA-256 hash of the content matches the expected hash, if there is one.
+pub fn verify_hash(content: &[u8], expected: Option<Bytes32>) -> Result<(), Chip0007Error> {
+    let Some(expected) = expected else {
+        return Ok(());
+    };
+
+    let mut hasher = Sha256::new();
+    hasher.update(content);
+    let found = Bytes32::new(hasher.finalize());
+
+    if found != expected {
+        return Err(Chip0007Error::HashMismatch { expected, found });
+    }
+
+    Ok(())
+}
+
+/// Resolves each URI in order until one of them has content that matches the expected hash.
+///
+/// If none of them do, the error from the last URI is returned.
+pub fn resolve_verified<R>(
+    resolver: &R,
+    uris: &[String],
+    expected: Option<Bytes32>,
+) -> Result<Vec<u8>, Chip0007Error>
+where
+    R: UriResolver + ?Sized,
+{
+    let mut error = Chip0007Error::MissingUri;
+
+    for uri in uris {
+        match resolver.resolve(uri) {
+            Ok(content) => match verify_hash(&content, expected) {
+                Ok(()) => return Ok(content),
+                Err(mismatch) => error = mismatch,
+            },
+            Err(io_error) => error = io_error.into(),
+        }
+    }
+
+    Err(error)
+}
+
+/// Resolves the CHIP-0007 metadata of an NFT, and checks it against the metadata hash.
+pub fn resolve_chip0007_metadata<R>(
+    resolver: &R,
+    metadata: &NftMetadata,
+) -> Result<Chip0007Metadata, Chip0007Error>
+where
+    R: UriResolver + ?Sized,
+{
+    let content = resolve_verified(resolver, &metadata.metadata_uris, metadata.metadata_hash)?;
+    Chip0007Metadata::from_json(&content)
+}
+
+/// Resolves the data of an NFT, such as an image, and checks it against the data hash.
+pub fn resolve_nft_data<R>(resolver: &R, metadata: &NftMetadata) -> Result<Vec<u8>, Chip0007Error>
+where
+    R: UriResolver + ?Sized,
+{
+    resolve_verified(resolver, &metadata.data_uris, metadata.data_hash)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{fs, path::PathBuf};
+
+    use super::*;
+
+    fn test_data(uri: &str) -> io::Result<Vec<u8>> {
+        let path = uri
+            .strip_prefix("file://")
+            .ok_or_else(|| io::Error::new(io::ErrorKind::Unsupported, uri.to_string()))?;
+
+        fs::read(
+            PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+                .join("src/primitives/nft/test_data")
+                .join(path),
+        )
+    }
+
+    fn hash(content: &[u8]) -> Bytes32 {
+        let mut hasher = Sha256::new();
+        hasher.update(content);
+        Bytes32::new(hasher.finalize())
+    }
+
+    #[test]
+    fn test_resolve_chip0007_metadata() -> anyhow::Result<()> {
+        let content = test_data("file://chip0007.json")?;
+
+        let mut metadata = NftMetadata {
+            metadata_uris: vec![
+                "https://example.com/metadata.json".to_string(),
+                "file://missing.json".to_string(),
+                "file://chip0007.json".to_string(),
+            ],
+            metadata_hash: Some(hash(&content)),
+            ..Default::default()
+        };
+
+        let chip0007 = resolve_chip0007_metadata(&test_data, &metadata)?;
+
+        assert_eq!(chip0007.name, "Pikachu");
+        assert_eq!(
+            chip0007.sensitive_content,
+            Some(SensitiveContent::Flag(false))
+        );
+        assert_eq!(
+            (chip0007.series_number, chip0007.series_total),
+            (Some(22), Some(1000))
+        );
+        assert_eq!(chip0007.attributes.len(), 3);
+        assert_eq!(
+            chip0007.attributes[2].value,
+            AttributeValue::Number(50.into())
+        );
+
+        let collection = chip0007.collection.clone().expect("missing collection");
+        assert_eq!(collection.attributes[0].kind, "description");
+
+        let reparsed = Chip0007Metadata::from_json(&chip0007.to_json()?)?;
+        assert_eq!(reparsed, chip0007);
+
+        // The content must match the hash.
+        metadata.metadata_hash = Some(Bytes32::default());
+
+        assert!(matches!(
+            resolve_chip0007_metadata(&test_data, &metadata),
+            Err(Chip0007Error::HashMismatch { .. })
+        ));
+
+        metadata.metadata_uris.clear();
+
+        assert!(matches!(
+            resolve_chip0007_metadata(&test_data, &metadata),
+            Err(Chip0007Error::MissingUri)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_resolve_nft_data() {
+        let metadata = NftMetadata {
+            data_uris: vec!["file://chip0007.json".to_string()],
+            ..Default::default()
+        };
+
+        // Without a hash, the content isn't verified.
+        assert!(resolve_nft_data(&test_data, &metadata).is_ok());
+
+        let json = br#"{"format": "CHIP-0008", "name": "", "description": ""}"#;
+
+        assert!(matches!(
+            Chip0007Metadata::from_json(json),
+            Err(Chip0007Error::InvalidFormat(..))
+        ));
+    }
+}
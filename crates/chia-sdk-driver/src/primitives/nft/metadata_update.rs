@@ -1,5 +1,23 @@
+use chia_puzzles::nft::NFT_METADATA_UPDATER_PUZZLE_HASH;
+use clvm_traits::ToClvm;
+use clvm_utils::TreeHash;
+use clvmr::{Allocator, NodePtr};
+use hex_literal::hex;
+
 use crate::{DriverError, Spend, SpendContext};
 
+/// A spend of a metadata updater puzzle, which is output by the NFT's inner puzzle to change its metadata.
+///
+/// The NFT state layer only allows the metadata updater that it was minted with, which is the one
+/// set with [`NftMint::with_custom_metadata_updater`](crate::NftMint::with_custom_metadata_updater).
+pub trait MetadataUpdater {
+    /// The tree hash of the metadata updater puzzle.
+    fn updater_puzzle_hash(&self) -> TreeHash;
+
+    fn spend(&self, ctx: &mut SpendContext) -> Result<Spend, DriverError>;
+}
+
+/// An update for the default metadata updater, which adds a URI to the front of one of the lists in the [`NftMetadata`](chia_puzzles::nft::NftMetadata).
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MetadataUpdate {
     NewDataUri(String),
@@ -7,8 +25,12 @@ pub enum MetadataUpdate {
     NewLicenseUri(String),
 }
 
-impl MetadataUpdate {
-    pub fn spend(&self, ctx: &mut SpendContext) -> Result<Spend, DriverError> {
+impl MetadataUpdater for MetadataUpdate {
+    fn updater_puzzle_hash(&self) -> TreeHash {
+        NFT_METADATA_UPDATER_PUZZLE_HASH
+    }
+
+    fn spend(&self, ctx: &mut SpendContext) -> Result<Spend, DriverError> {
         let solution = ctx.alloc(&match self {
             Self::NewDataUri(uri) => ("u", uri),
             Self::NewMetadataUri(uri) => ("mu", uri),
@@ -17,3 +39,82 @@ impl MetadataUpdate {
         Ok(Spend::new(ctx.nft_metadata_updater()?, solution))
     }
 }
+
+/// An update for the [`KEY_VALUE_METADATA_UPDATER_PUZZLE`], which replaces the value of each field in key-value
+/// metadata such as [`NftMetadata`](chia_puzzles::nft::NftMetadata). Fields that don't exist yet are added to the end.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KeyValueMetadataUpdate<V = NodePtr> {
+    pub fields: Vec<(String, V)>,
+}
+
+impl<V> KeyValueMetadataUpdate<V> {
+    pub fn new(fields: Vec<(String, V)>) -> Self {
+        Self { fields }
+    }
+}
+
+impl<V> MetadataUpdater for KeyValueMetadataUpdate<V>
+where
+    V: ToClvm<Allocator>,
+{
+    fn updater_puzzle_hash(&self) -> TreeHash {
+        KEY_VALUE_METADATA_UPDATER_PUZZLE_HASH
+    }
+
+    fn spend(&self, ctx: &mut SpendContext) -> Result<Spend, DriverError> {
+        let solution = ctx.alloc(&self.fields)?;
+        Ok(Spend::new(ctx.key_value_metadata_updater()?, solution))
+    }
+}
+
+/// ```text
+/// (mod (CURRENT_METADATA METADATA_UPDATER_PUZZLE_HASH new_fields)
+///   (defun set_field (metadata key value)
+///     (if metadata
+///         (if (= (f (f metadata)) key)
+///             (c (c key value) (r metadata))
+///             (c (f metadata) (set_field (r metadata) key value))
+///         )
+///         (list (c key value))
+///     )
+///   )
+///
+///   (defun set_fields (metadata fields)
+///     (if fields
+///         (set_fields (set_field metadata (f (f fields)) (r (f fields))) (r fields))
+///         metadata
+///     )
+///   )
+///
+///   (list (list (set_fields CURRENT_METADATA new_fields) METADATA_UPDATER_PUZZLE_HASH) ())
+/// )
+/// ```
+pub const KEY_VALUE_METADATA_UPDATER_PUZZLE: [u8; 239] = hex!(
+    "
+    ff02ffff01ff04ffff04ffff02ff06ffff04ff02ffff04ff05ffff04ff17ff80
+    80808080ffff04ff0bff808080ffff01ff808080ffff04ffff01ffff02ffff03
+    ff05ffff01ff02ffff03ffff09ff11ff0b80ffff01ff04ffff04ff0bff1780ff
+    0d80ffff01ff04ff09ffff02ff04ffff04ff02ffff04ff0dffff04ff0bffff04
+    ff17ff8080808080808080ff0180ffff01ff04ffff04ff0bff1780ff808080ff
+    0180ff02ffff03ff0bffff01ff02ff06ffff04ff02ffff04ffff02ff04ffff04
+    ff02ffff04ff05ffff04ff23ffff04ff33ff808080808080ffff04ff1bff8080
+    808080ffff010580ff0180ff018080
+    "
+);
+
+pub const KEY_VALUE_METADATA_UPDATER_PUZZLE_HASH: TreeHash = TreeHash::new(hex!(
+    "e5d2959059d67ea921ddbce9de64e92025b6ba3f8a9a323edd2ee854c8373a96"
+));
+
+#[cfg(test)]
+mod tests {
+    use crate::assert_puzzle_hash;
+
+    use super::*;
+
+    #[test]
+    fn test_puzzle_hashes() -> anyhow::Result<()> {
+        assert_puzzle_hash!(KEY_VALUE_METADATA_UPDATER_PUZZLE => KEY_VALUE_METADATA_UPDATER_PUZZLE_HASH);
+        Ok(())
+    }
+}
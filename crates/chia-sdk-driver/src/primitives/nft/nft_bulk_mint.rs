@@ -0,0 +1,455 @@
+use chia_bls::PublicKey;
+use chia_protocol::{Coin, CoinSpend};
+use chia_puzzles::{nft::NftMetadata, standard::StandardArgs};
+use chia_sdk_types::Conditions;
+use chia_sdk_utils::CoinSelectionError;
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::ToTreeHash;
+use clvmr::Allocator;
+
+use crate::{
+    Did, DidOwner, DriverError, IntermediateLauncher, Nft, NftMint, PlannedBundle, SpendContext,
+    StandardLayer,
+};
+
+/// The estimated cost of each spend in a bulk mint, which is used to decide how many NFTs fit in a spend bundle.
+///
+/// The defaults leave some headroom over minting NFTs with [`NftMetadata`] from a DID created with
+/// [`Launcher::create_simple_did`](crate::Launcher::create_simple_did). Larger metadata or a more complex DID needs higher costs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NftMintCosts {
+    /// The cost of spending the DID, excluding the conditions for each NFT.
+    pub did_spend: u64,
+    /// The cost of spending the XCH coin that funds the spend bundle.
+    pub xch_spend: u64,
+    /// The cost of minting each NFT, including the intermediate launcher and the DID announcements.
+    pub nft_mint: u64,
+}
+
+impl Default for NftMintCosts {
+    fn default() -> Self {
+        Self {
+            did_spend: 40_000_000,
+            xch_spend: 10_000_000,
+            nft_mint: 75_000_000,
+        }
+    }
+}
+
+/// The result of an [`NftBulkMinter`].
+///
+/// The DID is spent in every spend bundle, so they must be submitted in order and each must be confirmed before the next.
+#[derive(Debug, Clone)]
+pub struct NftBulkMint<M, D> {
+    pub bundles: Vec<PlannedBundle>,
+    /// The minted NFTs, in the same order as the mints.
+    pub nfts: Vec<Nft<M>>,
+    /// The DID after the last spend bundle.
+    pub did: Did<D>,
+    /// The XCH change from funding the NFTs and paying the fee.
+    pub change: Option<Coin>,
+}
+
+impl<M, D> NftBulkMint<M, D> {
+    pub fn total_fee(&self) -> u64 {
+        self.bundles.iter().map(|bundle| bundle.fee).sum()
+    }
+}
+
+/// Mints a large number of NFTs from a DID, split into spend bundles that don't exceed the cost limit.
+///
+/// Every NFT is attributed to the DID, and is minted with an [`IntermediateLauncher`] so that it has a unique mint number.
+/// Each NFT requires a mojo, which is paid along with the fee by an XCH coin. The DID and XCH coin must be owned by the synthetic key.
+///
+/// Any coin spends that are already in the [`SpendContext`] are included in the first spend bundle.
+/// If minting fails, the [`SpendContext`] is restored to the coin spends it had beforehand.
+#[derive(Debug, Clone, Copy)]
+#[must_use]
+pub struct NftBulkMinter {
+    synthetic_key: PublicKey,
+    max_cost: u64,
+    fee: u64,
+    costs: NftMintCosts,
+    mint_number_start: usize,
+    mint_total: Option<usize>,
+}
+
+impl NftBulkMinter {
+    /// Creates a minter that fits as many NFTs as the mempool allows into each spend bundle,
+    /// which is a cost of 5.5 billion.
+    pub fn new(synthetic_key: PublicKey) -> Self {
+        Self {
+            synthetic_key,
+            max_cost: 5_500_000_000,
+            fee: 0,
+            costs: NftMintCosts::default(),
+            mint_number_start: 1,
+            mint_total: None,
+        }
+    }
+
+    pub fn max_cost(mut self, max_cost: u64) -> Self {
+        self.max_cost = max_cost;
+        self
+    }
+
+    /// Sets the fee of each spend bundle, which is paid by the XCH coin along with a mojo for every NFT.
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    pub fn costs(mut self, costs: NftMintCosts) -> Self {
+        self.costs = costs;
+        self
+    }
+
+    /// Sets the mint number of the first NFT, and the total number of NFTs in the collection.
+    ///
+    /// This is needed when a collection is minted in multiple calls. By default, the mint numbers start at 1
+    /// and the total is the number of NFTs being minted.
+    pub fn mint_numbers(mut self, start: usize, total: usize) -> Self {
+        self.mint_number_start = start;
+        self.mint_total = Some(total);
+        self
+    }
+
+    /// Sets the edition number and total in the metadata of each NFT to match its mint number.
+    pub fn with_editions(
+        &self,
+        mints: Vec<NftMint<NftMetadata>>,
+    ) -> Result<Vec<NftMint<NftMetadata>>, DriverError> {
+        let total = self.mint_total(mints.len())?;
+
+        mints
+            .into_iter()
+            .enumerate()
+            .map(|(index, mut mint)| {
+                mint.metadata.edition_number = (self.mint_number_start + index).try_into()?;
+                mint.metadata.edition_total = total.try_into()?;
+                Ok(mint)
+            })
+            .collect()
+    }
+
+    /// Plans the spend bundles that mint the NFTs. The owner of each mint is replaced with the DID.
+    pub fn mint<M, D>(
+        &self,
+        ctx: &mut SpendContext,
+        did: Did<D>,
+        mints: Vec<NftMint<M>>,
+        coin: Coin,
+    ) -> Result<NftBulkMint<M, D>, DriverError>
+    where
+        M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+        D: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+    {
+        let capacity = self.capacity();
+
+        if capacity == 0 {
+            return Err(DriverError::CostLimitTooLow);
+        }
+
+        let mint_total = self.mint_total(mints.len())?;
+
+        // Each NFT requires a mojo, and each spend bundle pays the fee.
+        let bundle_count = mints.len().div_ceil(capacity) as u128;
+        let required = mints.len() as u128 + bundle_count * u128::from(self.fee);
+
+        if u128::from(coin.amount) < required {
+            return Err(CoinSelectionError::InsufficientBalance(coin.amount.into()).into());
+        }
+
+        let existing: Vec<CoinSpend> = ctx.iter().cloned().collect();
+        let result = self.mint_batches(ctx, did, mints, coin, capacity, mint_total);
+
+        if result.is_err() {
+            // Discard the partial coin spends, and restore the ones that were there beforehand.
+            ctx.take();
+
+            for coin_spend in existing {
+                ctx.insert(coin_spend);
+            }
+        }
+
+        result
+    }
+
+    fn mint_batches<M, D>(
+        &self,
+        ctx: &mut SpendContext,
+        did: Did<D>,
+        mints: Vec<NftMint<M>>,
+        coin: Coin,
+        capacity: usize,
+        mint_total: usize,
+    ) -> Result<NftBulkMint<M, D>, DriverError>
+    where
+        M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+        D: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+    {
+        let p2 = StandardLayer::new(self.synthetic_key);
+        let puzzle_hash = StandardArgs::curry_tree_hash(self.synthetic_key).into();
+
+        let mut plan = NftBulkMint {
+            bundles: Vec::new(),
+            nfts: Vec::with_capacity(mints.len()),
+            did,
+            change: Some(coin),
+        };
+
+        let mut mints = mints.into_iter();
+        let mut mint_number = self.mint_number_start;
+
+        loop {
+            let batch: Vec<NftMint<M>> = mints.by_ref().take(capacity).collect();
+
+            if batch.is_empty() {
+                break;
+            }
+
+            let batch_len = batch.len() as u64;
+            let owner = DidOwner::from_did_info(&plan.did.info);
+            let did_coin_id = plan.did.coin.coin_id();
+            let mut did_conditions = Conditions::new();
+
+            for mint in batch {
+                let mint = NftMint {
+                    owner: Some(owner),
+                    ..mint
+                };
+
+                let (mint_nft, nft) =
+                    IntermediateLauncher::new(did_coin_id, mint_number, mint_total)
+                        .create(ctx)?
+                        .mint_nft(ctx, mint)?;
+
+                did_conditions = did_conditions.extend(mint_nft);
+                plan.nfts.push(nft);
+                mint_number += 1;
+            }
+
+            let coin = plan.change.take().ok_or(DriverError::MissingCoin)?;
+            let change = coin
+                .amount
+                .checked_sub(self.fee + batch_len)
+                .ok_or(CoinSelectionError::InsufficientBalance(coin.amount.into()))?;
+
+            let mut conditions = Conditions::new().assert_concurrent_spend(did_coin_id);
+
+            if self.fee > 0 {
+                conditions = conditions.reserve_fee(self.fee);
+            }
+
+            if change > 0 {
+                conditions = conditions.create_coin(puzzle_hash, change, vec![puzzle_hash.into()]);
+                plan.change = Some(Coin::new(coin.coin_id(), puzzle_hash, change));
+            }
+
+            p2.spend(ctx, coin, conditions)?;
+
+            plan.did = plan.did.update(ctx, &p2, did_conditions)?;
+
+            plan.bundles.push(PlannedBundle {
+                coin_spends: ctx.take(),
+                fee: self.fee,
+            });
+        }
+
+        Ok(plan)
+    }
+
+    /// The total number of NFTs in the collection, which the mint numbers must not exceed.
+    fn mint_total(&self, len: usize) -> Result<usize, DriverError> {
+        let last = self.mint_number_start.saturating_sub(1) + len;
+        let total = self.mint_total.unwrap_or(last);
+
+        if last > total {
+            return Err(DriverError::MintNumberExceedsTotal(last, total));
+        }
+
+        Ok(total)
+    }
+
+    /// The number of NFTs that can be minted in each spend bundle.
+    fn capacity(&self) -> usize {
+        let remaining = self
+            .max_cost
+            .saturating_sub(self.costs.did_spend + self.costs.xch_spend);
+        usize::try_from(remaining / self.costs.nft_mint.max(1)).unwrap_or(usize::MAX)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_consensus::spendbundle_conditions::get_conditions_from_spendbundle;
+    use chia_protocol::SpendBundle;
+    use chia_sdk_test::{sign_transaction, Simulator};
+    use chia_sdk_types::TESTNET11_CONSTANTS;
+    use clvm_traits::{FromClvmError, ToClvmError};
+    use clvm_utils::{tree_hash_atom, TreeHash, TreeHasher};
+    use clvmr::NodePtr;
+
+    use crate::Launcher;
+
+    use super::*;
+
+    /// Metadata that can be hashed but not allocated, so that minting fails partway through a spend bundle.
+    #[derive(Debug, Clone, Copy)]
+    struct UnallocatableMetadata;
+
+    impl ToClvm<Allocator> for UnallocatableMetadata {
+        fn to_clvm(&self, _encoder: &mut Allocator) -> Result<NodePtr, ToClvmError> {
+            Err(ToClvmError::Custom("unallocatable metadata".to_string()))
+        }
+    }
+
+    impl ToClvm<TreeHasher> for UnallocatableMetadata {
+        fn to_clvm(&self, _encoder: &mut TreeHasher) -> Result<TreeHash, ToClvmError> {
+            Ok(tree_hash_atom(&[]))
+        }
+    }
+
+    impl FromClvm<Allocator> for UnallocatableMetadata {
+        fn from_clvm(_decoder: &Allocator, _node: NodePtr) -> Result<Self, FromClvmError> {
+            Ok(Self)
+        }
+    }
+
+    #[test]
+    fn test_nft_bulk_mint() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let xch_coin = sim.new_coin(puzzle_hash, 1000);
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let minter = NftBulkMinter::new(pk).max_cost(1_000_000_000).fee(100);
+
+        let mints = minter.with_editions(vec![
+            NftMint::new(
+                NftMetadata::default(),
+                puzzle_hash,
+                300,
+                None
+            );
+            25
+        ])?;
+
+        let plan = minter.mint(ctx, did, mints, xch_coin)?;
+
+        assert_eq!(plan.bundles.len(), 3);
+        assert_eq!(plan.nfts.len(), 25);
+        assert_eq!(plan.total_fee(), 300);
+        assert_eq!(plan.change.map(|coin| coin.amount), Some(675));
+
+        for bundle in plan.bundles {
+            let signature = sign_transaction(&bundle.coin_spends, &[sk.clone()])?;
+            let spend_bundle = SpendBundle::new(bundle.coin_spends, signature);
+
+            let conds = get_conditions_from_spendbundle(
+                &mut ctx.allocator,
+                &spend_bundle,
+                u64::MAX,
+                100_000_000,
+                &TESTNET11_CONSTANTS,
+            )?;
+            assert!(conds.cost <= 1_000_000_000);
+
+            sim.new_transaction(spend_bundle)?;
+        }
+
+        for (index, nft) in plan.nfts.iter().enumerate() {
+            assert!(sim.coin_state(nft.coin.coin_id()).is_some());
+            assert_eq!(nft.info.current_owner, Some(plan.did.info.launcher_id));
+            assert_eq!(nft.info.metadata.edition_number, index as u64 + 1);
+            assert_eq!(nft.info.metadata.edition_total, 25);
+        }
+
+        assert!(sim.coin_state(plan.did.coin.coin_id()).is_some());
+
+        // The lineage of the minted NFTs is correct, so they can be spent.
+        let nft = plan.nfts[24].clone();
+        let _nft = nft.transfer(ctx, &p2, puzzle_hash, Conditions::new())?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nft_bulk_mint_validation() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let mints = vec![NftMint::new(NftMetadata::default(), puzzle_hash, 300, None); 5];
+
+        // The last edition number would be 12, which is more than the total.
+        let minter = NftBulkMinter::new(pk).mint_numbers(8, 10);
+        assert!(matches!(
+            minter.with_editions(mints.clone()),
+            Err(DriverError::MintNumberExceedsTotal(12, 10))
+        ));
+        assert!(matches!(
+            minter.mint(ctx, did, mints.clone(), sim.new_coin(puzzle_hash, 5)),
+            Err(DriverError::MintNumberExceedsTotal(12, 10))
+        ));
+
+        let minter = NftBulkMinter::new(pk).max_cost(100_000_000);
+        assert!(matches!(
+            minter.mint(ctx, did, mints.clone(), sim.new_coin(puzzle_hash, 5)),
+            Err(DriverError::CostLimitTooLow)
+        ));
+
+        // There's enough for the NFTs and the first spend bundle's fee, but not for the second.
+        let minter = NftBulkMinter::new(pk).max_cost(300_000_000).fee(10);
+        assert!(matches!(
+            minter.mint(ctx, did, mints, sim.new_coin(puzzle_hash, 20)),
+            Err(DriverError::CoinSelection(
+                CoinSelectionError::InsufficientBalance(20)
+            ))
+        ));
+
+        assert_eq!(ctx.iter().count(), 0);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nft_bulk_mint_restores_context() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        p2.spend(ctx, sim.new_coin(puzzle_hash, 1), Conditions::new())?;
+        let existing: Vec<CoinSpend> = ctx.iter().cloned().collect();
+
+        let mints = vec![NftMint::new(UnallocatableMetadata, puzzle_hash, 300, None); 5];
+
+        assert!(matches!(
+            NftBulkMinter::new(pk).mint(ctx, did, mints, sim.new_coin(puzzle_hash, 5)),
+            Err(DriverError::ToClvm(..))
+        ));
+
+        assert_eq!(ctx.iter().cloned().collect::<Vec<_>>(), existing);
+
+        Ok(())
+    }
+}
@@ -1,16 +1,26 @@
+use chia_bls::SecretKey;
 use chia_protocol::Bytes32;
-use chia_puzzles::nft::{NftOwnershipLayerArgs, NftStateLayerArgs, NFT_STATE_LAYER_PUZZLE_HASH};
+use chia_puzzles::{
+    nft::{NftOwnershipLayerArgs, NftStateLayerArgs, NFT_STATE_LAYER_PUZZLE_HASH},
+    offer::Payment,
+};
+use chia_sdk_signer::{
+    sign_message_for_puzzle_hash, verify_message_for_puzzle_hash, SignedMessage,
+};
 use clvm_traits::{FromClvm, ToClvm};
 use clvm_utils::{CurriedProgram, ToTreeHash, TreeHash};
 use clvmr::Allocator;
 
 use crate::{
-    DriverError, Layer, NftOwnershipLayer, NftStateLayer, Puzzle, RoyaltyTransferLayer,
-    SingletonLayer,
+    calculate_nft_royalty, DidAllowlistTransferLayer, DriverError, Layer, NftOwnershipLayer,
+    NftStateLayer, NftTransferLayer, Puzzle, RoyaltySplit, RoyaltySplitTransferLayer,
+    RoyaltyTransferLayer, SingletonLayer, SoulboundTransferLayer,
 };
 
+use super::NftTransferProgram;
+
 pub type StandardNftLayers<M, I> =
-    SingletonLayer<NftStateLayer<M, NftOwnershipLayer<RoyaltyTransferLayer, I>>>;
+    SingletonLayer<NftStateLayer<M, NftOwnershipLayer<NftTransferLayer, I>>>;
 
 #[must_use]
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -19,9 +29,12 @@ pub struct NftInfo<M> {
     pub metadata: M,
     pub metadata_updater_puzzle_hash: Bytes32,
     pub current_owner: Option<Bytes32>,
+    /// The puzzle hash that royalties are paid to, which is only used by the standard transfer program.
     pub royalty_puzzle_hash: Bytes32,
+    /// The royalty percentage in ten thousandths, which is only used by the standard transfer program.
     pub royalty_ten_thousandths: u16,
     pub p2_puzzle_hash: Bytes32,
+    pub transfer_program: NftTransferProgram,
 }
 
 impl<M> NftInfo<M> {
@@ -42,6 +55,7 @@ impl<M> NftInfo<M> {
             royalty_puzzle_hash,
             royalty_ten_thousandths,
             p2_puzzle_hash,
+            transfer_program: NftTransferProgram::Royalty,
         }
     }
 
@@ -66,50 +80,101 @@ impl<M> NftInfo<M> {
     where
         I: ToTreeHash,
     {
+        let transfer_layer = layers.inner_puzzle.inner_puzzle.transfer_layer;
+
+        let (royalty_puzzle_hash, royalty_ten_thousandths) = match transfer_layer {
+            NftTransferLayer::Royalty(layer) => {
+                (layer.royalty_puzzle_hash, layer.royalty_ten_thousandths)
+            }
+            _ => (Bytes32::default(), 0),
+        };
+
         Self {
             launcher_id: layers.launcher_id,
             metadata: layers.inner_puzzle.metadata,
             metadata_updater_puzzle_hash: layers.inner_puzzle.metadata_updater_puzzle_hash,
             current_owner: layers.inner_puzzle.inner_puzzle.current_owner,
-            royalty_puzzle_hash: layers
-                .inner_puzzle
-                .inner_puzzle
-                .transfer_layer
-                .royalty_puzzle_hash,
-            royalty_ten_thousandths: layers
-                .inner_puzzle
-                .inner_puzzle
-                .transfer_layer
-                .royalty_ten_thousandths,
+            royalty_puzzle_hash,
+            royalty_ten_thousandths,
             p2_puzzle_hash: layers
                 .inner_puzzle
                 .inner_puzzle
                 .inner_puzzle
                 .tree_hash()
                 .into(),
+            transfer_program: NftTransferProgram::from_layer(transfer_layer),
         }
     }
 
     #[must_use]
     pub fn into_layers<I>(self, p2_puzzle: I) -> StandardNftLayers<M, I> {
+        let transfer_layer = self.transfer_layer();
+
         SingletonLayer::new(
             self.launcher_id,
             NftStateLayer::new(
                 self.metadata,
                 self.metadata_updater_puzzle_hash,
-                NftOwnershipLayer::new(
-                    self.current_owner,
-                    RoyaltyTransferLayer::new(
-                        self.launcher_id,
-                        self.royalty_puzzle_hash,
-                        self.royalty_ten_thousandths,
-                    ),
-                    p2_puzzle,
-                ),
+                NftOwnershipLayer::new(self.current_owner, transfer_layer, p2_puzzle),
             ),
         )
     }
 
+    /// The transfer program layer of the NFT, curried with its launcher id.
+    pub fn transfer_layer(&self) -> NftTransferLayer {
+        match self.transfer_program {
+            NftTransferProgram::Royalty => NftTransferLayer::Royalty(RoyaltyTransferLayer::new(
+                self.launcher_id,
+                self.royalty_puzzle_hash,
+                self.royalty_ten_thousandths,
+            )),
+            NftTransferProgram::RoyaltySplit(royalties) => NftTransferLayer::RoyaltySplit(
+                RoyaltySplitTransferLayer::new(self.launcher_id, royalties),
+            ),
+            NftTransferProgram::DidAllowlist(allowed_dids) => NftTransferLayer::DidAllowlist(
+                DidAllowlistTransferLayer::new(self.launcher_id, allowed_dids),
+            ),
+            NftTransferProgram::Soulbound(p2_puzzle_hash) => NftTransferLayer::Soulbound(
+                SoulboundTransferLayer::new(self.launcher_id, p2_puzzle_hash),
+            ),
+        }
+    }
+
+    /// The royalty payments that the transfer program requires when the NFT is traded for the given price.
+    ///
+    /// Every payment is required, even if its amount is zero, since the transfer program asserts all of them.
+    /// The allocator must be the one that the transfer program's royalties are kept in.
+    pub fn royalty_payments(
+        &self,
+        allocator: &Allocator,
+        trade_price: u64,
+    ) -> Result<Vec<Payment>, DriverError> {
+        let payment = |puzzle_hash: Bytes32, ten_thousandths: u16| {
+            let amount = calculate_nft_royalty(trade_price, ten_thousandths)
+                .ok_or(DriverError::InvalidRoyalty)?;
+            Ok(Payment::with_memos(
+                puzzle_hash,
+                amount,
+                vec![puzzle_hash.into()],
+            ))
+        };
+
+        match self.transfer_program {
+            NftTransferProgram::Royalty => Ok(vec![payment(
+                self.royalty_puzzle_hash,
+                self.royalty_ten_thousandths,
+            )?]),
+            NftTransferProgram::RoyaltySplit(royalties) => {
+                Vec::<RoyaltySplit>::from_clvm(allocator, royalties.ptr())?
+                    .into_iter()
+                    .map(|royalty| payment(royalty.puzzle_hash, royalty.ten_thousandths))
+                    .collect()
+            }
+            NftTransferProgram::DidAllowlist(..) => Ok(Vec::new()),
+            NftTransferProgram::Soulbound(..) => Err(DriverError::UntradableNft),
+        }
+    }
+
     pub fn with_metadata<N>(self, metadata: N) -> NftInfo<N> {
         NftInfo {
             launcher_id: self.launcher_id,
@@ -119,6 +184,7 @@ impl<M> NftInfo<M> {
             royalty_puzzle_hash: self.royalty_puzzle_hash,
             royalty_ten_thousandths: self.royalty_ten_thousandths,
             p2_puzzle_hash: self.p2_puzzle_hash,
+            transfer_program: self.transfer_program,
         }
     }
 
@@ -136,6 +202,24 @@ impl<M> NftInfo<M> {
         }
     }
 
+    /// Replaces the standard royalty transfer program.
+    ///
+    /// The royalty puzzle hash and percentage are reset to zero for any other program, since they aren't curried into it.
+    pub fn with_transfer_program(self, transfer_program: NftTransferProgram) -> Self {
+        match transfer_program {
+            NftTransferProgram::Royalty => Self {
+                transfer_program,
+                ..self
+            },
+            _ => Self {
+                royalty_puzzle_hash: Bytes32::default(),
+                royalty_ten_thousandths: 0,
+                transfer_program,
+                ..self
+            },
+        }
+    }
+
     pub fn inner_puzzle_hash(&self) -> TreeHash
     where
         M: ToTreeHash,
@@ -148,18 +232,31 @@ impl<M> NftInfo<M> {
                 metadata_updater_puzzle_hash: self.metadata_updater_puzzle_hash,
                 inner_puzzle: NftOwnershipLayerArgs::curry_tree_hash(
                     self.current_owner,
-                    RoyaltyTransferLayer::new(
-                        self.launcher_id,
-                        self.royalty_puzzle_hash,
-                        self.royalty_ten_thousandths,
-                    )
-                    .tree_hash(),
+                    self.transfer_layer().tree_hash(),
                     self.p2_puzzle_hash.into(),
                 ),
             },
         }
         .tree_hash()
     }
+
+    /// Signs a CHIP-0002 message as the NFT, with the key of its current p2 puzzle hash.
+    pub fn sign_message(
+        &self,
+        secret_keys: &[SecretKey],
+        message: &[u8],
+    ) -> Result<SignedMessage, DriverError> {
+        Ok(sign_message_for_puzzle_hash(
+            secret_keys,
+            self.p2_puzzle_hash,
+            message,
+        )?)
+    }
+
+    /// Verifies that a CHIP-0002 message was signed by the current owner of the NFT.
+    pub fn verify_message(&self, signed_message: &SignedMessage, message: &[u8]) -> bool {
+        verify_message_for_puzzle_hash(signed_message, self.p2_puzzle_hash, message)
+    }
 }
 
 #[cfg(test)]
@@ -22,10 +22,8 @@ impl Launcher {
     where
         M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
     {
-        let launcher_coin = self.coin();
-
         let nft_info = NftInfo::new(
-            launcher_coin.coin_id(),
+            self.coin().coin_id(),
             metadata,
             metadata_updater_puzzle_hash,
             None,
@@ -34,6 +32,20 @@ impl Launcher {
             p2_puzzle_hash,
         );
 
+        self.mint_eve_nft_with_info(ctx, nft_info)
+    }
+
+    /// Mints the eve NFT with the given info, which can have any transfer program.
+    fn mint_eve_nft_with_info<M>(
+        self,
+        ctx: &mut SpendContext,
+        nft_info: NftInfo<M>,
+    ) -> Result<(Conditions, Nft<M>), DriverError>
+    where
+        M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+    {
+        let launcher_coin = self.coin();
+
         let inner_puzzle_hash = nft_info.inner_puzzle_hash();
         let (launch_singleton, eve_coin) = self.spend(ctx, inner_puzzle_hash.into(), ())?;
 
@@ -72,14 +84,18 @@ impl Launcher {
         let p2_puzzle_hash = ctx.tree_hash(inner_puzzle).into();
         let inner_spend = Spend::new(inner_puzzle, NodePtr::NIL);
 
-        let (mint_eve_nft, eve_nft) = self.mint_eve_nft(
-            ctx,
-            p2_puzzle_hash,
+        let nft_info = NftInfo::new(
+            self.coin().coin_id(),
             mint.metadata,
             mint.metadata_updater_puzzle_hash,
+            None,
             mint.royalty_puzzle_hash,
             mint.royalty_ten_thousandths,
-        )?;
+            p2_puzzle_hash,
+        )
+        .with_transfer_program(mint.transfer_program);
+
+        let (mint_eve_nft, eve_nft) = self.mint_eve_nft_with_info(ctx, nft_info)?;
 
         eve_nft.spend(ctx, inner_spend)?;
 
@@ -1,16 +1,19 @@
 use chia_protocol::Bytes32;
 use chia_puzzles::nft::NFT_METADATA_UPDATER_PUZZLE_HASH;
 
-use super::DidOwner;
+use super::{DidOwner, NftTransferProgram};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct NftMint<M> {
     pub metadata: M,
     pub metadata_updater_puzzle_hash: Bytes32,
+    /// The puzzle hash that royalties are paid to, which is only used by the standard transfer program.
     pub royalty_puzzle_hash: Bytes32,
+    /// The royalty percentage in ten thousandths, which is only used by the standard transfer program.
     pub royalty_ten_thousandths: u16,
     pub p2_puzzle_hash: Bytes32,
     pub owner: Option<DidOwner>,
+    pub transfer_program: NftTransferProgram,
 }
 
 impl<M> NftMint<M> {
@@ -27,6 +30,7 @@ impl<M> NftMint<M> {
             royalty_ten_thousandths,
             p2_puzzle_hash,
             owner,
+            transfer_program: NftTransferProgram::Royalty,
         }
     }
 
@@ -38,6 +42,16 @@ impl<M> NftMint<M> {
         }
     }
 
+    /// Mints the NFT with a transfer program other than the standard royalty one, in which case the royalty
+    /// puzzle hash and percentage are ignored.
+    #[must_use]
+    pub fn with_transfer_program(self, transfer_program: NftTransferProgram) -> Self {
+        Self {
+            transfer_program,
+            ..self
+        }
+    }
+
     #[must_use]
     pub fn with_custom_metadata_updater(self, metadata_updater_puzzle_hash: Bytes32) -> Self {
         Self {
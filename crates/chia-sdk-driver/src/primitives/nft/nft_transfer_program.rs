@@ -0,0 +1,260 @@
+use chia_protocol::Bytes32;
+
+use crate::{DriverError, HashedPtr, NftTransferLayer, RoyaltySplit, SpendContext};
+
+/// The transfer program of an NFT, which decides how it can be assigned to DIDs and traded.
+///
+/// The lists of royalties and allowed DIDs are kept in the allocator, like the metadata of an NFT
+/// usually is, so that the [`NftInfo`](crate::NftInfo) can stay [`Copy`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum NftTransferProgram {
+    /// Pays the royalty percentage of the NFT to its royalty puzzle hash. This is the standard transfer program.
+    #[default]
+    Royalty,
+    /// Pays a percentage of the trade price to each of the recipients in the list of [`RoyaltySplit`].
+    RoyaltySplit(HashedPtr),
+    /// Can only be assigned to the DIDs in the list of launcher ids, and doesn't pay royalties.
+    DidAllowlist(HashedPtr),
+    /// Can't be traded or sent to any p2 puzzle hash other than this one.
+    Soulbound(Bytes32),
+}
+
+impl NftTransferProgram {
+    /// Allocates the royalties, and pays each recipient their own percentage of the trade price.
+    pub fn royalty_split(
+        ctx: &mut SpendContext,
+        royalties: &[RoyaltySplit],
+    ) -> Result<Self, DriverError> {
+        let ptr = ctx.alloc(&royalties.to_vec())?;
+        Ok(Self::RoyaltySplit(HashedPtr::from_ptr(&ctx.allocator, ptr)))
+    }
+
+    /// Allocates the launcher ids of the DIDs that the NFT can be assigned to.
+    pub fn did_allowlist(
+        ctx: &mut SpendContext,
+        allowed_dids: &[Bytes32],
+    ) -> Result<Self, DriverError> {
+        let ptr = ctx.alloc(&allowed_dids.to_vec())?;
+        Ok(Self::DidAllowlist(HashedPtr::from_ptr(&ctx.allocator, ptr)))
+    }
+
+    pub fn from_layer(layer: NftTransferLayer) -> Self {
+        match layer {
+            NftTransferLayer::Royalty(..) => Self::Royalty,
+            NftTransferLayer::RoyaltySplit(layer) => Self::RoyaltySplit(layer.royalties),
+            NftTransferLayer::DidAllowlist(layer) => Self::DidAllowlist(layer.allowed_dids),
+            NftTransferLayer::Soulbound(layer) => Self::Soulbound(layer.p2_puzzle_hash),
+        }
+    }
+
+    /// Whether the NFT can be locked in the settlement payments puzzle and traded in an offer.
+    pub fn is_tradable(&self) -> bool {
+        !matches!(self, Self::Soulbound(..))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_puzzles::{nft::NftMetadata, offer::Payment};
+    use chia_sdk_signer::SignerError;
+    use chia_sdk_test::{Simulator, SimulatorError};
+    use chia_sdk_types::Conditions;
+    use clvm_traits::ToClvm;
+    use clvmr::{reduction::EvalErr, Allocator};
+
+    use crate::{
+        DidOwner, IntermediateLauncher, Launcher, NftInfo, NftMint, Puzzle, SpendContext,
+        StandardLayer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_soulbound_nft() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(2)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+
+        // The royalty percentage isn't curried into the soulbound transfer program.
+        let mint = NftMint::new(
+            NftMetadata::default(),
+            puzzle_hash,
+            300,
+            Some(DidOwner::from_did_info(&did.info)),
+        )
+        .with_transfer_program(NftTransferProgram::Soulbound(puzzle_hash));
+
+        let (mint_nft, nft) = IntermediateLauncher::new(did.coin.coin_id(), 0, 1)
+            .create(ctx)?
+            .mint_nft(ctx, mint)?;
+        let _did = did.update(ctx, &p2, mint_nft)?;
+        assert_eq!(nft.info.royalty_ten_thousandths, 0);
+
+        // The NFT can still be spent, as long as it stays with the same p2 puzzle hash.
+        let nft = nft.transfer(ctx, &p2, puzzle_hash, Conditions::new())?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let puzzle_reveal = sim
+            .puzzle_reveal(nft.coin.parent_coin_info)
+            .expect("missing nft puzzle");
+        let mut allocator = Allocator::new();
+        let ptr = puzzle_reveal.to_clvm(&mut allocator)?;
+        let (info, _) = NftInfo::<NftMetadata>::parse(&allocator, Puzzle::parse(&allocator, ptr))?
+            .expect("not an nft");
+        assert_eq!(
+            info.transfer_program,
+            NftTransferProgram::Soulbound(puzzle_hash)
+        );
+        assert_eq!(info.royalty_ten_thousandths, 0);
+
+        assert!(matches!(
+            nft.clone()
+                .lock_settlement(ctx, &p2, Vec::new(), Conditions::new()),
+            Err(DriverError::UntradableNft)
+        ));
+
+        // Sending it anywhere else raises in the transfer program, so the spend can't even be signed.
+        let _nft = nft.transfer(ctx, &p2, Bytes32::new([1; 32]), Conditions::new())?;
+        assert!(matches!(
+            sim.spend_coins(ctx.take(), &[sk]).unwrap_err(),
+            SimulatorError::Signer(SignerError::Eval(EvalErr(_, message))) if message == "clvm raise"
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_soulbound_nft_same_owner() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(2)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+
+        let mint = NftMint::new(NftMetadata::default(), puzzle_hash, 0, None)
+            .with_transfer_program(NftTransferProgram::Soulbound(puzzle_hash));
+
+        let (mint_nft, nft) = IntermediateLauncher::new(did.coin.coin_id(), 0, 1)
+            .create(ctx)?
+            .mint_nft(ctx, mint)?;
+        let did = did.update(ctx, &p2, mint_nft)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        // Transferring the NFT back to the same owner is allowed any number of times.
+        let nft = nft.transfer(ctx, &p2, puzzle_hash, Conditions::new())?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let nft = nft.transfer(ctx, &p2, puzzle_hash, Conditions::new())?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        // So is assigning it to a DID, as long as the p2 puzzle hash stays the same.
+        let (assign_nft, nft) = nft.transfer_to_did(
+            ctx,
+            &p2,
+            puzzle_hash,
+            Some(DidOwner::from_did_info(&did.info)),
+            Conditions::new(),
+        )?;
+        let _did = did.update(ctx, &p2, assign_nft)?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        assert!(sim.coin_state(nft.coin.coin_id()).is_some());
+        assert_eq!(nft.info.p2_puzzle_hash, puzzle_hash);
+        assert_eq!(nft.info.current_owner, Some(did.info.launcher_id));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_did_allowlist_nft() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(2)?;
+        let other_coin = sim.new_coin(puzzle_hash, 2);
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+
+        let (create_other_did, other_did) =
+            Launcher::new(other_coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, other_coin, create_other_did)?;
+
+        let transfer_program = NftTransferProgram::did_allowlist(ctx, &[did.info.launcher_id])?;
+        let mint = NftMint::new(NftMetadata::default(), puzzle_hash, 0, None)
+            .with_transfer_program(transfer_program);
+
+        let (mint_nft, nft) = IntermediateLauncher::new(did.coin.coin_id(), 0, 1)
+            .create(ctx)?
+            .mint_nft(ctx, mint)?;
+        let did = did.update(ctx, &p2, mint_nft)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        // The NFT can be assigned to a DID on the allowlist.
+        let (assign_nft, nft) = nft.transfer_to_did(
+            ctx,
+            &p2,
+            puzzle_hash,
+            Some(DidOwner::from_did_info(&did.info)),
+            Conditions::new(),
+        )?;
+        let _did = did.update(ctx, &p2, assign_nft)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+        assert_eq!(nft.info.current_owner, Some(did.info.launcher_id));
+
+        // But not to any other DID.
+        let (assign_nft, _nft) = nft.transfer_to_did(
+            ctx,
+            &p2,
+            puzzle_hash,
+            Some(DidOwner::from_did_info(&other_did.info)),
+            Conditions::new(),
+        )?;
+        let _other_did = other_did.update(ctx, &p2, assign_nft)?;
+        assert!(matches!(
+            sim.spend_coins(ctx.take(), &[sk]).unwrap_err(),
+            SimulatorError::Signer(..)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_royalty_payments() -> anyhow::Result<()> {
+        let ctx = &mut SpendContext::new();
+
+        let first = Bytes32::new([1; 32]);
+        let second = Bytes32::new([2; 32]);
+        let info = NftInfo::new(first, (), first, None, first, 300, first);
+
+        let program = NftTransferProgram::royalty_split(
+            ctx,
+            &[RoyaltySplit::new(first, 250), RoyaltySplit::new(second, 50)],
+        )?;
+        assert_eq!(
+            info.with_transfer_program(program)
+                .royalty_payments(&ctx.allocator, 1000)?,
+            [
+                Payment::with_memos(first, 25, vec![first.into()]),
+                Payment::with_memos(second, 5, vec![second.into()]),
+            ]
+        );
+
+        let program = NftTransferProgram::did_allowlist(ctx, &[])?;
+        assert!(info
+            .with_transfer_program(program)
+            .royalty_payments(&ctx.allocator, 1000)?
+            .is_empty());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,325 @@
+use chia_protocol::{Bytes32, Coin};
+use chia_puzzles::{
+    singleton::{SingletonArgs, SingletonSolution},
+    EveProof, LineageProof, Proof,
+};
+use chia_sdk_types::{run_puzzle, Condition, Conditions};
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::{ToTreeHash, TreeHash};
+use clvmr::{Allocator, NodePtr};
+
+use crate::{
+    DriverError, Launcher, Layer, Puzzle, SingletonLayer, Spend, SpendContext, SpendWithConditions,
+};
+
+/// A generic singleton, for custom singleton apps that don't have a dedicated primitive.
+///
+/// The info can be anything that the inner puzzle hash is calculated from, such as a layer
+/// or just the [`TreeHash`] of the inner puzzle itself.
+#[must_use]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Singleton<I> {
+    pub coin: Coin,
+    pub proof: Proof,
+    pub launcher_id: Bytes32,
+    pub info: I,
+}
+
+impl<I> Singleton<I> {
+    pub fn new(coin: Coin, proof: Proof, launcher_id: Bytes32, info: I) -> Self {
+        Self {
+            coin,
+            proof,
+            launcher_id,
+            info,
+        }
+    }
+
+    pub fn with_info<N>(self, info: N) -> Singleton<N> {
+        Singleton {
+            coin: self.coin,
+            proof: self.proof,
+            launcher_id: self.launcher_id,
+            info,
+        }
+    }
+}
+
+impl<I> Singleton<I>
+where
+    I: ToTreeHash,
+{
+    pub fn inner_puzzle_hash(&self) -> TreeHash {
+        self.info.tree_hash()
+    }
+
+    /// Returns the lineage proof that would be used by the child.
+    pub fn child_lineage_proof(&self) -> LineageProof {
+        LineageProof {
+            parent_parent_coin_info: self.coin.parent_coin_info,
+            parent_inner_puzzle_hash: self.inner_puzzle_hash().into(),
+            parent_amount: self.coin.amount,
+        }
+    }
+
+    /// Creates a wrapped spendable singleton for the child, with the same amount.
+    pub fn child<N>(&self, info: N) -> Singleton<N>
+    where
+        N: ToTreeHash,
+    {
+        self.child_with_amount(info, self.coin.amount)
+    }
+
+    /// Creates a wrapped spendable singleton for the child, with a different odd amount.
+    ///
+    /// An even amount can't be a singleton, which is checked in debug builds.
+    pub fn child_with_amount<N>(&self, info: N, amount: u64) -> Singleton<N>
+    where
+        N: ToTreeHash,
+    {
+        debug_assert!(amount % 2 == 1, "singleton amounts must be odd");
+
+        Singleton {
+            coin: Coin::new(
+                self.coin.coin_id(),
+                SingletonArgs::curry_tree_hash(self.launcher_id, info.tree_hash()).into(),
+                amount,
+            ),
+            proof: Proof::Lineage(self.child_lineage_proof()),
+            launcher_id: self.launcher_id,
+            info,
+        }
+    }
+
+    /// Creates a coin spend for this singleton.
+    ///
+    /// The inner puzzle of the spend must match the inner puzzle hash of the singleton.
+    pub fn spend(&self, ctx: &mut SpendContext, inner_spend: Spend) -> Result<(), DriverError> {
+        let layer = SingletonLayer::new(self.launcher_id, inner_spend.puzzle);
+
+        let puzzle = layer.construct_puzzle(ctx)?;
+        let solution = layer.construct_solution(
+            ctx,
+            SingletonSolution {
+                lineage_proof: self.proof,
+                amount: self.coin.amount,
+                inner_solution: inner_spend.solution,
+            },
+        )?;
+
+        ctx.spend(self.coin, Spend::new(puzzle, solution))
+    }
+
+    /// Spends this singleton with an inner puzzle that supports being spent with conditions.
+    pub fn spend_with<P>(
+        &self,
+        ctx: &mut SpendContext,
+        inner: &P,
+        conditions: Conditions,
+    ) -> Result<(), DriverError>
+    where
+        P: SpendWithConditions,
+    {
+        let inner_spend = inner.spend_with_conditions(ctx, conditions)?;
+        self.spend(ctx, inner_spend)
+    }
+
+    /// Recreates this singleton with new info, and outputs additional conditions via the inner puzzle.
+    pub fn update<P, N>(
+        self,
+        ctx: &mut SpendContext,
+        inner: &P,
+        info: N,
+        extra_conditions: Conditions,
+    ) -> Result<Singleton<N>, DriverError>
+    where
+        P: SpendWithConditions,
+        N: ToTreeHash,
+    {
+        self.spend_with(
+            ctx,
+            inner,
+            extra_conditions.create_coin(info.tree_hash().into(), self.coin.amount, Vec::new()),
+        )?;
+
+        Ok(self.child(info))
+    }
+
+    /// Melts this singleton, so that it's spent without creating a child.
+    pub fn melt<P>(
+        self,
+        ctx: &mut SpendContext,
+        inner: &P,
+        extra_conditions: Conditions,
+    ) -> Result<(), DriverError>
+    where
+        P: SpendWithConditions,
+    {
+        self.spend_with(ctx, inner, extra_conditions.melt_singleton())
+    }
+}
+
+impl Singleton<TreeHash> {
+    /// Parses the child of a singleton coin spend, which is the coin created with an odd amount.
+    ///
+    /// Returns [`None`] if the parent isn't a singleton, or if it was melted.
+    pub fn parse_child(
+        allocator: &mut Allocator,
+        parent_coin: Coin,
+        parent_puzzle: Puzzle,
+        parent_solution: NodePtr,
+    ) -> Result<Option<Self>, DriverError> {
+        let Some(singleton_layer) =
+            SingletonLayer::<Puzzle>::parse_puzzle(allocator, parent_puzzle)?
+        else {
+            return Ok(None);
+        };
+
+        let singleton_solution =
+            SingletonLayer::<NodePtr>::parse_solution(allocator, parent_solution)?;
+
+        let output = run_puzzle(
+            allocator,
+            singleton_layer.inner_puzzle.ptr(),
+            singleton_solution.inner_solution,
+        )?;
+        let conditions = Vec::<Condition>::from_clvm(allocator, output)?;
+
+        let Some(create_coin) = conditions
+            .into_iter()
+            .filter_map(Condition::into_create_coin)
+            .find(|create_coin| create_coin.amount % 2 == 1)
+        else {
+            return Ok(None);
+        };
+
+        let parent = Singleton::new(
+            parent_coin,
+            singleton_solution.lineage_proof,
+            singleton_layer.launcher_id,
+            singleton_layer.inner_puzzle.curried_puzzle_hash(),
+        );
+
+        Ok(Some(parent.child_with_amount(
+            TreeHash::from(create_coin.puzzle_hash),
+            create_coin.amount,
+        )))
+    }
+}
+
+impl Launcher {
+    /// Creates an eve singleton with the given info, and a key value list that is revealed in the launcher solution.
+    pub fn create_singleton<I, T>(
+        self,
+        ctx: &mut SpendContext,
+        info: I,
+        key_value_list: T,
+    ) -> Result<(Conditions, Singleton<I>), DriverError>
+    where
+        I: ToTreeHash,
+        T: ToClvm<Allocator>,
+    {
+        let launcher_coin = self.coin();
+
+        let (launch_singleton, eve_coin) =
+            self.spend(ctx, info.tree_hash().into(), key_value_list)?;
+
+        let proof = Proof::Eve(EveProof {
+            parent_parent_coin_info: launcher_coin.parent_coin_info,
+            parent_amount: launcher_coin.amount,
+        });
+
+        Ok((
+            launch_singleton,
+            Singleton::new(eve_coin, proof, launcher_coin.coin_id(), info),
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_sdk_test::Simulator;
+
+    use crate::StandardLayer;
+
+    use super::*;
+
+    #[test]
+    fn test_singleton_lifecycle() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(3)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (launch_singleton, singleton) =
+            Launcher::new(coin.coin_id(), 1).create_singleton(ctx, p2, ())?;
+        p2.spend(ctx, coin, launch_singleton)?;
+
+        // The even coin isn't part of the singleton lineage.
+        let singleton = singleton.update(
+            ctx,
+            &p2,
+            p2,
+            Conditions::new().create_coin(puzzle_hash, 2, Vec::new()),
+        )?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let parent_coin = sim
+            .coin_state(singleton.coin.parent_coin_info)
+            .expect("missing parent coin state")
+            .coin;
+        let puzzle_reveal = sim
+            .puzzle_reveal(parent_coin.coin_id())
+            .expect("missing puzzle")
+            .to_clvm(&mut ctx.allocator)?;
+        let solution = sim
+            .solution(parent_coin.coin_id())
+            .expect("missing solution")
+            .to_clvm(&mut ctx.allocator)?;
+
+        let puzzle = Puzzle::parse(&ctx.allocator, puzzle_reveal);
+        let child = Singleton::parse_child(&mut ctx.allocator, parent_coin, puzzle, solution)?
+            .expect("missing child");
+
+        assert_eq!(child, singleton.with_info(p2.tree_hash()));
+
+        // Once it's melted, there's no child.
+        singleton.melt(ctx, &p2, Conditions::new())?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        let puzzle_reveal = sim
+            .puzzle_reveal(singleton.coin.coin_id())
+            .expect("missing puzzle")
+            .to_clvm(&mut ctx.allocator)?;
+        let solution = sim
+            .solution(singleton.coin.coin_id())
+            .expect("missing solution")
+            .to_clvm(&mut ctx.allocator)?;
+
+        let puzzle = Puzzle::parse(&ctx.allocator, puzzle_reveal);
+        assert_eq!(
+            Singleton::parse_child(&mut ctx.allocator, singleton.coin, puzzle, solution)?,
+            None
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "singleton amounts must be odd")]
+    fn test_even_child_amount() {
+        let singleton = Singleton::new(
+            Coin::new(Bytes32::default(), Bytes32::default(), 1),
+            Proof::Eve(EveProof {
+                parent_parent_coin_info: Bytes32::default(),
+                parent_amount: 1,
+            }),
+            Bytes32::default(),
+            TreeHash::new([0; 32]),
+        );
+
+        let _child = singleton.child_with_amount(TreeHash::new([0; 32]), 2);
+    }
+}
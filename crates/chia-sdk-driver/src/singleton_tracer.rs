@@ -0,0 +1,454 @@
+use std::collections::HashMap;
+
+use chia_protocol::{Bytes32, Coin, CoinSpend, CoinState};
+use chia_puzzles::singleton::SINGLETON_LAUNCHER_PUZZLE_HASH;
+use chia_sdk_client::Peer;
+use clvm_traits::{FromClvm, ToClvm};
+use clvm_utils::{ToTreeHash, TreeHash};
+use clvmr::{Allocator, NodePtr};
+
+use crate::{Did, DidInfo, DriverError, Layer, Nft, NftInfo, Puzzle, Singleton, SingletonLayer};
+
+/// A singleton primitive that can be parsed generation by generation, starting from the launcher spend.
+pub trait TraceSingleton: Sized {
+    /// Parses the child of the parent coin spend, given the previous generation if it was parsed.
+    ///
+    /// Returns [`None`] if the parent is the launcher and the primitive can't be parsed from its solution.
+    fn parse_child(
+        allocator: &mut Allocator,
+        parent_spend: &CoinSpend,
+        child_coin: Coin,
+        parent: Option<&Self>,
+    ) -> Result<Option<Self>, DriverError>;
+
+    /// Parses the eve generation from its own coin spend, since the launcher spend doesn't reveal its puzzle.
+    ///
+    /// This is only called if [`TraceSingleton::parse_child`] returned [`None`] for the launcher.
+    fn parse_eve(
+        _allocator: &mut Allocator,
+        _eve_spend: &CoinSpend,
+    ) -> Result<Option<Self>, DriverError> {
+        Ok(None)
+    }
+
+    fn coin(&self) -> Coin;
+}
+
+/// Parses the puzzle and solution of a coin spend.
+fn parse_spend(
+    allocator: &mut Allocator,
+    coin_spend: &CoinSpend,
+) -> Result<(Puzzle, NodePtr), DriverError> {
+    let puzzle = coin_spend.puzzle_reveal.to_clvm(allocator)?;
+    let solution = coin_spend.solution.to_clvm(allocator)?;
+    Ok((Puzzle::parse(allocator, puzzle), solution))
+}
+
+impl<M> TraceSingleton for Nft<M>
+where
+    M: ToClvm<Allocator> + FromClvm<Allocator> + ToTreeHash + Clone,
+{
+    fn parse_child(
+        allocator: &mut Allocator,
+        parent_spend: &CoinSpend,
+        _child_coin: Coin,
+        _parent: Option<&Self>,
+    ) -> Result<Option<Self>, DriverError> {
+        let (puzzle, solution) = parse_spend(allocator, parent_spend)?;
+        Nft::parse_child(allocator, parent_spend.coin, puzzle, solution)
+    }
+
+    fn parse_eve(
+        allocator: &mut Allocator,
+        eve_spend: &CoinSpend,
+    ) -> Result<Option<Self>, DriverError> {
+        let (puzzle, solution) = parse_spend(allocator, eve_spend)?;
+
+        let Some((info, _p2_puzzle)) = NftInfo::parse(allocator, puzzle)? else {
+            return Ok(None);
+        };
+
+        let proof = SingletonLayer::<NodePtr>::parse_solution(allocator, solution)?.lineage_proof;
+
+        Ok(Some(Nft::new(eve_spend.coin, proof, info)))
+    }
+
+    fn coin(&self) -> Coin {
+        self.coin
+    }
+}
+
+impl<M> TraceSingleton for Did<M>
+where
+    M: ToClvm<Allocator> + FromClvm<Allocator> + Clone,
+{
+    fn parse_child(
+        allocator: &mut Allocator,
+        parent_spend: &CoinSpend,
+        child_coin: Coin,
+        _parent: Option<&Self>,
+    ) -> Result<Option<Self>, DriverError> {
+        let (puzzle, solution) = parse_spend(allocator, parent_spend)?;
+        Did::parse_child(allocator, parent_spend.coin, puzzle, solution, child_coin)
+    }
+
+    fn parse_eve(
+        allocator: &mut Allocator,
+        eve_spend: &CoinSpend,
+    ) -> Result<Option<Self>, DriverError> {
+        let (puzzle, solution) = parse_spend(allocator, eve_spend)?;
+
+        let Some((info, _p2_puzzle)) = DidInfo::parse(allocator, puzzle)? else {
+            return Ok(None);
+        };
+
+        let proof = SingletonLayer::<NodePtr>::parse_solution(allocator, solution)?.lineage_proof;
+
+        Ok(Some(Did::new(eve_spend.coin, proof, info)))
+    }
+
+    fn coin(&self) -> Coin {
+        self.coin
+    }
+}
+
+impl TraceSingleton for Singleton<TreeHash> {
+    fn parse_child(
+        allocator: &mut Allocator,
+        parent_spend: &CoinSpend,
+        _child_coin: Coin,
+        _parent: Option<&Self>,
+    ) -> Result<Option<Self>, DriverError> {
+        let (puzzle, solution) = parse_spend(allocator, parent_spend)?;
+        Singleton::parse_child(allocator, parent_spend.coin, puzzle, solution)
+    }
+
+    fn parse_eve(
+        allocator: &mut Allocator,
+        eve_spend: &CoinSpend,
+    ) -> Result<Option<Self>, DriverError> {
+        let (puzzle, solution) = parse_spend(allocator, eve_spend)?;
+
+        let Some(layer) = SingletonLayer::<Puzzle>::parse_puzzle(allocator, puzzle)? else {
+            return Ok(None);
+        };
+
+        let proof = SingletonLayer::<NodePtr>::parse_solution(allocator, solution)?.lineage_proof;
+
+        Ok(Some(Singleton::new(
+            eve_spend.coin,
+            proof,
+            layer.launcher_id,
+            layer.inner_puzzle.curried_puzzle_hash(),
+        )))
+    }
+
+    fn coin(&self) -> Coin {
+        self.coin
+    }
+}
+
+#[cfg(feature = "chip-0035")]
+impl<M> TraceSingleton for crate::DataStore<M>
+where
+    M: ToClvm<Allocator> + FromClvm<Allocator> + crate::MetadataWithRootHash,
+{
+    fn parse_child(
+        allocator: &mut Allocator,
+        parent_spend: &CoinSpend,
+        _child_coin: Coin,
+        parent: Option<&Self>,
+    ) -> Result<Option<Self>, DriverError> {
+        let delegated_puzzles = parent.map_or(&[][..], |parent| &parent.info.delegated_puzzles);
+        crate::DataStore::from_spend(allocator, parent_spend, delegated_puzzles)
+    }
+
+    fn coin(&self) -> Coin {
+        self.coin
+    }
+}
+
+/// The history of a singleton, from its launcher up to the latest coin that has been traced.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SingletonTrace<T> {
+    pub launcher_id: Bytes32,
+    /// The state of the latest coin in the lineage, which is the launcher until the singleton has been created.
+    pub tip: CoinState,
+    /// Every generation that has been parsed, in order, starting with the eve.
+    ///
+    /// The eve is only parsed once it has been spent, since its puzzle isn't revealed before then.
+    pub history: Vec<T>,
+}
+
+impl<T> SingletonTrace<T> {
+    /// The latest generation of the singleton, if it has been parsed.
+    pub fn latest(&self) -> Option<&T> {
+        self.history.last()
+    }
+
+    /// Whether the tip has been spent without creating a child.
+    pub fn is_melted(&self) -> bool {
+        self.tip.spent_height.is_some()
+    }
+}
+
+/// Walks singletons forward from their launchers to find their current state.
+///
+/// Traces are cached, so tracing the same singleton again only requests the generations that were created since.
+/// If the primitive references the allocator, the same allocator must be used every time.
+#[derive(Debug, Clone)]
+pub struct SingletonTracer<T> {
+    genesis_challenge: Bytes32,
+    traces: HashMap<Bytes32, SingletonTrace<T>>,
+}
+
+impl<T> SingletonTracer<T>
+where
+    T: TraceSingleton,
+{
+    pub fn new(genesis_challenge: Bytes32) -> Self {
+        Self {
+            genesis_challenge,
+            traces: HashMap::new(),
+        }
+    }
+
+    /// The cached trace of a singleton, without requesting any updates from the peer.
+    pub fn cached(&self, launcher_id: Bytes32) -> Option<&SingletonTrace<T>> {
+        self.traces.get(&launcher_id)
+    }
+
+    /// Traces the singleton with the given launcher id up to its latest coin.
+    pub async fn trace(
+        &mut self,
+        allocator: &mut Allocator,
+        peer: &Peer,
+        launcher_id: Bytes32,
+    ) -> Result<&SingletonTrace<T>, DriverError> {
+        let mut trace = if let Some(trace) = self.traces.remove(&launcher_id) {
+            trace
+        } else {
+            SingletonTrace {
+                launcher_id,
+                tip: request_coin_state(peer, launcher_id, self.genesis_challenge).await?,
+                history: Vec::new(),
+            }
+        };
+
+        let result = self.advance(allocator, peer, &mut trace).await;
+
+        let trace = self.traces.entry(launcher_id).or_insert(trace);
+        result.map(|()| &*trace)
+    }
+
+    async fn advance(
+        &self,
+        allocator: &mut Allocator,
+        peer: &Peer,
+        trace: &mut SingletonTrace<T>,
+    ) -> Result<(), DriverError> {
+        if trace.tip.spent_height.is_none() {
+            trace.tip =
+                request_coin_state(peer, trace.tip.coin.coin_id(), self.genesis_challenge).await?;
+        }
+
+        while let Some((child, generations)) = trace_child(
+            allocator,
+            peer,
+            trace.launcher_id,
+            &trace.tip,
+            trace.latest(),
+        )
+        .await?
+        {
+            trace.history.extend(generations);
+            trace.tip = child;
+        }
+
+        Ok(())
+    }
+}
+
+/// Finds the child of a spent singleton coin, and parses the generations revealed by its spend.
+///
+/// Returns [`None`] if the coin is unspent or was melted. The generations are empty for the launcher,
+/// unless the primitive can be parsed from its spend, and include the eve itself when the tip is the eve.
+pub(crate) async fn trace_child<T>(
+    allocator: &mut Allocator,
+    peer: &Peer,
+    launcher_id: Bytes32,
+    tip: &CoinState,
+    parent: Option<&T>,
+) -> Result<Option<(CoinState, Vec<T>)>, DriverError>
+where
+    T: TraceSingleton,
+{
+    let Some(spent_height) = tip.spent_height else {
+        return Ok(None);
+    };
+
+    let coin_id = tip.coin.coin_id();
+
+    let children = peer.request_children(coin_id).await?.coin_states;
+
+    let Some(child) = children
+        .into_iter()
+        .find(|child| child.coin.amount % 2 == 1)
+    else {
+        // The singleton has been melted.
+        return Ok(None);
+    };
+
+    let response = peer
+        .request_puzzle_and_solution(coin_id, spent_height)
+        .await?
+        .map_err(|rejection| DriverError::RejectPuzzleSolution(rejection.coin_name))?;
+
+    let parent_spend = CoinSpend::new(tip.coin, response.puzzle, response.solution);
+
+    let mut generations = Vec::new();
+
+    if parent.is_none() && tip.coin.parent_coin_info == launcher_id {
+        let Some(eve) = T::parse_eve(allocator, &parent_spend)? else {
+            return Err(DriverError::UnexpectedSingleton(launcher_id));
+        };
+        generations.push(eve);
+    }
+
+    let parent = generations.last().or(parent);
+
+    match T::parse_child(allocator, &parent_spend, child.coin, parent)? {
+        Some(parsed) if parsed.coin() == child.coin => generations.push(parsed),
+        None if tip.coin.puzzle_hash == SINGLETON_LAUNCHER_PUZZLE_HASH.into() => {}
+        _ => return Err(DriverError::UnexpectedSingleton(launcher_id)),
+    }
+
+    Ok(Some((child, generations)))
+}
+
+pub(crate) async fn request_coin_state(
+    peer: &Peer,
+    coin_id: Bytes32,
+    genesis_challenge: Bytes32,
+) -> Result<CoinState, DriverError> {
+    peer.request_coin_state(vec![coin_id], None, genesis_challenge, false)
+        .await?
+        .map_err(|rejection| DriverError::RejectCoinState(rejection.reason))?
+        .coin_states
+        .into_iter()
+        .find(|coin_state| coin_state.coin.coin_id() == coin_id)
+        .ok_or(DriverError::MissingCoin)
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_protocol::SpendBundle;
+    use chia_puzzles::{nft::NftMetadata, standard::StandardArgs, Proof};
+    use chia_sdk_test::{sign_transaction, test_secret_key, PeerSimulator};
+    use chia_sdk_types::Conditions;
+
+    use crate::{Launcher, NftMint, SpendContext, StandardLayer};
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_trace_nft() -> anyhow::Result<()> {
+        let sim = PeerSimulator::new().await?;
+        let peer = sim.connect().await?;
+        let ctx = &mut SpendContext::new();
+
+        let sk = test_secret_key()?;
+        let p2 = StandardLayer::new(sk.public_key());
+        let puzzle_hash = StandardArgs::curry_tree_hash(sk.public_key()).into();
+        let coin = sim.mint_coin(puzzle_hash, 1).await;
+
+        let (mint_nft, nft) = Launcher::new(coin.coin_id(), 1).mint_nft(
+            ctx,
+            NftMint::new(NftMetadata::default(), puzzle_hash, 300, None),
+        )?;
+        p2.spend(ctx, coin, mint_nft)?;
+
+        let nft = nft.transfer(ctx, &p2, puzzle_hash, Conditions::new())?;
+
+        let coin_spends = ctx.take();
+        let signature = sign_transaction(&coin_spends, &[sk.clone()])?;
+        let ack = peer
+            .send_transaction(SpendBundle::new(coin_spends, signature))
+            .await?;
+        assert_eq!(ack.error, None);
+
+        let launcher_id = nft.info.launcher_id;
+        let mut tracer =
+            SingletonTracer::<Nft<NftMetadata>>::new(sim.config().constants.genesis_challenge);
+
+        // The history starts with the eve, which is parsed from its own spend.
+        let trace = tracer.trace(&mut ctx.allocator, &peer, launcher_id).await?;
+        assert_eq!(trace.history.len(), 3);
+        assert_eq!(trace.history[0].coin.parent_coin_info, launcher_id);
+        assert!(matches!(trace.history[0].proof, Proof::Eve(_)));
+        assert_eq!(trace.latest(), Some(&nft));
+        assert_eq!(trace.tip.coin, nft.coin);
+        assert!(!trace.is_melted());
+
+        // Tracing again only fetches the generations that were created since.
+        let bob_puzzle_hash = Bytes32::new([1; 32]);
+        let _nft = nft.transfer(ctx, &p2, bob_puzzle_hash, Conditions::new())?;
+
+        let coin_spends = ctx.take();
+        let signature = sign_transaction(&coin_spends, &[sk])?;
+        let ack = peer
+            .send_transaction(SpendBundle::new(coin_spends, signature))
+            .await?;
+        assert_eq!(ack.error, None);
+
+        let trace = tracer.trace(&mut ctx.allocator, &peer, launcher_id).await?;
+        assert_eq!(trace.history.len(), 4);
+        assert_eq!(
+            trace.latest().map(|nft| nft.info.p2_puzzle_hash),
+            Some(bob_puzzle_hash)
+        );
+
+        // The singleton can't be traced as a different primitive.
+        let mut tracer = SingletonTracer::<Did<()>>::new(sim.config().constants.genesis_challenge);
+        assert!(matches!(
+            tracer.trace(&mut ctx.allocator, &peer, launcher_id).await,
+            Err(DriverError::UnexpectedSingleton(id)) if id == launcher_id
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_trace_did() -> anyhow::Result<()> {
+        let sim = PeerSimulator::new().await?;
+        let peer = sim.connect().await?;
+        let ctx = &mut SpendContext::new();
+
+        let sk = test_secret_key()?;
+        let p2 = StandardLayer::new(sk.public_key());
+        let puzzle_hash = StandardArgs::curry_tree_hash(sk.public_key()).into();
+        let coin = sim.mint_coin(puzzle_hash, 1).await;
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+
+        let did = did.update(ctx, &p2, Conditions::new())?;
+
+        let coin_spends = ctx.take();
+        let signature = sign_transaction(&coin_spends, &[sk])?;
+        let ack = peer
+            .send_transaction(SpendBundle::new(coin_spends, signature))
+            .await?;
+        assert_eq!(ack.error, None);
+
+        let launcher_id = did.info.launcher_id;
+        let mut tracer = SingletonTracer::<Did<()>>::new(sim.config().constants.genesis_challenge);
+
+        let trace = tracer.trace(&mut ctx.allocator, &peer, launcher_id).await?;
+        assert_eq!(trace.history[0].coin.parent_coin_info, launcher_id);
+        assert_eq!(trace.history[0].info.p2_puzzle_hash, puzzle_hash);
+        assert_eq!(trace.latest(), Some(&did));
+
+        Ok(())
+    }
+}
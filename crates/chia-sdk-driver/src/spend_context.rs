@@ -3,9 +3,9 @@ use std::collections::HashMap;
 use chia_protocol::{Coin, CoinSpend, Program};
 use chia_puzzles::{
     cat::{
-        CAT_PUZZLE, CAT_PUZZLE_HASH, EVERYTHING_WITH_SIGNATURE_TAIL_PUZZLE,
-        EVERYTHING_WITH_SIGNATURE_TAIL_PUZZLE_HASH, GENESIS_BY_COIN_ID_TAIL_PUZZLE,
-        GENESIS_BY_COIN_ID_TAIL_PUZZLE_HASH,
+        CAT_PUZZLE, CAT_PUZZLE_HASH, CAT_PUZZLE_HASH_V1, CAT_PUZZLE_V1,
+        EVERYTHING_WITH_SIGNATURE_TAIL_PUZZLE, EVERYTHING_WITH_SIGNATURE_TAIL_PUZZLE_HASH,
+        GENESIS_BY_COIN_ID_TAIL_PUZZLE, GENESIS_BY_COIN_ID_TAIL_PUZZLE_HASH,
     },
     did::{DID_INNER_PUZZLE, DID_INNER_PUZZLE_HASH},
     nft::{
@@ -14,7 +14,10 @@ use chia_puzzles::{
         NFT_OWNERSHIP_LAYER_PUZZLE_HASH, NFT_ROYALTY_TRANSFER_PUZZLE,
         NFT_ROYALTY_TRANSFER_PUZZLE_HASH, NFT_STATE_LAYER_PUZZLE, NFT_STATE_LAYER_PUZZLE_HASH,
     },
-    offer::{SETTLEMENT_PAYMENTS_PUZZLE, SETTLEMENT_PAYMENTS_PUZZLE_HASH},
+    offer::{
+        SETTLEMENT_PAYMENTS_PUZZLE, SETTLEMENT_PAYMENTS_PUZZLE_HASH,
+        SETTLEMENT_PAYMENTS_PUZZLE_HASH_V1, SETTLEMENT_PAYMENTS_PUZZLE_V1,
+    },
     singleton::{
         SINGLETON_LAUNCHER_PUZZLE, SINGLETON_LAUNCHER_PUZZLE_HASH, SINGLETON_TOP_LAYER_PUZZLE,
         SINGLETON_TOP_LAYER_PUZZLE_HASH,
@@ -27,9 +30,16 @@ use clvm_utils::{tree_hash, TreeHash};
 use clvmr::{serde::node_from_bytes, Allocator, NodePtr};
 
 use crate::{
-    DriverError, Spend, P2_DELEGATED_CONDITIONS_PUZZLE, P2_DELEGATED_CONDITIONS_PUZZLE_HASH,
+    DriverError, Spend, DELEGATED_TAIL_PUZZLE, DELEGATED_TAIL_PUZZLE_HASH,
+    DID_ALLOWLIST_TRANSFER_PUZZLE, DID_ALLOWLIST_TRANSFER_PUZZLE_HASH,
+    GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE, GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE_HASH,
+    KEY_VALUE_METADATA_UPDATER_PUZZLE, KEY_VALUE_METADATA_UPDATER_PUZZLE_HASH,
+    MELTABLE_GENESIS_BY_COIN_ID_TAIL_PUZZLE, MELTABLE_GENESIS_BY_COIN_ID_TAIL_PUZZLE_HASH,
+    P2_DELEGATED_CONDITIONS_PUZZLE, P2_DELEGATED_CONDITIONS_PUZZLE_HASH,
     P2_DELEGATED_SINGLETON_PUZZLE, P2_DELEGATED_SINGLETON_PUZZLE_HASH, P2_ONE_OF_MANY_PUZZLE,
     P2_ONE_OF_MANY_PUZZLE_HASH, P2_SINGLETON_PUZZLE, P2_SINGLETON_PUZZLE_HASH,
+    ROYALTY_SPLIT_TRANSFER_PUZZLE, ROYALTY_SPLIT_TRANSFER_PUZZLE_HASH, SOULBOUND_TRANSFER_PUZZLE,
+    SOULBOUND_TRANSFER_PUZZLE_HASH,
 };
 
 /// A wrapper around [`Allocator`] that caches puzzles and keeps track of a list of [`CoinSpend`].
@@ -113,6 +123,11 @@ impl SpendContext {
         self.puzzle(CAT_PUZZLE_HASH, &CAT_PUZZLE)
     }
 
+    /// Allocate the legacy CAT1 puzzle and return its pointer.
+    pub fn cat_puzzle_v1(&mut self) -> Result<NodePtr, DriverError> {
+        self.puzzle(CAT_PUZZLE_HASH_V1, &CAT_PUZZLE_V1)
+    }
+
     /// Allocate the DID inner puzzle and return its pointer.
     pub fn did_inner_puzzle(&mut self) -> Result<NodePtr, DriverError> {
         self.puzzle(DID_INNER_PUZZLE_HASH, &DID_INNER_PUZZLE)
@@ -134,6 +149,27 @@ impl SpendContext {
         )
     }
 
+    /// Allocate the NFT royalty split transfer puzzle and return its pointer.
+    pub fn royalty_split_transfer_puzzle(&mut self) -> Result<NodePtr, DriverError> {
+        self.puzzle(
+            ROYALTY_SPLIT_TRANSFER_PUZZLE_HASH,
+            &ROYALTY_SPLIT_TRANSFER_PUZZLE,
+        )
+    }
+
+    /// Allocate the NFT DID allowlist transfer puzzle and return its pointer.
+    pub fn did_allowlist_transfer_puzzle(&mut self) -> Result<NodePtr, DriverError> {
+        self.puzzle(
+            DID_ALLOWLIST_TRANSFER_PUZZLE_HASH,
+            &DID_ALLOWLIST_TRANSFER_PUZZLE,
+        )
+    }
+
+    /// Allocate the NFT soulbound transfer puzzle and return its pointer.
+    pub fn soulbound_transfer_puzzle(&mut self) -> Result<NodePtr, DriverError> {
+        self.puzzle(SOULBOUND_TRANSFER_PUZZLE_HASH, &SOULBOUND_TRANSFER_PUZZLE)
+    }
+
     /// Allocate the NFT metadata updater puzzle and return its pointer.
     pub fn nft_metadata_updater(&mut self) -> Result<NodePtr, DriverError> {
         self.puzzle(
@@ -142,6 +178,14 @@ impl SpendContext {
         )
     }
 
+    /// Allocate the key-value NFT metadata updater puzzle and return its pointer.
+    pub fn key_value_metadata_updater(&mut self) -> Result<NodePtr, DriverError> {
+        self.puzzle(
+            KEY_VALUE_METADATA_UPDATER_PUZZLE_HASH,
+            &KEY_VALUE_METADATA_UPDATER_PUZZLE,
+        )
+    }
+
     /// Allocate the NFT ownership layer puzzle and return its pointer.
     pub fn nft_ownership_layer(&mut self) -> Result<NodePtr, DriverError> {
         self.puzzle(NFT_OWNERSHIP_LAYER_PUZZLE_HASH, &NFT_OWNERSHIP_LAYER_PUZZLE)
@@ -178,11 +222,40 @@ impl SpendContext {
         )
     }
 
+    /// Allocate the delegated TAIL puzzle and return its pointer.
+    pub fn delegated_tail_puzzle(&mut self) -> Result<NodePtr, DriverError> {
+        self.puzzle(DELEGATED_TAIL_PUZZLE_HASH, &DELEGATED_TAIL_PUZZLE)
+    }
+
+    /// Allocate the genesis by puzzle hash TAIL puzzle and return its pointer.
+    pub fn genesis_by_puzzle_hash_tail_puzzle(&mut self) -> Result<NodePtr, DriverError> {
+        self.puzzle(
+            GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE_HASH,
+            &GENESIS_BY_PUZZLE_HASH_TAIL_PUZZLE,
+        )
+    }
+
+    /// Allocate the meltable single-issuance TAIL puzzle and return its pointer.
+    pub fn meltable_genesis_by_coin_id_tail_puzzle(&mut self) -> Result<NodePtr, DriverError> {
+        self.puzzle(
+            MELTABLE_GENESIS_BY_COIN_ID_TAIL_PUZZLE_HASH,
+            &MELTABLE_GENESIS_BY_COIN_ID_TAIL_PUZZLE,
+        )
+    }
+
     /// Allocate the settlement payments puzzle and return its pointer.
     pub fn settlement_payments_puzzle(&mut self) -> Result<NodePtr, DriverError> {
         self.puzzle(SETTLEMENT_PAYMENTS_PUZZLE_HASH, &SETTLEMENT_PAYMENTS_PUZZLE)
     }
 
+    /// Allocate the legacy settlement payments puzzle, used alongside CAT1, and return its pointer.
+    pub fn settlement_payments_puzzle_v1(&mut self) -> Result<NodePtr, DriverError> {
+        self.puzzle(
+            SETTLEMENT_PAYMENTS_PUZZLE_HASH_V1,
+            &SETTLEMENT_PAYMENTS_PUZZLE_V1,
+        )
+    }
+
     /// Allocate the p2 delegated conditions puzzle and return its pointer.
     pub fn p2_delegated_conditions_puzzle(&mut self) -> Result<NodePtr, DriverError> {
         self.puzzle(
@@ -209,6 +282,12 @@ impl SpendContext {
         )
     }
 
+    /// Allocate the p2 parent puzzle, used by datastore mirrors, and return its pointer.
+    #[cfg(feature = "chip-0035")]
+    pub fn p2_parent_puzzle(&mut self) -> Result<NodePtr, DriverError> {
+        self.puzzle(crate::P2_PARENT_PUZZLE_HASH, &crate::P2_PARENT_PUZZLE)
+    }
+
     /// Preload a puzzle into the cache.
     pub fn preload(&mut self, puzzle_hash: TreeHash, ptr: NodePtr) {
         self.puzzles.insert(puzzle_hash, ptr);
@@ -0,0 +1,397 @@
+use std::collections::{HashMap, HashSet};
+
+use chia_protocol::{Bytes, Bytes32, Coin, CoinSpend, SpendBundle};
+use chia_sdk_types::{run_puzzle, Condition};
+use clvm_traits::{FromClvm, ToClvm};
+use clvmr::Allocator;
+
+use crate::{AssetRegistry, DecodedAsset, DriverError, SpendContext};
+
+/// The asset that a coin in a [`SpendSummary`] is accounted under.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SummaryAsset {
+    Xch,
+    /// A CAT2 with the given asset id.
+    Cat(Bytes32),
+    /// A legacy CAT1 with the given asset id.
+    Cat1(Bytes32),
+    /// An NFT with the given launcher id.
+    Nft(Bytes32),
+    /// A DID with the given launcher id.
+    Did(Bytes32),
+    /// A data store with the given launcher id.
+    DataStore(Bytes32),
+    /// A coin with a puzzle registered under the given name.
+    Custom(&'static str),
+    /// A coin whose puzzle isn't known, so it can't be attributed to an asset.
+    Unknown,
+}
+
+/// The coins of a single asset that are spent and created by a transaction.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct AssetSummary {
+    /// The coins that are spent, excluding ones created in the same transaction.
+    pub inputs: Vec<Coin>,
+    /// The coins that are created, excluding ones spent in the same transaction.
+    pub outputs: Vec<Coin>,
+    /// The change in balance of each p2 puzzle hash, which is negative if it's being spent.
+    pub net_changes: HashMap<Bytes32, i128>,
+}
+
+impl AssetSummary {
+    pub fn input_amount(&self) -> u128 {
+        self.inputs.iter().map(|coin| u128::from(coin.amount)).sum()
+    }
+
+    pub fn output_amount(&self) -> u128 {
+        self.outputs
+            .iter()
+            .map(|coin| u128::from(coin.amount))
+            .sum()
+    }
+
+    /// The net change of the asset, which is negative if some of it is burned or melted.
+    #[allow(clippy::cast_possible_wrap)]
+    pub fn net_change(&self) -> i128 {
+        self.output_amount() as i128 - self.input_amount() as i128
+    }
+}
+
+/// The hint and memos of a coin created by a transaction.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CreatedCoinMemos {
+    pub coin: Coin,
+    /// The first memo, if it's a valid puzzle hash. Wallets use this to find coins they own.
+    pub hint: Option<Bytes32>,
+    pub memos: Vec<Bytes>,
+}
+
+/// A summary of what a transaction does, which can be shown to the user before it's signed.
+///
+/// Each coin spend is run and its coins are decoded with an [`AssetRegistry`]. Coins which
+/// are created and spent in the same transaction (such as singleton launchers) cancel out,
+/// so only the outermost inputs and the final outputs are included.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct SpendSummary {
+    pub assets: HashMap<SummaryAsset, AssetSummary>,
+    /// The sum of the `RESERVE_FEE` conditions.
+    pub reserved_fee: u64,
+    /// The total amount of mojos that are spent but not created, which is paid to the farmer.
+    /// This is at least the reserved fee, and includes any implicit fee.
+    pub fee: u64,
+    /// The hints and memos of each coin created, including ones spent in the same transaction.
+    pub memos: Vec<CreatedCoinMemos>,
+}
+
+impl SpendSummary {
+    pub fn from_spend_bundle(
+        allocator: &mut Allocator,
+        registry: &AssetRegistry,
+        spend_bundle: &SpendBundle,
+    ) -> Result<Self, DriverError> {
+        Self::from_coin_spends(allocator, registry, &spend_bundle.coin_spends)
+    }
+
+    /// Summarizes the coin spends that have been collected by the [`SpendContext`] so far, without removing them.
+    pub fn from_spend_context(
+        ctx: &mut SpendContext,
+        registry: &AssetRegistry,
+    ) -> Result<Self, DriverError> {
+        let coin_spends: Vec<CoinSpend> = ctx.iter().cloned().collect();
+        Self::from_coin_spends(&mut ctx.allocator, registry, &coin_spends)
+    }
+
+    pub fn from_coin_spends(
+        allocator: &mut Allocator,
+        registry: &AssetRegistry,
+        coin_spends: &[CoinSpend],
+    ) -> Result<Self, DriverError> {
+        let spent_coin_ids: HashSet<Bytes32> = coin_spends
+            .iter()
+            .map(|coin_spend| coin_spend.coin.coin_id())
+            .collect();
+
+        let mut summary = Self::default();
+        let mut created_coin_ids = HashSet::new();
+        let mut total_input = 0;
+        let mut total_output = 0;
+
+        for coin_spend in coin_spends {
+            let puzzle = coin_spend.puzzle_reveal.to_clvm(allocator)?;
+            let solution = coin_spend.solution.to_clvm(allocator)?;
+            let output = run_puzzle(allocator, puzzle, solution)?;
+            let conditions = Vec::<Condition>::from_clvm(allocator, output)?;
+
+            let mut outputs = Vec::new();
+
+            for condition in conditions {
+                match condition {
+                    Condition::ReserveFee(reserve_fee) => {
+                        summary.reserved_fee += reserve_fee.amount;
+                    }
+                    Condition::CreateCoin(create_coin) => {
+                        let coin = Coin::new(
+                            coin_spend.coin.coin_id(),
+                            create_coin.puzzle_hash,
+                            create_coin.amount,
+                        );
+
+                        created_coin_ids.insert(coin.coin_id());
+
+                        summary.memos.push(CreatedCoinMemos {
+                            coin,
+                            hint: create_coin
+                                .memos
+                                .first()
+                                .and_then(|memo| memo.clone().try_into().ok()),
+                            memos: create_coin.memos,
+                        });
+
+                        if !spent_coin_ids.contains(&coin.coin_id()) {
+                            outputs.push(coin);
+                        }
+                    }
+                    _ => {}
+                }
+            }
+
+            if outputs.is_empty() {
+                continue;
+            }
+
+            for decoded in registry.decode_children(allocator, coin_spend, &outputs)? {
+                total_output += u128::from(decoded.coin().amount);
+                summary.add_output(&decoded);
+            }
+        }
+
+        for coin_spend in coin_spends {
+            if created_coin_ids.contains(&coin_spend.coin.coin_id()) {
+                continue;
+            }
+
+            total_input += u128::from(coin_spend.coin.amount);
+
+            let decoded = registry.decode_spend(allocator, coin_spend)?;
+            summary.add_input(&decoded);
+        }
+
+        if total_output > total_input {
+            return Err(DriverError::OutputsExceedInputs(total_output - total_input));
+        }
+
+        summary.fee = (total_input - total_output).try_into()?;
+
+        Ok(summary)
+    }
+
+    /// Returns the summary of the given asset, if any of its coins are spent or created.
+    pub fn asset(&self, asset: SummaryAsset) -> Option<&AssetSummary> {
+        self.assets.get(&asset)
+    }
+
+    fn add_input(&mut self, decoded: &DecodedAsset) {
+        let (asset, p2_puzzle_hash) = asset_key(decoded);
+        let coin = decoded.coin();
+        let summary = self.assets.entry(asset).or_default();
+        *summary.net_changes.entry(p2_puzzle_hash).or_default() -= i128::from(coin.amount);
+        summary.inputs.push(coin);
+    }
+
+    fn add_output(&mut self, decoded: &DecodedAsset) {
+        let (asset, p2_puzzle_hash) = asset_key(decoded);
+        let coin = decoded.coin();
+        let summary = self.assets.entry(asset).or_default();
+        *summary.net_changes.entry(p2_puzzle_hash).or_default() += i128::from(coin.amount);
+        summary.outputs.push(coin);
+    }
+}
+
+fn asset_key(decoded: &DecodedAsset) -> (SummaryAsset, Bytes32) {
+    match decoded {
+        DecodedAsset::Xch(coin) => (SummaryAsset::Xch, coin.puzzle_hash),
+        DecodedAsset::Cat(cat) => (SummaryAsset::Cat(cat.asset_id), cat.p2_puzzle_hash),
+        DecodedAsset::Cat1(cat) => (SummaryAsset::Cat1(cat.asset_id), cat.p2_puzzle_hash),
+        DecodedAsset::Nft(nft) => (
+            SummaryAsset::Nft(nft.info.launcher_id),
+            nft.info.p2_puzzle_hash,
+        ),
+        DecodedAsset::Did(did) => (
+            SummaryAsset::Did(did.info.launcher_id),
+            did.info.p2_puzzle_hash,
+        ),
+        #[cfg(feature = "chip-0035")]
+        DecodedAsset::DataStore(datastore) => (
+            SummaryAsset::DataStore(datastore.info.launcher_id),
+            datastore.info.owner_puzzle_hash,
+        ),
+        DecodedAsset::Custom { name, coin, .. } => (SummaryAsset::Custom(name), coin.puzzle_hash),
+        DecodedAsset::Unknown { coin, .. } => (SummaryAsset::Unknown, coin.puzzle_hash),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_puzzles::nft::NftMetadata;
+    use chia_sdk_test::Simulator;
+    use chia_sdk_types::Conditions;
+
+    use crate::{
+        Cat, CatSpend, DidOwner, IntermediateLauncher, Launcher, NftMint, SpendWithConditions,
+        StandardLayer,
+    };
+
+    use super::*;
+
+    #[test]
+    fn test_xch_summary() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let p2 = StandardLayer::new(pk);
+
+        let recipient = Bytes32::new([1; 32]);
+
+        p2.spend(
+            ctx,
+            coin,
+            Conditions::new()
+                .create_coin(recipient, 850, vec![recipient.into()])
+                .create_coin(puzzle_hash, 100, Vec::new())
+                .reserve_fee(25),
+        )?;
+
+        let summary = SpendSummary::from_spend_context(ctx, &AssetRegistry::new())?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        assert_eq!(summary.reserved_fee, 25);
+        assert_eq!(summary.fee, 50);
+
+        let xch = summary.asset(SummaryAsset::Xch).expect("missing XCH");
+        assert_eq!(xch.inputs, [coin]);
+        assert_eq!(xch.input_amount(), 1000);
+        assert_eq!(xch.output_amount(), 950);
+        assert_eq!(xch.net_change(), -50);
+        assert_eq!(xch.net_changes[&puzzle_hash], -900);
+        assert_eq!(xch.net_changes[&recipient], 850);
+
+        assert_eq!(summary.memos.len(), 2);
+        assert_eq!(summary.memos[0].hint, Some(recipient));
+        assert_eq!(summary.memos[1].hint, None);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_outputs_exceed_inputs() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (_sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+
+        StandardLayer::new(pk).spend(
+            ctx,
+            coin,
+            Conditions::new().create_coin(puzzle_hash, 1500, Vec::new()),
+        )?;
+
+        assert!(matches!(
+            SpendSummary::from_spend_context(ctx, &AssetRegistry::new()),
+            Err(DriverError::OutputsExceedInputs(500))
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_cat_summary() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (issue_cat, eve) = Cat::single_issuance_eve(
+            ctx,
+            coin.coin_id(),
+            1000,
+            Conditions::new().create_coin(puzzle_hash, 1000, vec![puzzle_hash.into()]),
+        )?;
+        p2.spend(ctx, coin, issue_cat)?;
+
+        let cat = eve.wrapped_child(puzzle_hash, 1000);
+        let recipient = Bytes32::new([1; 32]);
+
+        let inner_spend = p2.spend_with_conditions(
+            ctx,
+            Conditions::new()
+                .create_coin(recipient, 400, vec![recipient.into()])
+                .create_coin(puzzle_hash, 600, vec![puzzle_hash.into()]),
+        )?;
+        Cat::spend_all(ctx, &[CatSpend::new(cat, inner_spend)])?;
+
+        let summary = SpendSummary::from_spend_context(ctx, &AssetRegistry::new())?;
+        sim.spend_coins(ctx.take(), &[sk])?;
+
+        assert_eq!(summary.fee, 0);
+
+        // The issued XCH is converted into the CAT, and the intermediate CAT coins cancel out.
+        let xch = summary.asset(SummaryAsset::Xch).expect("missing XCH");
+        assert_eq!(xch.net_change(), -1000);
+        assert!(xch.outputs.is_empty());
+
+        let cat = summary
+            .asset(SummaryAsset::Cat(eve.asset_id))
+            .expect("missing CAT");
+        assert!(cat.inputs.is_empty());
+        assert_eq!(cat.output_amount(), 1000);
+        assert_eq!(cat.net_changes[&puzzle_hash], 600);
+        assert_eq!(cat.net_changes[&recipient], 400);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_nft_mint_summary() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        // The intermediate launcher has no value, so the NFT's mojo is paid for by this coin as well.
+        let (sk2, pk2, _puzzle_hash, fee_coin) = sim.new_p2(2)?;
+        let mint = NftMint::new(
+            NftMetadata::default(),
+            puzzle_hash,
+            300,
+            Some(DidOwner::from_did_info(&did.info)),
+        );
+        let (mint_nft, nft) = IntermediateLauncher::new(did.coin.coin_id(), 0, 1)
+            .create(ctx)?
+            .mint_nft(ctx, mint)?;
+        let new_did = did.update(ctx, &p2, mint_nft)?;
+        StandardLayer::new(pk2).spend(ctx, fee_coin, Conditions::new().reserve_fee(1))?;
+
+        let summary = SpendSummary::from_spend_context(ctx, &AssetRegistry::new())?;
+        sim.spend_coins(ctx.take(), &[sk, sk2])?;
+
+        assert_eq!(summary.reserved_fee, 1);
+        assert_eq!(summary.fee, 1);
+
+        let did = summary
+            .asset(SummaryAsset::Did(did.info.launcher_id))
+            .expect("missing DID");
+        assert_eq!(did.outputs, [new_did.coin]);
+        assert_eq!(did.net_change(), 0);
+
+        let nft = summary
+            .asset(SummaryAsset::Nft(nft.info.launcher_id))
+            .expect("missing NFT");
+        assert!(nft.inputs.is_empty());
+        assert_eq!(nft.net_changes[&puzzle_hash], 1);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,980 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chia_bls::{PublicKey, Signature};
+use chia_protocol::{Bytes, Bytes32, Coin, SpendBundle};
+use chia_puzzles::{nft::NftMetadata, standard::StandardArgs};
+use chia_sdk_signer::{AggSigConstants, RequiredSignature};
+use chia_sdk_types::{Conditions, TradePrice};
+use chia_sdk_utils::{select_coins, CoinSelectionError};
+
+use crate::{
+    Cat, CatSpend, Did, DidOwner, DriverError, HashedPtr, IntermediateLauncher, Nft, NftMint,
+    SpendContext, SpendWithConditions, StandardLayer,
+};
+
+/// A single high-level action that a [`TransactionBuilder`] resolves into coin spends.
+#[derive(Debug, Clone)]
+pub enum Action {
+    /// Sends XCH to a puzzle hash, with the memos used as is.
+    SendXch {
+        puzzle_hash: Bytes32,
+        amount: u64,
+        memos: Vec<Bytes>,
+    },
+    /// Sends a CAT to a p2 puzzle hash, with the memos used as is.
+    /// The first memo should be the hint, so that the recipient's wallet can find the coin.
+    SendCat {
+        asset_id: Bytes32,
+        puzzle_hash: Bytes32,
+        amount: u64,
+        memos: Vec<Bytes>,
+    },
+    /// Transfers an NFT to a new p2 puzzle hash, without changing its owner DID.
+    TransferNft {
+        launcher_id: Bytes32,
+        puzzle_hash: Bytes32,
+    },
+    /// Transfers an NFT to the settlement payments puzzle, so that it can be traded in an offer.
+    /// The trade prices are used to calculate the royalties that must be paid when it's unlocked.
+    LockNft {
+        launcher_id: Bytes32,
+        trade_prices: Vec<TradePrice>,
+    },
+    /// Mints a new NFT. If a DID is given, it will be spent and set as the owner of the NFT.
+    MintNft {
+        mint: Box<NftMint<NftMetadata>>,
+        did_id: Option<Bytes32>,
+    },
+    /// Recreates a DID, optionally with new metadata.
+    UpdateDid {
+        launcher_id: Bytes32,
+        metadata: Option<HashedPtr>,
+    },
+    /// Issues a new single issuance CAT, which is paid for with XCH.
+    IssueCat { puzzle_hash: Bytes32, amount: u64 },
+}
+
+/// The coins that a [`TransactionBuilder`] can select from to fund its actions.
+///
+/// The metadata of NFTs and DIDs must be allocated in the same [`SpendContext`] that is used to build the transaction.
+#[derive(Debug, Default, Clone)]
+pub struct SpendableCoins {
+    pub xch: Vec<Coin>,
+    pub cats: HashMap<Bytes32, Vec<Cat>>,
+    pub nfts: HashMap<Bytes32, Nft<HashedPtr>>,
+    pub dids: HashMap<Bytes32, Did<HashedPtr>>,
+}
+
+impl SpendableCoins {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_xch(&mut self, coin: Coin) {
+        self.xch.push(coin);
+    }
+
+    pub fn add_cat(&mut self, cat: Cat) {
+        self.cats.entry(cat.asset_id).or_default().push(cat);
+    }
+
+    pub fn add_nft(&mut self, nft: Nft<HashedPtr>) {
+        self.nfts.insert(nft.info.launcher_id, nft);
+    }
+
+    pub fn add_did(&mut self, did: Did<HashedPtr>) {
+        self.dids.insert(did.info.launcher_id, did);
+    }
+
+    /// The ids of every coin, which can be used to calculate an offer nonce.
+    pub fn coin_ids(&self) -> Vec<Bytes32> {
+        self.xch
+            .iter()
+            .copied()
+            .chain(self.cats.values().flatten().map(|cat| cat.coin))
+            .chain(self.nfts.values().map(|nft| nft.coin))
+            .chain(self.dids.values().map(|did| did.coin))
+            .map(|coin| coin.coin_id())
+            .collect()
+    }
+}
+
+/// The result of a [`TransactionBuilder`], which still needs to be signed.
+#[derive(Debug, Clone)]
+pub struct UnsignedTransaction {
+    /// The spend bundle, with an empty aggregate signature.
+    pub spend_bundle: SpendBundle,
+    /// The signatures that are required for the spend bundle to be valid.
+    pub required_signatures: Vec<RequiredSignature>,
+    /// The NFTs that were minted, in the order of the actions.
+    pub minted_nfts: Vec<Nft<NftMetadata>>,
+    /// The CATs that were issued, in the order of the actions.
+    pub issued_cats: Vec<Cat>,
+    /// The XCH coins that were sent, in the order of the actions.
+    pub sent_coins: Vec<Coin>,
+    /// The CATs that were sent, in the order of the actions.
+    pub sent_cats: Vec<Cat>,
+    /// The NFTs that were locked in the settlement payments puzzle, in the order of the actions.
+    pub locked_nfts: Vec<Nft<HashedPtr>>,
+}
+
+/// Builds a transaction from a list of [`Action`], by selecting coins, creating change and paying the fee.
+///
+/// Every coin is spent with the standard puzzle, so the public key for each p2 puzzle hash must be added.
+/// All of the coin spends are linked together with `ASSERT_CONCURRENT_SPEND`, so that none of them
+/// can be removed from the spend bundle. The first coin asserts every other coin, and they assert it in turn.
+#[derive(Debug, Clone)]
+#[must_use]
+pub struct TransactionBuilder {
+    change_puzzle_hash: Bytes32,
+    keys: HashMap<Bytes32, PublicKey>,
+    actions: Vec<Action>,
+    conditions: Conditions,
+    fee: u64,
+}
+
+/// The amounts and coins that the actions of a [`TransactionBuilder`] require.
+#[derive(Debug, Default)]
+struct Requirements {
+    xch_amount: u128,
+    cat_amounts: BTreeMap<Bytes32, u128>,
+    nft_transfers: BTreeMap<Bytes32, Bytes32>,
+    nft_locks: BTreeMap<Bytes32, Vec<TradePrice>>,
+    did_updates: BTreeMap<Bytes32, Option<HashedPtr>>,
+    issuances: Vec<(Bytes32, u64)>,
+}
+
+impl Requirements {
+    fn validate(&self) -> Result<(), DriverError> {
+        if let Some(launcher_id) = self
+            .nft_transfers
+            .keys()
+            .find(|launcher_id| self.nft_locks.contains_key(launcher_id))
+        {
+            return Err(DriverError::ConflictingNftActions(*launcher_id));
+        }
+
+        Ok(())
+    }
+}
+
+impl TransactionBuilder {
+    pub fn new(change_puzzle_hash: Bytes32) -> Self {
+        Self {
+            change_puzzle_hash,
+            keys: HashMap::new(),
+            actions: Vec::new(),
+            conditions: Conditions::new(),
+            fee: 0,
+        }
+    }
+
+    /// Adds a public key, which can be used to spend coins with the corresponding standard puzzle hash.
+    pub fn key(mut self, public_key: PublicKey) -> Self {
+        let puzzle_hash = StandardArgs::curry_tree_hash(public_key).into();
+        self.keys.insert(puzzle_hash, public_key);
+        self
+    }
+
+    pub fn keys(mut self, public_keys: impl IntoIterator<Item = PublicKey>) -> Self {
+        for public_key in public_keys {
+            self = self.key(public_key);
+        }
+        self
+    }
+
+    pub fn fee(mut self, fee: u64) -> Self {
+        self.fee = fee;
+        self
+    }
+
+    /// Adds extra conditions to the first coin spend, such as announcements that must be asserted.
+    pub fn conditions(mut self, conditions: Conditions) -> Self {
+        self.conditions = self.conditions.extend(conditions);
+        self
+    }
+
+    pub fn action(mut self, action: Action) -> Self {
+        self.actions.push(action);
+        self
+    }
+
+    pub fn send_xch(self, puzzle_hash: Bytes32, amount: u64) -> Self {
+        self.action(Action::SendXch {
+            puzzle_hash,
+            amount,
+            memos: Vec::new(),
+        })
+    }
+
+    /// Sends a CAT, hinted to the recipient's p2 puzzle hash.
+    pub fn send_cat(self, asset_id: Bytes32, puzzle_hash: Bytes32, amount: u64) -> Self {
+        self.action(Action::SendCat {
+            asset_id,
+            puzzle_hash,
+            amount,
+            memos: vec![puzzle_hash.into()],
+        })
+    }
+
+    pub fn transfer_nft(self, launcher_id: Bytes32, puzzle_hash: Bytes32) -> Self {
+        self.action(Action::TransferNft {
+            launcher_id,
+            puzzle_hash,
+        })
+    }
+
+    pub fn lock_nft(self, launcher_id: Bytes32, trade_prices: Vec<TradePrice>) -> Self {
+        self.action(Action::LockNft {
+            launcher_id,
+            trade_prices,
+        })
+    }
+
+    pub fn mint_nft(self, mint: NftMint<NftMetadata>, did_id: Option<Bytes32>) -> Self {
+        self.action(Action::MintNft {
+            mint: Box::new(mint),
+            did_id,
+        })
+    }
+
+    pub fn update_did(self, launcher_id: Bytes32, metadata: Option<HashedPtr>) -> Self {
+        self.action(Action::UpdateDid {
+            launcher_id,
+            metadata,
+        })
+    }
+
+    pub fn issue_cat(self, puzzle_hash: Bytes32, amount: u64) -> Self {
+        self.action(Action::IssueCat {
+            puzzle_hash,
+            amount,
+        })
+    }
+
+    /// Resolves the actions into coin spends, and takes every coin spend in the [`SpendContext`]
+    /// to create the spend bundle. This includes any coin spends that were added before.
+    pub fn build(
+        self,
+        ctx: &mut SpendContext,
+        coins: &SpendableCoins,
+        constants: &AggSigConstants,
+    ) -> Result<UnsignedTransaction, DriverError> {
+        let selected = self.select(coins)?;
+        self.build_selected(ctx, &selected, constants)
+    }
+
+    /// Selects the coins that are needed to fund the actions, without spending them.
+    pub fn select(&self, coins: &SpendableCoins) -> Result<SpendableCoins, DriverError> {
+        let requirements = self.requirements();
+        requirements.validate()?;
+
+        let mut selected = SpendableCoins::new();
+
+        if requirements.xch_amount > 0 {
+            selected.xch = select_coins(coins.xch.clone(), requirements.xch_amount)?;
+        }
+
+        // Each issuance needs its own parent coin, so add the smallest remaining coins until there are enough.
+        if selected.xch.len() < requirements.issuances.len() {
+            let mut remaining: Vec<Coin> = coins
+                .xch
+                .iter()
+                .filter(|coin| !selected.xch.contains(coin))
+                .copied()
+                .collect();
+            remaining.sort_by_key(|coin| coin.amount);
+
+            let missing = requirements.issuances.len() - selected.xch.len();
+
+            if remaining.len() < missing {
+                return Err(DriverError::MissingIssuanceParent);
+            }
+
+            selected.xch.extend(remaining.into_iter().take(missing));
+        }
+
+        for (asset_id, amount) in &requirements.cat_amounts {
+            let cats = coins.cats.get(asset_id).ok_or(DriverError::MissingCoin)?;
+            let selected_coins = select_coins(cats.iter().map(|cat| cat.coin).collect(), *amount)?;
+
+            for coin in selected_coins {
+                let cat = cats
+                    .iter()
+                    .find(|cat| cat.coin == coin)
+                    .copied()
+                    .ok_or(DriverError::MissingCoin)?;
+
+                selected.add_cat(cat);
+            }
+        }
+
+        for launcher_id in requirements
+            .nft_transfers
+            .keys()
+            .chain(requirements.nft_locks.keys())
+        {
+            let nft = coins
+                .nfts
+                .get(launcher_id)
+                .ok_or(DriverError::MissingCoin)?;
+            selected.add_nft(*nft);
+        }
+
+        for launcher_id in requirements.did_updates.keys() {
+            let did = coins
+                .dids
+                .get(launcher_id)
+                .ok_or(DriverError::MissingCoin)?;
+            selected.add_did(*did);
+        }
+
+        Ok(selected)
+    }
+
+    /// Resolves the actions into coin spends like [`Self::build`], but spends all of the coins that were
+    /// previously chosen with [`Self::select`] rather than selecting them again.
+    pub fn build_selected(
+        self,
+        ctx: &mut SpendContext,
+        selected: &SpendableCoins,
+        constants: &AggSigConstants,
+    ) -> Result<UnsignedTransaction, DriverError> {
+        let requirements = self.requirements();
+        requirements.validate()?;
+
+        let Requirements {
+            xch_amount,
+            cat_amounts,
+            nft_transfers,
+            nft_locks,
+            did_updates,
+            issuances,
+        } = requirements;
+
+        let xch_coins = selected.xch.clone();
+        let xch_selected: u128 = xch_coins.iter().map(|coin| u128::from(coin.amount)).sum();
+
+        if xch_selected < xch_amount {
+            return Err(CoinSelectionError::InsufficientBalance(xch_selected).into());
+        }
+
+        // Each issuance needs its own parent coin, since the asset id is derived from it.
+        if issuances.len() > xch_coins.len() {
+            return Err(DriverError::MissingIssuanceParent);
+        }
+
+        let mut cat_coins = Vec::new();
+
+        for (asset_id, amount) in &cat_amounts {
+            let cats = selected
+                .cats
+                .get(asset_id)
+                .filter(|cats| !cats.is_empty())
+                .ok_or(DriverError::MissingCoin)?;
+            let cats_selected: u128 = cats.iter().map(|cat| u128::from(cat.coin.amount)).sum();
+
+            if cats_selected < *amount {
+                return Err(CoinSelectionError::InsufficientBalance(cats_selected).into());
+            }
+
+            cat_coins.push(cats.clone());
+        }
+
+        let find_nft = |launcher_id: &Bytes32| {
+            selected
+                .nfts
+                .get(launcher_id)
+                .copied()
+                .ok_or(DriverError::MissingCoin)
+        };
+
+        let nfts = nft_transfers
+            .iter()
+            .map(|(launcher_id, puzzle_hash)| Ok((find_nft(launcher_id)?, *puzzle_hash)))
+            .collect::<Result<Vec<_>, DriverError>>()?;
+
+        let locked = nft_locks
+            .into_iter()
+            .map(|(launcher_id, trade_prices)| Ok((find_nft(&launcher_id)?, trade_prices)))
+            .collect::<Result<Vec<_>, DriverError>>()?;
+
+        let dids = did_updates
+            .iter()
+            .map(|(launcher_id, metadata)| {
+                selected
+                    .dids
+                    .get(launcher_id)
+                    .map(|did| (*did, *metadata))
+                    .ok_or(DriverError::MissingCoin)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let Some(anchor) = xch_coins
+            .first()
+            .copied()
+            .or_else(|| cat_coins.first().map(|cats| cats[0].coin))
+            .or_else(|| nfts.first().map(|(nft, _)| nft.coin))
+            .or_else(|| locked.first().map(|(nft, _)| nft.coin))
+            .or_else(|| dids.first().map(|(did, _)| did.coin))
+        else {
+            return Err(DriverError::MissingCoin);
+        };
+
+        let linked_coin_ids: Vec<Bytes32> = xch_coins
+            .iter()
+            .copied()
+            .chain(cat_coins.iter().flatten().map(|cat| cat.coin))
+            .chain(nfts.iter().map(|(nft, _)| nft.coin))
+            .chain(locked.iter().map(|(nft, _)| nft.coin))
+            .chain(dids.iter().map(|(did, _)| did.coin))
+            .filter(|&coin| coin != anchor)
+            .map(|coin| coin.coin_id())
+            .collect();
+
+        let link = |coin: Coin| {
+            if coin == anchor {
+                linked_coin_ids
+                    .iter()
+                    .fold(self.conditions.clone(), |conditions, &coin_id| {
+                        conditions.assert_concurrent_spend(coin_id)
+                    })
+            } else {
+                Conditions::new().assert_concurrent_spend(anchor.coin_id())
+            }
+        };
+
+        // Collect the conditions for each of the DIDs that need to be spent.
+        let mut did_conditions: Vec<Conditions> =
+            dids.iter().map(|(did, _)| link(did.coin)).collect();
+        let mut primary_conditions = Conditions::new();
+        let mut minted_nfts = Vec::new();
+        let mut sent_coins = Vec::new();
+
+        let mint_total = self
+            .actions
+            .iter()
+            .filter(|action| matches!(action, Action::MintNft { did_id: None, .. }))
+            .count();
+        let mut mint_numbers = HashMap::<Option<Bytes32>, usize>::new();
+
+        for action in &self.actions {
+            match action {
+                Action::SendXch {
+                    puzzle_hash,
+                    amount,
+                    memos,
+                } => {
+                    primary_conditions =
+                        primary_conditions.create_coin(*puzzle_hash, *amount, memos.clone());
+                    sent_coins.push(Coin::new(anchor.coin_id(), *puzzle_hash, *amount));
+                }
+                Action::MintNft { mint, did_id } => {
+                    let mint_number = mint_numbers.entry(*did_id).or_default();
+
+                    let (mint, parent_coin_id, total) = if let Some(did_id) = did_id {
+                        let index = dids
+                            .iter()
+                            .position(|(did, _)| did.info.launcher_id == *did_id)
+                            .ok_or(DriverError::MissingCoin)?;
+                        let did = &dids[index].0;
+
+                        let total = self
+                            .actions
+                            .iter()
+                            .filter(|action| {
+                                matches!(action, Action::MintNft { did_id: Some(id), .. } if id == did_id)
+                            })
+                            .count();
+
+                        let mint = NftMint {
+                            owner: Some(DidOwner::from_did_info(&did.info)),
+                            ..mint.as_ref().clone()
+                        };
+
+                        (mint, did.coin.coin_id(), total)
+                    } else {
+                        (mint.as_ref().clone(), anchor.coin_id(), mint_total)
+                    };
+
+                    let (mint_nft, nft) =
+                        IntermediateLauncher::new(parent_coin_id, *mint_number, total)
+                            .create(ctx)?
+                            .mint_nft(ctx, mint)?;
+
+                    *mint_number += 1;
+                    minted_nfts.push(nft);
+
+                    if let Some(did_id) = did_id {
+                        let index = dids
+                            .iter()
+                            .position(|(did, _)| did.info.launcher_id == *did_id)
+                            .ok_or(DriverError::MissingCoin)?;
+                        did_conditions[index] =
+                            std::mem::take(&mut did_conditions[index]).extend(mint_nft);
+                    } else {
+                        primary_conditions = primary_conditions.extend(mint_nft);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        // Spend the XCH coins, with the primary coin creating the outputs and change.
+        let mut issued_cats = Vec::new();
+
+        if !xch_coins.is_empty() {
+            let change: u64 = (xch_selected - xch_amount).try_into()?;
+
+            if change > 0 {
+                primary_conditions = primary_conditions.create_coin(
+                    self.change_puzzle_hash,
+                    change,
+                    vec![self.change_puzzle_hash.into()],
+                );
+            }
+
+            if self.fee > 0 {
+                primary_conditions = primary_conditions.reserve_fee(self.fee);
+            }
+
+            for (index, coin) in xch_coins.iter().enumerate() {
+                let mut conditions = link(*coin);
+
+                if index == 0 {
+                    conditions = conditions.extend(std::mem::take(&mut primary_conditions));
+                }
+
+                if let Some((puzzle_hash, amount)) = issuances.get(index) {
+                    let (issue_cat, eve) = Cat::single_issuance_eve(
+                        ctx,
+                        coin.coin_id(),
+                        *amount,
+                        Conditions::new().create_coin(
+                            *puzzle_hash,
+                            *amount,
+                            vec![(*puzzle_hash).into()],
+                        ),
+                    )?;
+                    issued_cats.push(eve.wrapped_child(*puzzle_hash, *amount));
+                    conditions = conditions.extend(issue_cat);
+                }
+
+                self.p2(coin.puzzle_hash)?.spend(ctx, *coin, conditions)?;
+            }
+        }
+
+        // Spend each CAT in its own ring, with the first coin creating the outputs and change.
+        let mut ring_parents = HashMap::new();
+
+        for cats in cat_coins {
+            let asset_id = cats[0].asset_id;
+            let selected_amount: u128 = cats.iter().map(|cat| u128::from(cat.coin.amount)).sum();
+            let change: u64 = (selected_amount - cat_amounts[&asset_id]).try_into()?;
+
+            let mut outputs = Conditions::new();
+
+            for action in &self.actions {
+                if let Action::SendCat {
+                    asset_id: id,
+                    puzzle_hash,
+                    amount,
+                    memos,
+                } = action
+                {
+                    if *id == asset_id {
+                        outputs = outputs.create_coin(*puzzle_hash, *amount, memos.clone());
+                    }
+                }
+            }
+
+            if change > 0 {
+                outputs = outputs.create_coin(
+                    self.change_puzzle_hash,
+                    change,
+                    vec![self.change_puzzle_hash.into()],
+                );
+            }
+
+            ring_parents.insert(asset_id, cats[0]);
+
+            let mut cat_spends = Vec::new();
+
+            for (index, cat) in cats.into_iter().enumerate() {
+                let mut conditions = link(cat.coin);
+
+                if index == 0 {
+                    conditions = conditions.extend(std::mem::take(&mut outputs));
+                }
+
+                let inner_spend = self
+                    .p2(cat.p2_puzzle_hash)?
+                    .spend_with_conditions(ctx, conditions)?;
+
+                cat_spends.push(CatSpend::new(cat, inner_spend));
+            }
+
+            Cat::spend_all(ctx, &cat_spends)?;
+        }
+
+        let sent_cats = self
+            .actions
+            .iter()
+            .filter_map(|action| match action {
+                Action::SendCat {
+                    asset_id,
+                    puzzle_hash,
+                    amount,
+                    ..
+                } => Some(ring_parents[asset_id].wrapped_child(*puzzle_hash, *amount)),
+                _ => None,
+            })
+            .collect();
+
+        for (nft, puzzle_hash) in nfts {
+            let p2 = self.p2(nft.info.p2_puzzle_hash)?;
+            let conditions = link(nft.coin);
+            let _nft = nft.transfer(ctx, &p2, puzzle_hash, conditions)?;
+        }
+
+        let mut locked_nfts = Vec::new();
+
+        for action in &self.actions {
+            let Action::LockNft { launcher_id, .. } = action else {
+                continue;
+            };
+
+            let Some((nft, trade_prices)) = locked
+                .iter()
+                .find(|(nft, _)| nft.info.launcher_id == *launcher_id)
+            else {
+                continue;
+            };
+
+            let p2 = self.p2(nft.info.p2_puzzle_hash)?;
+            let conditions = link(nft.coin);
+            locked_nfts.push(nft.lock_settlement(ctx, &p2, trade_prices.clone(), conditions)?);
+        }
+
+        for ((did, metadata), conditions) in dids.into_iter().zip(did_conditions) {
+            let p2 = self.p2(did.info.p2_puzzle_hash)?;
+            let metadata = metadata.unwrap_or(did.info.metadata);
+            let _did = did.update_with_metadata(ctx, &p2, metadata, conditions)?;
+        }
+
+        let coin_spends = ctx.take();
+        let required_signatures =
+            RequiredSignature::from_coin_spends(&mut ctx.allocator, &coin_spends, constants)?;
+
+        Ok(UnsignedTransaction {
+            spend_bundle: SpendBundle::new(coin_spends, Signature::default()),
+            required_signatures,
+            minted_nfts,
+            issued_cats,
+            sent_coins,
+            sent_cats,
+            locked_nfts,
+        })
+    }
+
+    fn requirements(&self) -> Requirements {
+        let mut requirements = Requirements {
+            xch_amount: u128::from(self.fee),
+            ..Default::default()
+        };
+
+        for action in &self.actions {
+            match action {
+                Action::SendXch { amount, .. } => requirements.xch_amount += u128::from(*amount),
+                Action::SendCat {
+                    asset_id, amount, ..
+                } => {
+                    *requirements.cat_amounts.entry(*asset_id).or_default() += u128::from(*amount);
+                }
+                Action::TransferNft {
+                    launcher_id,
+                    puzzle_hash,
+                } => {
+                    requirements
+                        .nft_transfers
+                        .insert(*launcher_id, *puzzle_hash);
+                }
+                Action::LockNft {
+                    launcher_id,
+                    trade_prices,
+                } => {
+                    requirements
+                        .nft_locks
+                        .insert(*launcher_id, trade_prices.clone());
+                }
+                // The intermediate launcher has no value, so the launcher's mojo must be paid for.
+                Action::MintNft { did_id, .. } => {
+                    requirements.xch_amount += 1;
+
+                    if let Some(did_id) = did_id {
+                        requirements.did_updates.entry(*did_id).or_default();
+                    }
+                }
+                Action::UpdateDid {
+                    launcher_id,
+                    metadata,
+                } => {
+                    let entry = requirements.did_updates.entry(*launcher_id).or_default();
+                    if metadata.is_some() {
+                        *entry = *metadata;
+                    }
+                }
+                Action::IssueCat {
+                    puzzle_hash,
+                    amount,
+                } => {
+                    requirements.xch_amount += u128::from(*amount);
+                    requirements.issuances.push((*puzzle_hash, *amount));
+                }
+            }
+        }
+
+        requirements
+    }
+
+    /// The total amount of XCH that the actions and fee require.
+    pub fn required_xch(&self) -> u128 {
+        self.requirements().xch_amount
+    }
+
+    /// The puzzle hash that change is sent to, which offers also use to receive assets.
+    pub fn change_puzzle_hash(&self) -> Bytes32 {
+        self.change_puzzle_hash
+    }
+
+    fn p2(&self, puzzle_hash: Bytes32) -> Result<StandardLayer, DriverError> {
+        self.keys
+            .get(&puzzle_hash)
+            .copied()
+            .map(StandardLayer::new)
+            .ok_or(DriverError::MissingKey)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_consensus::consensus_constants::TEST_CONSTANTS;
+    use chia_sdk_test::Simulator;
+
+    use crate::Launcher;
+
+    use super::*;
+
+    #[test]
+    fn test_issue_and_send_cat() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let constants = AggSigConstants::from(&TEST_CONSTANTS);
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+
+        let recipient = Bytes32::new([1; 32]);
+
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(coin);
+
+        let tx = TransactionBuilder::new(puzzle_hash)
+            .key(pk)
+            .issue_cat(puzzle_hash, 500)
+            .send_xch(recipient, 100)
+            .fee(10)
+            .build(ctx, &coins, &constants)?;
+
+        assert_eq!(tx.required_signatures.len(), 1);
+        sim.spend_coins(tx.spend_bundle.coin_spends, &[sk.clone()])?;
+
+        let change = Coin::new(coin.coin_id(), puzzle_hash, 390);
+        assert!(sim.coin_state(change.coin_id()).is_some());
+        assert!(sim
+            .coin_state(Coin::new(coin.coin_id(), recipient, 100).coin_id())
+            .is_some());
+
+        let cat = tx.issued_cats[0];
+        assert!(sim.coin_state(cat.coin.coin_id()).is_some());
+
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(change);
+        coins.add_cat(cat);
+
+        let tx = TransactionBuilder::new(puzzle_hash)
+            .key(pk)
+            .send_cat(cat.asset_id, recipient, 200)
+            .fee(5)
+            .build(ctx, &coins, &constants)?;
+
+        sim.spend_coins(tx.spend_bundle.coin_spends, &[sk])?;
+
+        let sent = cat.wrapped_child(recipient, 200);
+        let cat_change = cat.wrapped_child(puzzle_hash, 300);
+        assert!(sim.coin_state(sent.coin.coin_id()).is_some());
+        assert!(sim.coin_state(cat_change.coin.coin_id()).is_some());
+        assert!(sim
+            .coin_state(Coin::new(change.coin_id(), puzzle_hash, 385).coin_id())
+            .is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_mint_and_transfer_nft() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let constants = AggSigConstants::from(&TEST_CONSTANTS);
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1)?;
+        let p2 = StandardLayer::new(pk);
+
+        let (create_did, did) = Launcher::new(coin.coin_id(), 1).create_simple_did(ctx, &p2)?;
+        p2.spend(ctx, coin, create_did)?;
+        sim.spend_coins(ctx.take(), &[sk.clone()])?;
+
+        let xch = sim.new_coin(puzzle_hash, 10);
+
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(xch);
+        coins.add_did(did.with_metadata(HashedPtr::NIL));
+
+        let tx = TransactionBuilder::new(puzzle_hash)
+            .key(pk)
+            .mint_nft(
+                NftMint::new(NftMetadata::default(), puzzle_hash, 300, None),
+                Some(did.info.launcher_id),
+            )
+            .fee(1)
+            .build(ctx, &coins, &constants)?;
+
+        sim.spend_coins(tx.spend_bundle.coin_spends, &[sk.clone()])?;
+
+        let nft = tx.minted_nfts[0].clone();
+        assert_eq!(nft.info.current_owner, Some(did.info.launcher_id));
+        assert!(sim.coin_state(nft.coin.coin_id()).is_some());
+
+        // The metadata must be allocated in the same context for the NFT to be spent.
+        let metadata = ctx.alloc(&nft.info.metadata)?;
+        let nft = nft.with_metadata(HashedPtr::from_ptr(&ctx.allocator, metadata));
+
+        let mut coins = SpendableCoins::new();
+        coins.add_nft(nft);
+
+        let recipient = Bytes32::new([1; 32]);
+
+        let result = TransactionBuilder::new(puzzle_hash)
+            .key(pk)
+            .transfer_nft(nft.info.launcher_id, recipient)
+            .build(ctx, &coins, &constants)?;
+
+        sim.spend_coins(result.spend_bundle.coin_spends, &[sk])?;
+
+        let child = nft.wrapped_child(recipient, nft.info.current_owner, nft.info.metadata);
+        assert!(sim.coin_state(child.coin.coin_id()).is_some());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_issue_cats_from_different_parents() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let constants = AggSigConstants::from(&TEST_CONSTANTS);
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(1000)?;
+        let small_coin = sim.new_coin(puzzle_hash, 1);
+
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(coin);
+        coins.add_xch(small_coin);
+
+        let builder = TransactionBuilder::new(puzzle_hash)
+            .key(pk)
+            .issue_cat(puzzle_hash, 100)
+            .issue_cat(puzzle_hash, 200);
+
+        // A single coin would cover the amount, but each issuance needs its own parent.
+        let selected = builder.select(&coins)?;
+        assert_eq!(selected.xch.len(), 2);
+
+        let tx = builder.build_selected(ctx, &selected, &constants)?;
+        sim.spend_coins(tx.spend_bundle.coin_spends, &[sk])?;
+
+        assert_eq!(tx.issued_cats.len(), 2);
+        assert_ne!(tx.issued_cats[0].asset_id, tx.issued_cats[1].asset_id);
+
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(coin);
+
+        assert!(matches!(
+            TransactionBuilder::new(puzzle_hash)
+                .issue_cat(puzzle_hash, 100)
+                .issue_cat(puzzle_hash, 200)
+                .select(&coins),
+            Err(DriverError::MissingIssuanceParent)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_spends_are_linked() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let constants = AggSigConstants::from(&TEST_CONSTANTS);
+        let (sk, pk, puzzle_hash, coin) = sim.new_p2(100)?;
+        let other_coin = sim.new_coin(puzzle_hash, 100);
+
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(coin);
+        coins.add_xch(other_coin);
+
+        let tx = TransactionBuilder::new(puzzle_hash)
+            .key(pk)
+            .send_xch(Bytes32::new([1; 32]), 150)
+            .build(ctx, &coins, &constants)?;
+
+        // Neither spend is valid without the other.
+        for index in 0..2 {
+            let mut coin_spends = tx.spend_bundle.coin_spends.clone();
+            coin_spends.remove(index);
+            assert!(sim.clone().spend_coins(coin_spends, &[sk.clone()]).is_err());
+        }
+
+        sim.spend_coins(tx.spend_bundle.coin_spends, &[sk])?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_conflicting_nft_actions() {
+        let launcher_id = Bytes32::new([1; 32]);
+
+        let result = TransactionBuilder::new(Bytes32::default())
+            .transfer_nft(launcher_id, Bytes32::default())
+            .lock_nft(launcher_id, Vec::new())
+            .select(&SpendableCoins::new());
+
+        assert!(matches!(
+            result,
+            Err(DriverError::ConflictingNftActions(id)) if id == launcher_id
+        ));
+    }
+
+    #[test]
+    fn test_missing_key() -> anyhow::Result<()> {
+        let mut sim = Simulator::new();
+        let ctx = &mut SpendContext::new();
+        let constants = AggSigConstants::from(&TEST_CONSTANTS);
+        let (_sk, _pk, puzzle_hash, coin) = sim.new_p2(100)?;
+
+        let mut coins = SpendableCoins::new();
+        coins.add_xch(coin);
+
+        let result = TransactionBuilder::new(puzzle_hash)
+            .send_xch(puzzle_hash, 50)
+            .build(ctx, &coins, &constants);
+
+        assert!(matches!(result, Err(DriverError::MissingKey)));
+
+        Ok(())
+    }
+}
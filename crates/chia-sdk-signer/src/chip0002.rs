@@ -0,0 +1,128 @@
+use chia_bls::{sign, verify, PublicKey, SecretKey, Signature};
+use chia_protocol::Bytes32;
+use chia_puzzles::standard::StandardArgs;
+use clvm_utils::{tree_hash_atom, tree_hash_pair};
+
+use crate::SignerError;
+
+/// The prefix that is paired with the message before it's hashed, so that signed messages can't be used as spend signatures.
+pub const CHIP_0002_SIGN_MESSAGE_PREFIX: &str = "Chia Signed Message";
+
+/// The signing mode that is reported to dapps for messages signed with [`sign_message`].
+pub const CHIP_0002_SIGNING_MODE: &str = "BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_AUG:CHIP-0002_";
+
+/// A message signature, as defined by [CHIP-0002](https://github.com/Chia-Network/chips/blob/main/CHIPs/chip-0002.md).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SignedMessage {
+    /// The synthetic public key of the p2 puzzle hash that the message was signed with.
+    pub public_key: PublicKey,
+    pub signature: Signature,
+}
+
+/// Calculates the tree hash of `("Chia Signed Message" . message)`, which is what actually gets signed.
+pub fn chip0002_message_hash(message: &[u8]) -> Bytes32 {
+    tree_hash_pair(
+        tree_hash_atom(CHIP_0002_SIGN_MESSAGE_PREFIX.as_bytes()),
+        tree_hash_atom(message),
+    )
+    .into()
+}
+
+/// Signs a message with the synthetic secret key of a standard p2 puzzle.
+pub fn sign_message(secret_key: &SecretKey, message: &[u8]) -> SignedMessage {
+    SignedMessage {
+        public_key: secret_key.public_key(),
+        signature: sign(secret_key, chip0002_message_hash(message)),
+    }
+}
+
+/// Signs a message as the given p2 puzzle hash, using whichever of the synthetic secret keys it was derived from.
+pub fn sign_message_for_puzzle_hash(
+    secret_keys: &[SecretKey],
+    puzzle_hash: Bytes32,
+    message: &[u8],
+) -> Result<SignedMessage, SignerError> {
+    let secret_key = secret_keys
+        .iter()
+        .find(|secret_key| {
+            StandardArgs::curry_tree_hash(secret_key.public_key()) == puzzle_hash.into()
+        })
+        .ok_or(SignerError::MissingKey(puzzle_hash))?;
+
+    Ok(sign_message(secret_key, message))
+}
+
+/// Verifies that the message was signed by the public key.
+pub fn verify_message(signed_message: &SignedMessage, message: &[u8]) -> bool {
+    verify(
+        &signed_message.signature,
+        &signed_message.public_key,
+        chip0002_message_hash(message),
+    )
+}
+
+/// Verifies that the message was signed by the public key, and that the key belongs to the p2 puzzle hash.
+pub fn verify_message_for_puzzle_hash(
+    signed_message: &SignedMessage,
+    puzzle_hash: Bytes32,
+    message: &[u8],
+) -> bool {
+    StandardArgs::curry_tree_hash(signed_message.public_key) == puzzle_hash.into()
+        && verify_message(signed_message, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use chia_bls::master_to_wallet_unhardened;
+    use chia_puzzles::DeriveSynthetic;
+    use hex_literal::hex;
+
+    use super::*;
+
+    #[test]
+    fn test_message_hash() {
+        assert_eq!(
+            chip0002_message_hash(b"Hello, world!"),
+            Bytes32::new(hex!(
+                "0b948deb133e18d1f75b527cbd4557b9c40931b8f8bac888ca5d95efcc011cab"
+            ))
+        );
+    }
+
+    #[test]
+    fn test_sign_message_for_puzzle_hash() -> Result<(), SignerError> {
+        let root_sk = SecretKey::from_bytes(&hex!(
+            "1b72f8ed55860ea5441729c8e36ce1d6f4c8be9bbcf658502a7a0169f55638b9"
+        ))
+        .unwrap();
+        let secret_keys: Vec<SecretKey> = (0..2)
+            .map(|index| master_to_wallet_unhardened(&root_sk, index).derive_synthetic())
+            .collect();
+        let puzzle_hash = StandardArgs::curry_tree_hash(secret_keys[1].public_key()).into();
+
+        let signed_message = sign_message_for_puzzle_hash(&secret_keys, puzzle_hash, b"Hello")?;
+
+        assert_eq!(signed_message.public_key, secret_keys[1].public_key());
+        assert!(verify_message(&signed_message, b"Hello"));
+        assert!(verify_message_for_puzzle_hash(
+            &signed_message,
+            puzzle_hash,
+            b"Hello"
+        ));
+
+        // The signature is only valid for the message and puzzle hash that were signed.
+        assert!(!verify_message(&signed_message, b"Goodbye"));
+        assert!(!verify_message_for_puzzle_hash(
+            &signed_message,
+            Bytes32::default(),
+            b"Hello"
+        ));
+
+        assert!(matches!(
+            sign_message_for_puzzle_hash(&secret_keys, Bytes32::default(), b"Hello"),
+            Err(SignerError::MissingKey(..))
+        ));
+
+        Ok(())
+    }
+}
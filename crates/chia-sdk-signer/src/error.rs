@@ -1,3 +1,4 @@
+use chia_protocol::Bytes32;
 use clvm_traits::{FromClvmError, ToClvmError};
 use clvmr::reduction::EvalErr;
 use thiserror::Error;
@@ -15,4 +16,7 @@ pub enum SignerError {
 
     #[error("Infinity public key")]
     InfinityPublicKey,
+
+    #[error("Missing key for puzzle hash {0}")]
+    MissingKey(Bytes32),
 }
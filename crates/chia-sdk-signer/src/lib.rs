@@ -1,7 +1,9 @@
 mod agg_sig_constants;
+mod chip0002;
 mod error;
 mod required_signature;
 
 pub use agg_sig_constants::*;
+pub use chip0002::*;
 pub use error::*;
 pub use required_signature::*;
@@ -6,6 +6,12 @@ use rand::{Rng, SeedableRng};
 use rand_chacha::ChaCha8Rng;
 use thiserror::Error;
 
+mod coin_selector;
+mod selection_strategy;
+
+pub use coin_selector::*;
+pub use selection_strategy::*;
+
 /// An error that occurs when selecting coins.
 #[derive(Debug, Clone, Copy, Error, PartialEq, Eq)]
 pub enum CoinSelectionError {
@@ -20,15 +26,42 @@ pub enum CoinSelectionError {
     /// The selected coins exceeded the maximum.
     #[error("exceeded max coins")]
     ExceededMaxCoins,
+
+    /// The cost of spending the selected coins exceeded the budget.
+    #[error("exceeded cost budget")]
+    ExceededCostBudget,
+
+    /// The change would have been less than the minimum, but more than zero.
+    #[error("change below minimum")]
+    ChangeBelowMinimum,
+
+    /// There was no combination of coins that exactly matched the amount.
+    #[error("no exact match")]
+    NoExactMatch,
 }
 
 /// Uses the knapsack algorithm to select coins.
+///
+/// This is equivalent to the [`Knapsack`] strategy with its default seed and a maximum of 500 coins.
+/// Use [`select_coins_with`] for other strategies and constraints.
 pub fn select_coins(
-    mut spendable_coins: Vec<Coin>,
+    spendable_coins: Vec<Coin>,
     amount: u128,
 ) -> Result<Vec<Coin>, CoinSelectionError> {
-    let max_coins = 500;
+    knapsack_select_coins(
+        &mut ChaCha8Rng::seed_from_u64(0),
+        spendable_coins,
+        amount,
+        500,
+    )
+}
 
+fn knapsack_select_coins(
+    rng: &mut impl Rng,
+    mut spendable_coins: Vec<Coin>,
+    amount: u128,
+    max_coins: usize,
+) -> Result<Vec<Coin>, CoinSelectionError> {
     // You cannot spend no coins.
     if spendable_coins.is_empty() {
         return Err(CoinSelectionError::NoSpendableCoins);
@@ -77,13 +110,9 @@ pub fn select_coins(
 
     // Apply the knapsack algorithm otherwise.
     if smaller_sum > amount {
-        if let Some(result) = knapsack_coin_algorithm(
-            &mut ChaCha8Rng::seed_from_u64(0),
-            &spendable_coins,
-            amount,
-            u128::MAX,
-            max_coins,
-        ) {
+        if let Some(result) =
+            knapsack_coin_algorithm(rng, &spendable_coins, amount, u128::MAX, max_coins)
+        {
             return Ok(result.into_iter().collect());
         }
 
@@ -0,0 +1,321 @@
+use std::collections::HashSet;
+
+use chia_protocol::{Bytes32, Coin};
+use thiserror::Error;
+
+use super::{CoinSelectionError, SelectionStrategy};
+
+/// Limits that are applied to every coin selection, regardless of the strategy.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SelectionConstraints {
+    /// The maximum number of coins that can be selected.
+    pub max_coins: usize,
+    /// The ids of coins that must not be selected, for example because they are locked in a pending transaction.
+    pub excluded_coins: HashSet<Bytes32>,
+    /// If there is change, it must be at least this amount. This prevents the creation of dust.
+    pub min_change: u64,
+    /// The maximum cost that the selected coins can add to the transaction.
+    pub max_cost: Option<u64>,
+    /// The estimated cost of spending each coin, which is used to enforce the cost budget.
+    pub cost_per_coin: u64,
+}
+
+impl Default for SelectionConstraints {
+    fn default() -> Self {
+        Self {
+            max_coins: 500,
+            excluded_coins: HashSet::new(),
+            min_change: 0,
+            max_cost: None,
+            cost_per_coin: 0,
+        }
+    }
+}
+
+impl SelectionConstraints {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    #[must_use]
+    pub fn with_max_coins(mut self, max_coins: usize) -> Self {
+        self.max_coins = max_coins;
+        self
+    }
+
+    #[must_use]
+    pub fn with_excluded_coins(mut self, coin_ids: impl IntoIterator<Item = Bytes32>) -> Self {
+        self.excluded_coins.extend(coin_ids);
+        self
+    }
+
+    #[must_use]
+    pub fn with_min_change(mut self, min_change: u64) -> Self {
+        self.min_change = min_change;
+        self
+    }
+
+    #[must_use]
+    pub fn with_cost_budget(mut self, max_cost: u64, cost_per_coin: u64) -> Self {
+        self.max_cost = Some(max_cost);
+        self.cost_per_coin = cost_per_coin;
+        self
+    }
+
+    /// The maximum number of coins that can be selected, taking the cost budget into account.
+    pub fn coin_limit(&self) -> usize {
+        match self.max_cost {
+            Some(max_cost) if self.cost_per_coin > 0 => {
+                let limit = usize::try_from(max_cost / self.cost_per_coin).unwrap_or(usize::MAX);
+                self.max_coins.min(limit)
+            }
+            _ => self.max_coins,
+        }
+    }
+}
+
+/// The coins chosen by [`select_coins_with`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CoinSelection {
+    pub coins: Vec<Coin>,
+    /// The amount left over after the requested amount, which should be sent back as change.
+    pub change: u128,
+}
+
+/// A report of why [`select_coins_with`] failed, to help explain the failure to the user.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Error)]
+#[error("{strategy} coin selection failed: {reason}")]
+pub struct SelectionFailure {
+    /// The reason that selection failed.
+    pub reason: CoinSelectionError,
+    /// The name of the strategy that was used.
+    pub strategy: &'static str,
+    /// The amount that was requested.
+    pub amount: u128,
+    /// The number of coins that could be selected from, after exclusions.
+    pub spendable_coins: usize,
+    /// The number of coins that were excluded by the constraints.
+    pub excluded_coins: usize,
+    /// The total amount of the coins that could be selected from.
+    pub spendable_amount: u128,
+    /// The maximum number of coins that could be selected, taking the cost budget into account.
+    pub coin_limit: usize,
+}
+
+/// Selects coins with the given strategy, and checks that the result satisfies the constraints.
+///
+/// If the change would be less than the minimum, the selection is retried with the minimum change
+/// added to the amount, so that the change is large enough.
+pub fn select_coins_with<S>(
+    strategy: &mut S,
+    spendable_coins: &[Coin],
+    amount: u128,
+    constraints: &SelectionConstraints,
+) -> Result<CoinSelection, SelectionFailure>
+where
+    S: SelectionStrategy + ?Sized,
+{
+    let coins: Vec<Coin> = spendable_coins
+        .iter()
+        .filter(|coin| !constraints.excluded_coins.contains(&coin.coin_id()))
+        .copied()
+        .collect();
+
+    let coin_limit = constraints.coin_limit();
+    let strategy_name = strategy.name();
+
+    let failure = |reason| SelectionFailure {
+        reason,
+        strategy: strategy_name,
+        amount,
+        spendable_coins: coins.len(),
+        excluded_coins: spendable_coins.len() - coins.len(),
+        spendable_amount: coins.iter().map(|coin| u128::from(coin.amount)).sum(),
+        coin_limit,
+    };
+
+    let selection =
+        select_checked(strategy, &coins, amount, coin_limit, constraints).map_err(failure)?;
+
+    let min_change = u128::from(constraints.min_change);
+
+    if selection.change == 0 || selection.change >= min_change {
+        return Ok(selection);
+    }
+
+    let mut selection = select_checked(
+        strategy,
+        &coins,
+        amount + min_change,
+        coin_limit,
+        constraints,
+    )
+    .map_err(|reason| match reason {
+        CoinSelectionError::InsufficientBalance(..) => {
+            failure(CoinSelectionError::ChangeBelowMinimum)
+        }
+        reason => failure(reason),
+    })?;
+
+    selection.change += min_change;
+
+    Ok(selection)
+}
+
+fn select_checked<S>(
+    strategy: &mut S,
+    coins: &[Coin],
+    amount: u128,
+    coin_limit: usize,
+    constraints: &SelectionConstraints,
+) -> Result<CoinSelection, CoinSelectionError>
+where
+    S: SelectionStrategy + ?Sized,
+{
+    // If the cost budget allows fewer coins than the coin cap, it's the budget that was exceeded.
+    let selected = strategy
+        .select(coins, amount, coin_limit)
+        .map_err(|error| match error {
+            CoinSelectionError::ExceededMaxCoins if coin_limit < constraints.max_coins => {
+                CoinSelectionError::ExceededCostBudget
+            }
+            error => error,
+        })?;
+
+    if let Some(max_cost) = constraints.max_cost {
+        let cost = u128::from(constraints.cost_per_coin) * selected.len() as u128;
+
+        if cost > u128::from(max_cost) {
+            return Err(CoinSelectionError::ExceededCostBudget);
+        }
+    }
+
+    if selected.len() > constraints.max_coins {
+        return Err(CoinSelectionError::ExceededMaxCoins);
+    }
+
+    let total: u128 = selected.iter().map(|coin| u128::from(coin.amount)).sum();
+
+    Ok(CoinSelection {
+        coins: selected,
+        change: total - amount,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::{BranchAndBound, LargestFirst, Privacy, SmallestFirst};
+
+    fn coins(amounts: &[u64]) -> Vec<Coin> {
+        amounts
+            .iter()
+            .enumerate()
+            .map(|(index, &amount)| {
+                Coin::new(
+                    Bytes32::new([u8::try_from(index).unwrap(); 32]),
+                    Bytes32::default(),
+                    amount,
+                )
+            })
+            .collect()
+    }
+
+    fn amounts(selection: &CoinSelection) -> Vec<u64> {
+        selection.coins.iter().map(|coin| coin.amount).collect()
+    }
+
+    #[test]
+    fn test_branch_and_bound() {
+        let coins = coins(&[1, 5, 10, 25, 50]);
+        let constraints = SelectionConstraints::new();
+
+        let selection =
+            select_coins_with(&mut BranchAndBound::default(), &coins, 36, &constraints).unwrap();
+        assert_eq!(amounts(&selection), [25, 10, 1]);
+        assert_eq!(selection.change, 0);
+
+        let failure = select_coins_with(&mut BranchAndBound::default(), &coins, 45, &constraints)
+            .unwrap_err();
+        assert_eq!(failure.reason, CoinSelectionError::NoExactMatch);
+        assert_eq!(failure.strategy, "branch and bound");
+    }
+
+    #[test]
+    fn test_largest_and_smallest_first() {
+        let coins = coins(&[1, 2, 3, 100, 200]);
+        let constraints = SelectionConstraints::new();
+
+        let selection = select_coins_with(&mut LargestFirst, &coins, 150, &constraints).unwrap();
+        assert_eq!(amounts(&selection), [200]);
+        assert_eq!(selection.change, 50);
+
+        let selection = select_coins_with(&mut SmallestFirst, &coins, 5, &constraints).unwrap();
+        assert_eq!(amounts(&selection), [1, 2, 3]);
+
+        // The three smallest coins can't reach the amount, so the window slides up.
+        let constraints = constraints.with_max_coins(3);
+        let selection = select_coins_with(&mut SmallestFirst, &coins, 150, &constraints).unwrap();
+        assert_eq!(amounts(&selection), [3, 100, 200]);
+    }
+
+    #[test]
+    fn test_privacy_prefers_single_coin() {
+        let coins = coins(&[10, 20, 30, 40]);
+        let constraints = SelectionConstraints::new();
+
+        let selection = select_coins_with(&mut Privacy::new(0), &coins, 25, &constraints).unwrap();
+        assert_eq!(amounts(&selection), [30]);
+
+        let selection = select_coins_with(&mut Privacy::new(0), &coins, 90, &constraints).unwrap();
+        let total: u64 = amounts(&selection).iter().sum();
+        assert!(total >= 90);
+    }
+
+    #[test]
+    fn test_excluded_coins() {
+        let coins = coins(&[100, 200]);
+        let constraints = SelectionConstraints::new().with_excluded_coins([coins[1].coin_id()]);
+
+        let failure = select_coins_with(&mut LargestFirst, &coins, 150, &constraints).unwrap_err();
+        assert_eq!(failure.reason, CoinSelectionError::InsufficientBalance(100));
+        assert_eq!(failure.spendable_coins, 1);
+        assert_eq!(failure.excluded_coins, 1);
+        assert_eq!(failure.spendable_amount, 100);
+    }
+
+    #[test]
+    fn test_min_change() {
+        let coins = coins(&[100, 200]);
+        let constraints = SelectionConstraints::new().with_min_change(50);
+
+        // Spending only the 200 coin would leave 10 change, so both coins are spent instead.
+        let selection = select_coins_with(&mut LargestFirst, &coins, 190, &constraints).unwrap();
+        assert_eq!(amounts(&selection), [200, 100]);
+        assert_eq!(selection.change, 110);
+
+        let failure = select_coins_with(&mut LargestFirst, &coins, 290, &constraints).unwrap_err();
+        assert_eq!(failure.reason, CoinSelectionError::ChangeBelowMinimum);
+    }
+
+    #[test]
+    fn test_cost_budget() {
+        let coins = coins(&[10, 10, 10, 10]);
+        let constraints = SelectionConstraints::new().with_cost_budget(25, 10);
+        assert_eq!(constraints.coin_limit(), 2);
+
+        let selection = select_coins_with(&mut SmallestFirst, &coins, 20, &constraints).unwrap();
+        assert_eq!(selection.coins.len(), 2);
+
+        let failure = select_coins_with(&mut LargestFirst, &coins, 30, &constraints).unwrap_err();
+        assert_eq!(failure.reason, CoinSelectionError::ExceededCostBudget);
+        assert_eq!(failure.coin_limit, 2);
+
+        // When the coin cap is tighter than the budget, the cap is what was exceeded.
+        let constraints = constraints.with_max_coins(1);
+        let failure = select_coins_with(&mut LargestFirst, &coins, 30, &constraints).unwrap_err();
+        assert_eq!(failure.reason, CoinSelectionError::ExceededMaxCoins);
+        assert_eq!(failure.coin_limit, 1);
+    }
+}
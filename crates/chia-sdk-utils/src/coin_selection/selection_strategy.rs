@@ -0,0 +1,313 @@
+use chia_protocol::Coin;
+use rand::{seq::SliceRandom, SeedableRng};
+use rand_chacha::ChaCha8Rng;
+
+use super::{knapsack_select_coins, CoinSelectionError};
+
+/// An algorithm for choosing which coins to spend in order to reach an amount.
+pub trait SelectionStrategy {
+    /// The name of the strategy, which is included in the report if selection fails.
+    fn name(&self) -> &'static str;
+
+    /// Selects at least one coin, whose sum is at least the amount, without using more than `max_coins`.
+    fn select(
+        &mut self,
+        spendable_coins: &[Coin],
+        amount: u128,
+        max_coins: usize,
+    ) -> Result<Vec<Coin>, CoinSelectionError>;
+}
+
+/// The knapsack algorithm used by [`select_coins`](crate::select_coins), with a configurable random seed.
+#[derive(Debug, Clone)]
+pub struct Knapsack {
+    rng: ChaCha8Rng,
+}
+
+impl Knapsack {
+    pub fn new(seed: u64) -> Self {
+        Self::from_rng(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    pub fn from_rng(rng: ChaCha8Rng) -> Self {
+        Self { rng }
+    }
+}
+
+impl Default for Knapsack {
+    fn default() -> Self {
+        Self::new(0)
+    }
+}
+
+impl SelectionStrategy for Knapsack {
+    fn name(&self) -> &'static str {
+        "knapsack"
+    }
+
+    fn select(
+        &mut self,
+        spendable_coins: &[Coin],
+        amount: u128,
+        max_coins: usize,
+    ) -> Result<Vec<Coin>, CoinSelectionError> {
+        let selected =
+            knapsack_select_coins(&mut self.rng, spendable_coins.to_vec(), amount, max_coins)?;
+
+        if selected.len() > max_coins {
+            return Err(CoinSelectionError::ExceededMaxCoins);
+        }
+
+        Ok(selected)
+    }
+}
+
+/// Searches for a combination of coins that exactly matches the amount, so that no change is needed.
+/// This is useful for reducing the size of transactions, since it doesn't create a change coin.
+#[derive(Debug, Clone, Copy)]
+pub struct BranchAndBound {
+    max_tries: usize,
+}
+
+impl BranchAndBound {
+    pub fn new(max_tries: usize) -> Self {
+        Self { max_tries }
+    }
+}
+
+impl Default for BranchAndBound {
+    fn default() -> Self {
+        Self::new(100_000)
+    }
+}
+
+impl SelectionStrategy for BranchAndBound {
+    fn name(&self) -> &'static str {
+        "branch and bound"
+    }
+
+    fn select(
+        &mut self,
+        spendable_coins: &[Coin],
+        amount: u128,
+        max_coins: usize,
+    ) -> Result<Vec<Coin>, CoinSelectionError> {
+        let coins = sorted_coins(spendable_coins, amount)?;
+        let coins: Vec<Coin> = coins.into_iter().rev().collect();
+
+        // The sum of every coin from each index onward, used to prune branches that can't reach the amount.
+        let mut remaining = vec![0; coins.len() + 1];
+        for (index, coin) in coins.iter().enumerate().rev() {
+            remaining[index] = remaining[index + 1] + u128::from(coin.amount);
+        }
+
+        let mut search = Search {
+            coins: &coins,
+            remaining: &remaining,
+            amount,
+            max_coins,
+            tries: self.max_tries,
+            selected: Vec::new(),
+        };
+
+        if amount > 0 && search.find(0, 0) {
+            return Ok(search.selected);
+        }
+
+        Err(CoinSelectionError::NoExactMatch)
+    }
+}
+
+struct Search<'a> {
+    coins: &'a [Coin],
+    remaining: &'a [u128],
+    amount: u128,
+    max_coins: usize,
+    tries: usize,
+    selected: Vec<Coin>,
+}
+
+impl Search<'_> {
+    fn find(&mut self, index: usize, sum: u128) -> bool {
+        if sum == self.amount {
+            return true;
+        }
+
+        if self.tries == 0
+            || index == self.coins.len()
+            || self.selected.len() == self.max_coins
+            || sum + self.remaining[index] < self.amount
+        {
+            return false;
+        }
+
+        self.tries -= 1;
+
+        let coin = self.coins[index];
+        let next_sum = sum + u128::from(coin.amount);
+
+        // Include the coin first, since the coins are sorted from largest to smallest.
+        if next_sum <= self.amount {
+            self.selected.push(coin);
+
+            if self.find(index + 1, next_sum) {
+                return true;
+            }
+
+            self.selected.pop();
+        }
+
+        self.find(index + 1, sum)
+    }
+}
+
+/// Selects the largest coins first, which minimizes the number of coins spent.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct LargestFirst;
+
+impl SelectionStrategy for LargestFirst {
+    fn name(&self) -> &'static str {
+        "largest first"
+    }
+
+    fn select(
+        &mut self,
+        spendable_coins: &[Coin],
+        amount: u128,
+        max_coins: usize,
+    ) -> Result<Vec<Coin>, CoinSelectionError> {
+        let coins = sorted_coins(spendable_coins, amount)?;
+        accumulate(coins.into_iter().rev(), amount, max_coins)
+    }
+}
+
+/// Selects the smallest coins first, which consolidates dust as part of regular spends.
+///
+/// If the smallest coins would exceed the maximum, the smallest coins that fit within it are used instead.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct SmallestFirst;
+
+impl SelectionStrategy for SmallestFirst {
+    fn name(&self) -> &'static str {
+        "smallest first"
+    }
+
+    fn select(
+        &mut self,
+        spendable_coins: &[Coin],
+        amount: u128,
+        max_coins: usize,
+    ) -> Result<Vec<Coin>, CoinSelectionError> {
+        let coins = sorted_coins(spendable_coins, amount)?;
+        let window = max_coins.min(coins.len());
+
+        if window == 0 {
+            return Err(CoinSelectionError::ExceededMaxCoins);
+        }
+
+        // Slide a window of the maximum size over the coins, until it contains enough to reach the amount.
+        let mut sum: u128 = coins[..window]
+            .iter()
+            .map(|coin| u128::from(coin.amount))
+            .sum();
+        let mut start = 0;
+
+        while sum < amount {
+            if start + window == coins.len() {
+                return Err(CoinSelectionError::ExceededMaxCoins);
+            }
+
+            sum -= u128::from(coins[start].amount);
+            sum += u128::from(coins[start + window].amount);
+            start += 1;
+        }
+
+        accumulate(coins[start..].iter().copied(), amount, max_coins)
+    }
+}
+
+/// Avoids linking coins together when possible, by preferring a single coin that covers the amount.
+/// Otherwise, coins are chosen at random rather than by amount, so the selection reveals less about the wallet.
+#[derive(Debug, Clone)]
+pub struct Privacy {
+    rng: ChaCha8Rng,
+}
+
+impl Privacy {
+    pub fn new(seed: u64) -> Self {
+        Self::from_rng(ChaCha8Rng::seed_from_u64(seed))
+    }
+
+    pub fn from_rng(rng: ChaCha8Rng) -> Self {
+        Self { rng }
+    }
+}
+
+impl SelectionStrategy for Privacy {
+    fn name(&self) -> &'static str {
+        "privacy"
+    }
+
+    fn select(
+        &mut self,
+        spendable_coins: &[Coin],
+        amount: u128,
+        max_coins: usize,
+    ) -> Result<Vec<Coin>, CoinSelectionError> {
+        let mut coins = sorted_coins(spendable_coins, amount)?;
+
+        if let Some(coin) = coins.iter().find(|coin| u128::from(coin.amount) >= amount) {
+            return Ok(vec![*coin]);
+        }
+
+        coins.shuffle(&mut self.rng);
+
+        accumulate(coins.iter().copied(), amount, max_coins)
+            .or_else(|_| LargestFirst.select(&coins, amount, max_coins))
+    }
+}
+
+/// Checks that the coins can reach the amount, and sorts them from smallest to largest.
+fn sorted_coins(spendable_coins: &[Coin], amount: u128) -> Result<Vec<Coin>, CoinSelectionError> {
+    if spendable_coins.is_empty() {
+        return Err(CoinSelectionError::NoSpendableCoins);
+    }
+
+    let spendable_amount: u128 = spendable_coins
+        .iter()
+        .map(|coin| u128::from(coin.amount))
+        .sum();
+
+    if spendable_amount < amount {
+        return Err(CoinSelectionError::InsufficientBalance(spendable_amount));
+    }
+
+    let mut coins = spendable_coins.to_vec();
+    coins.sort_by_key(|coin| (coin.amount, coin.coin_id()));
+    Ok(coins)
+}
+
+/// Selects coins in order until the amount is reached.
+fn accumulate(
+    coins: impl Iterator<Item = Coin>,
+    amount: u128,
+    max_coins: usize,
+) -> Result<Vec<Coin>, CoinSelectionError> {
+    let mut selected = Vec::new();
+    let mut sum = 0;
+
+    for coin in coins {
+        if selected.len() == max_coins {
+            return Err(CoinSelectionError::ExceededMaxCoins);
+        }
+
+        selected.push(coin);
+        sum += u128::from(coin.amount);
+
+        if sum >= amount {
+            return Ok(selected);
+        }
+    }
+
+    Err(CoinSelectionError::InsufficientBalance(sum))
+}
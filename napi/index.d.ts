@@ -174,6 +174,8 @@ export interface NftInfo {
   royaltyPuzzleHash: Uint8Array
   royaltyTenThousandths: number
   p2PuzzleHash: Uint8Array
+  /** The serialized transfer program, if it isn't the standard royalty transfer program. */
+  transferProgram?: Uint8Array
 }
 export interface NftMetadata {
   editionNumber: bigint
@@ -2,7 +2,7 @@ use chia::{
     bls,
     clvm_traits::{clvm_quote, ClvmEncoder, FromClvm, ToClvm},
     clvm_utils::{self, CurriedProgram, TreeHash},
-    protocol::{self, Bytes32},
+    protocol::Bytes32,
     puzzles::nft::{self, NFT_METADATA_UPDATER_PUZZLE_HASH},
 };
 use chia_wallet_sdk::{self as sdk, HashedPtr, SpendContext};
@@ -258,18 +258,19 @@ impl ClvmAllocator {
                         royalty_ten_thousandths: nft_mint.royalty_ten_thousandths,
                         metadata_updater_puzzle_hash: NFT_METADATA_UPDATER_PUZZLE_HASH.into(),
                         owner: None,
+                        transfer_program: sdk::NftTransferProgram::Royalty,
                     },
                 )
                 .map_err(|error| Error::from_reason(error.to_string()))?;
 
-            let serialized_metadata = self
+            let metadata = self
                 .0
-                .serialize(&nft.info.metadata)
+                .alloc(&nft.info.metadata)
                 .map_err(|error| Error::from_reason(error.to_string()))?;
+            let metadata = HashedPtr::from_ptr(&self.0.allocator, metadata);
 
-            result
-                .nfts
-                .push(nft.with_metadata(serialized_metadata).into_js()?);
+            let nft = self.nft_into_js(&nft.with_metadata(metadata))?;
+            result.nfts.push(nft);
 
             for condition in conditions {
                 let condition = condition
@@ -303,14 +304,14 @@ impl ClvmAllocator {
         let puzzle = sdk::Puzzle::parse(&self.0.allocator, puzzle.ptr);
 
         let Some((nft_info, inner_puzzle)) =
-            sdk::NftInfo::<protocol::Program>::parse(&self.0.allocator, puzzle)
+            sdk::NftInfo::<HashedPtr>::parse(&self.0.allocator, puzzle)
                 .map_err(|error| Error::from_reason(error.to_string()))?
         else {
             return Ok(None);
         };
 
         Ok(Some(ParsedNft {
-            info: nft_info.into_js()?,
+            info: self.nft_info_into_js(&nft_info)?,
             inner_puzzle: Program::new(this, inner_puzzle.ptr()).into_instance(env)?,
         }))
     }
@@ -335,18 +336,13 @@ impl ClvmAllocator {
             return Ok(None);
         };
 
-        let serialized_metadata = self
-            .0
-            .serialize(&nft.info.metadata)
-            .map_err(|error| Error::from_reason(error.to_string()))?;
-
-        Ok(Some(nft.with_metadata(serialized_metadata).into_js()?))
+        Ok(Some(self.nft_into_js(&nft)?))
     }
 
     #[napi]
     pub fn spend_nft(&mut self, nft: Nft, inner_spend: Spend) -> Result<Vec<CoinSpend>> {
+        let nft = self.nft_from_js(nft)?;
         let ctx = &mut self.0;
-        let nft = sdk::Nft::<protocol::Program>::from_js(nft)?;
 
         nft.spend(
             ctx,
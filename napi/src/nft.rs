@@ -1,11 +1,11 @@
-use chia::protocol;
 use chia::puzzles::nft;
-use chia_wallet_sdk as sdk;
+use chia_wallet_sdk::{self as sdk, HashedPtr, Layer};
+use clvmr::serde::{node_from_bytes, node_to_bytes};
 use napi::bindgen_prelude::*;
 
 use crate::{
     traits::{FromJs, IntoJs, IntoRust},
-    Coin, CoinSpend, LineageProof, Program,
+    ClvmAllocator, Coin, CoinSpend, LineageProof, Program,
 };
 
 #[napi(object)]
@@ -15,26 +15,6 @@ pub struct Nft {
     pub info: NftInfo,
 }
 
-impl IntoJs<Nft> for sdk::Nft<protocol::Program> {
-    fn into_js(self) -> Result<Nft> {
-        Ok(Nft {
-            coin: self.coin.into_js()?,
-            lineage_proof: self.proof.into_js()?,
-            info: self.info.into_js()?,
-        })
-    }
-}
-
-impl FromJs<Nft> for sdk::Nft<protocol::Program> {
-    fn from_js(nft: Nft) -> Result<Self> {
-        Ok(sdk::Nft {
-            coin: nft.coin.into_rust()?,
-            proof: nft.lineage_proof.into_rust()?,
-            info: nft.info.into_rust()?,
-        })
-    }
-}
-
 #[napi(object)]
 pub struct NftInfo {
     pub launcher_id: Uint8Array,
@@ -44,34 +24,80 @@ pub struct NftInfo {
     pub royalty_puzzle_hash: Uint8Array,
     pub royalty_ten_thousandths: u16,
     pub p2_puzzle_hash: Uint8Array,
+    /// The serialized transfer program, if it isn't the standard royalty transfer program.
+    pub transfer_program: Option<Uint8Array>,
 }
 
-impl IntoJs<NftInfo> for sdk::NftInfo<protocol::Program> {
-    fn into_js(self) -> Result<NftInfo> {
-        Ok(NftInfo {
-            launcher_id: self.launcher_id.into_js()?,
-            metadata: self.metadata.into_js()?,
-            metadata_updater_puzzle_hash: self.metadata_updater_puzzle_hash.into_js()?,
-            current_owner: self.current_owner.map(IntoJs::into_js).transpose()?,
-            royalty_puzzle_hash: self.royalty_puzzle_hash.into_js()?,
-            royalty_ten_thousandths: self.royalty_ten_thousandths,
-            p2_puzzle_hash: self.p2_puzzle_hash.into_js()?,
+impl ClvmAllocator {
+    /// Serializes the metadata and transfer program of the NFT out of the allocator.
+    pub(crate) fn nft_into_js(&mut self, nft: &sdk::Nft<HashedPtr>) -> Result<Nft> {
+        Ok(Nft {
+            coin: nft.coin.into_js()?,
+            lineage_proof: nft.proof.into_js()?,
+            info: self.nft_info_into_js(&nft.info)?,
         })
     }
-}
 
-impl FromJs<NftInfo> for sdk::NftInfo<protocol::Program> {
-    fn from_js(info: NftInfo) -> Result<Self> {
-        Ok(sdk::NftInfo {
-            launcher_id: info.launcher_id.into_rust()?,
-            metadata: info.metadata.into_rust()?,
-            metadata_updater_puzzle_hash: info.metadata_updater_puzzle_hash.into_rust()?,
-            current_owner: info.current_owner.map(IntoRust::into_rust).transpose()?,
-            royalty_puzzle_hash: info.royalty_puzzle_hash.into_rust()?,
+    /// Deserializes the metadata and transfer program of the NFT into the allocator.
+    pub(crate) fn nft_from_js(&mut self, nft: Nft) -> Result<sdk::Nft<HashedPtr>> {
+        Ok(sdk::Nft {
+            coin: nft.coin.into_rust()?,
+            proof: nft.lineage_proof.into_rust()?,
+            info: self.nft_info_from_js(nft.info)?,
+        })
+    }
+
+    pub(crate) fn nft_info_into_js(&mut self, info: &sdk::NftInfo<HashedPtr>) -> Result<NftInfo> {
+        let transfer_program = if info.transfer_program == sdk::NftTransferProgram::Royalty {
+            None
+        } else {
+            let ptr = info
+                .transfer_layer()
+                .construct_puzzle(&mut self.0)
+                .map_err(|error| Error::from_reason(error.to_string()))?;
+            Some(node_to_bytes(&self.0.allocator, ptr)?.into_js()?)
+        };
+
+        Ok(NftInfo {
+            launcher_id: info.launcher_id.into_js()?,
+            metadata: node_to_bytes(&self.0.allocator, info.metadata.ptr())?.into_js()?,
+            metadata_updater_puzzle_hash: info.metadata_updater_puzzle_hash.into_js()?,
+            current_owner: info.current_owner.map(IntoJs::into_js).transpose()?,
+            royalty_puzzle_hash: info.royalty_puzzle_hash.into_js()?,
             royalty_ten_thousandths: info.royalty_ten_thousandths,
-            p2_puzzle_hash: info.p2_puzzle_hash.into_rust()?,
+            p2_puzzle_hash: info.p2_puzzle_hash.into_js()?,
+            transfer_program,
         })
     }
+
+    pub(crate) fn nft_info_from_js(&mut self, info: NftInfo) -> Result<sdk::NftInfo<HashedPtr>> {
+        let metadata = node_from_bytes(&mut self.0.allocator, &info.metadata)?;
+
+        let nft_info = sdk::NftInfo::new(
+            info.launcher_id.into_rust()?,
+            HashedPtr::from_ptr(&self.0.allocator, metadata),
+            info.metadata_updater_puzzle_hash.into_rust()?,
+            info.current_owner.map(IntoRust::into_rust).transpose()?,
+            info.royalty_puzzle_hash.into_rust()?,
+            info.royalty_ten_thousandths,
+            info.p2_puzzle_hash.into_rust()?,
+        );
+
+        let Some(transfer_program) = info.transfer_program else {
+            return Ok(nft_info);
+        };
+
+        let ptr = node_from_bytes(&mut self.0.allocator, &transfer_program)?;
+        let puzzle = sdk::Puzzle::parse(&self.0.allocator, ptr);
+
+        let Some(transfer_layer) = sdk::NftTransferLayer::parse_puzzle(&self.0.allocator, puzzle)
+            .map_err(|error| Error::from_reason(error.to_string()))?
+        else {
+            return Err(Error::from_reason("unsupported NFT transfer program"));
+        };
+
+        Ok(nft_info.with_transfer_program(sdk::NftTransferProgram::from_layer(transfer_layer)))
+    }
 }
 
 #[napi(object)]